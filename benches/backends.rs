@@ -0,0 +1,89 @@
+//! Compares the backends on representative workloads.
+
+mod workloads;
+
+use std::path::PathBuf;
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use tempfile::TempDir;
+use unifs::{MemoryFs, PhysicalFs, UniFs};
+use workloads::Size;
+
+/// A filesystem with an empty directory to run a workload in.
+struct Backend<F: UniFs> {
+    fs: F,
+    root: PathBuf,
+    /// Keeps the temporary directory of a [`PhysicalFs`] alive.
+    _dir: Option<TempDir>,
+}
+
+fn memory_fs() -> Backend<MemoryFs> {
+    Backend {
+        fs: MemoryFs::default(),
+        root: PathBuf::from("/bench"),
+        _dir: None,
+    }
+}
+
+fn physical_fs() -> Backend<PhysicalFs> {
+    let dir = tempfile::tempdir().expect("failed to create a temporary directory");
+    Backend {
+        fs: PhysicalFs,
+        root: dir.path().join("bench"),
+        _dir: Some(dir),
+    }
+}
+
+fn bench_backend<F: UniFs>(c: &mut Criterion, name: &str, new: fn() -> Backend<F>) {
+    let size = &Size::BENCH;
+
+    c.bench_function(&format!("{name}/bulk_create"), |b| {
+        b.iter_batched(
+            new,
+            |backend| {
+                workloads::bulk_create(&backend.fs, &backend.root, size).unwrap();
+                backend
+            },
+            BatchSize::PerIteration,
+        )
+    });
+
+    let backend = new();
+    workloads::bulk_create(&backend.fs, &backend.root, size).unwrap();
+    c.bench_function(&format!("{name}/bulk_read"), |b| {
+        b.iter(|| workloads::bulk_read(&backend.fs, &backend.root, size).unwrap())
+    });
+
+    let backend = new();
+    workloads::deep_tree(&backend.fs, &backend.root, size).unwrap();
+    c.bench_function(&format!("{name}/walk_dir"), |b| {
+        b.iter(|| workloads::walk(&backend.fs, &backend.root).unwrap())
+    });
+
+    c.bench_function(&format!("{name}/copy"), |b| {
+        b.iter_batched(
+            || {
+                let backend = new();
+                workloads::bulk_create(&backend.fs, &backend.root.join("src"), size).unwrap();
+                backend
+            },
+            |backend| {
+                let root = &backend.root;
+                workloads::copy(&backend.fs, &root.join("src"), &root.join("dst")).unwrap();
+                backend
+            },
+            BatchSize::PerIteration,
+        )
+    });
+}
+
+fn memory(c: &mut Criterion) {
+    bench_backend(c, "MemoryFs", memory_fs);
+}
+
+fn physical(c: &mut Criterion) {
+    bench_backend(c, "PhysicalFs", physical_fs);
+}
+
+criterion_group!(benches, memory, physical);
+criterion_main!(benches);
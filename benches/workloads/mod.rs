@@ -0,0 +1,80 @@
+//! Workloads shared by the benchmarks and the test running them with a small size.
+
+use std::path::Path;
+
+use unifs::{UniFs, UniFsExt as _};
+
+/// The dimensions of the files and trees a workload operates on.
+pub struct Size {
+    /// The number of files created by [`bulk_create`].
+    pub files: usize,
+    /// The length of each file in bytes.
+    pub file_len: usize,
+    /// The number of nested directories created by [`deep_tree`].
+    pub depth: usize,
+    /// The number of files in each directory created by [`deep_tree`].
+    pub width: usize,
+}
+
+impl Size {
+    /// The size used by the benchmarks.
+    pub const BENCH: Size = Size {
+        files: 1000,
+        file_len: 4096,
+        depth: 32,
+        width: 16,
+    };
+}
+
+/// Creates `root` with `size.files` files of `size.file_len` bytes each.
+pub fn bulk_create<F: UniFs>(fs: &F, root: &Path, size: &Size) -> unifs::Result<()> {
+    let data = vec![0x5a; size.file_len];
+    fs.create_dir_all(root)?;
+    for i in 0..size.files {
+        fs.write(root.join(format!("file-{i}.bin")), &data)?;
+    }
+
+    Ok(())
+}
+
+/// Reads every file created by [`bulk_create`] and returns the number of bytes read.
+pub fn bulk_read<F: UniFs>(fs: &F, root: &Path, size: &Size) -> unifs::Result<u64> {
+    let mut read = 0;
+    for i in 0..size.files {
+        read += fs.read(root.join(format!("file-{i}.bin")))?.len() as u64;
+    }
+
+    Ok(read)
+}
+
+/// Creates a chain of `size.depth` nested directories below `root`, each containing
+/// `size.width` files.
+pub fn deep_tree<F: UniFs>(fs: &F, root: &Path, size: &Size) -> unifs::Result<()> {
+    let data = vec![0x5a; size.file_len];
+    let mut dir = root.to_path_buf();
+    for _ in 0..size.depth {
+        dir.push("level");
+        fs.create_dir_all(&dir)?;
+        for i in 0..size.width {
+            fs.write(dir.join(format!("file-{i}.bin")), &data)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Walks the tree below `root` and returns the number of entries.
+pub fn walk<F: UniFs>(fs: &F, root: &Path) -> unifs::Result<usize> {
+    let mut entries = 0;
+    for entry in fs.walk_dir(root) {
+        entry?;
+        entries += 1;
+    }
+
+    Ok(entries)
+}
+
+/// Recursively copies the tree at `from` to `to` and returns the number of bytes copied.
+pub fn copy<F: UniFs>(fs: &F, from: &Path, to: &Path) -> unifs::Result<u64> {
+    fs.copy_dir_all(from, to)
+}
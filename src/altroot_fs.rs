@@ -3,10 +3,13 @@
 use std::{
     borrow::Cow,
     io::ErrorKind,
-    path::{Path, PathBuf},
+    path::{Component, Path, PathBuf},
 };
 
-use crate::{Result, UniDirBuilder, UniDirEntry, UniFs, UniMetadata, UniOpenOptions};
+use crate::{
+    Result, SpecialFileKind, UniDirBuilder, UniDirEntry, UniFs, UniMetadata, UniOpenOptions,
+    WriteReport,
+};
 
 /// Wraps a filesystem to provide an alternative root directory.
 pub struct AltrootFs<FS: UniFs> {
@@ -14,6 +17,11 @@ pub struct AltrootFs<FS: UniFs> {
     fs: FS,
 }
 
+/// A handle to a directory of a filesystem, returned by [`crate::UniFsExt::open_dir`].
+///
+/// All paths passed to the handle are resolved relative to the directory.
+pub type DirHandle<'a, FS> = AltrootFs<&'a FS>;
+
 /// Represents a directory entry in the alternative root filesystem.
 pub struct AltrootDirEntry<T: UniDirEntry> {
     root: PathBuf,
@@ -54,6 +62,28 @@ fn get_real_path<P: AsRef<Path>, Q: AsRef<Path>>(root: P, path: Q) -> PathBuf {
     root.as_ref().join(path)
 }
 
+fn get_checked_real_path<P: AsRef<Path>, Q: AsRef<Path>>(root: P, path: Q) -> Result<PathBuf> {
+    let path = path.as_ref();
+
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Prefix(_) | Component::RootDir | Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    return Err(std::io::Error::new(
+                        ErrorKind::PermissionDenied,
+                        format!("Path escapes the root directory: {}", path.display()),
+                    ));
+                }
+            }
+            Component::Normal(name) => normalized.push(name),
+        }
+    }
+
+    Ok(root.as_ref().join(normalized))
+}
+
 impl<FS: UniFs> AltrootFs<FS> {
     /// Creates a new `AltrootFs` with the specified filesystem and root path.
     ///
@@ -88,9 +118,20 @@ impl<FS: UniFs> AltrootFs<FS> {
         Self::new(fs, root)
     }
 
+    /// Returns the root path in the wrapped filesystem.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
     fn get_real_path<P: AsRef<Path>>(&self, path: P) -> PathBuf {
         get_real_path(&self.root, path)
     }
+
+    /// Maps the path into the root, failing with [`ErrorKind::PermissionDenied`]
+    /// if it would resolve to a location outside of the root.
+    fn get_checked_real_path<P: AsRef<Path>>(&self, path: P) -> Result<PathBuf> {
+        get_checked_real_path(&self.root, path)
+    }
 }
 
 impl<FS: UniFs> UniFs for AltrootFs<FS> {
@@ -116,8 +157,8 @@ impl<FS: UniFs> UniFs for AltrootFs<FS> {
         from: P,
         to: Q,
     ) -> Result<u64> {
-        let from = self.get_real_path(from);
-        let to = self.get_real_path(to);
+        let from = self.get_checked_real_path(from)?;
+        let to = self.get_checked_real_path(to)?;
 
         self.fs.copy(from, to)
     }
@@ -144,8 +185,8 @@ impl<FS: UniFs> UniFs for AltrootFs<FS> {
         original: P,
         link: Q,
     ) -> Result<()> {
-        let original = self.get_real_path(original);
-        let link = self.get_real_path(link);
+        let original = self.get_checked_real_path(original)?;
+        let link = self.get_checked_real_path(link)?;
 
         self.fs.hard_link(original, link)
     }
@@ -206,8 +247,8 @@ impl<FS: UniFs> UniFs for AltrootFs<FS> {
         from: P,
         to: Q,
     ) -> Result<()> {
-        let from = self.get_real_path(from);
-        let to = self.get_real_path(to);
+        let from = self.get_checked_real_path(from)?;
+        let to = self.get_checked_real_path(to)?;
 
         self.fs.rename(from, to)
     }
@@ -234,6 +275,55 @@ impl<FS: UniFs> UniFs for AltrootFs<FS> {
         self.fs.write(path, contents)
     }
 
+    fn write_reporting<P: AsRef<std::path::Path>, C: AsRef<[u8]>>(
+        &self,
+        path: P,
+        contents: C,
+    ) -> Result<WriteReport> {
+        let path = self.get_real_path(path);
+
+        self.fs.write_reporting(path, contents)
+    }
+
+    fn create_special<P: AsRef<std::path::Path>>(
+        &self,
+        path: P,
+        kind: SpecialFileKind,
+    ) -> Result<()> {
+        let path = self.get_real_path(path);
+
+        self.fs.create_special(path, kind)
+    }
+
+    fn set_owner<P: AsRef<std::path::Path>>(
+        &self,
+        path: P,
+        uid: Option<u32>,
+        gid: Option<u32>,
+    ) -> Result<()> {
+        let path = self.get_real_path(path);
+
+        self.fs.set_owner(path, uid, gid)
+    }
+
+    fn symlink<P: AsRef<std::path::Path>, Q: AsRef<std::path::Path>>(
+        &self,
+        original: P,
+        link: Q,
+    ) -> Result<()> {
+        let link = self.get_checked_real_path(link)?;
+
+        // The target is stored verbatim and resolved by the wrapped filesystem, so
+        // absolute targets refer to its root rather than the alternative root.
+        self.fs.symlink(original, link)
+    }
+
+    fn physical_path<P: AsRef<std::path::Path>>(&self, path: P) -> Option<PathBuf> {
+        let path = self.get_real_path(path);
+
+        self.fs.physical_path(path)
+    }
+
     fn open_file<P: AsRef<std::path::Path>>(&self, path: P) -> Result<Self::File> {
         let path = self.get_real_path(path);
 
@@ -314,6 +404,11 @@ impl<O: UniOpenOptions> UniOpenOptions for AltrootOpenOptions<O> {
         self
     }
 
+    fn direct(&mut self, direct: bool) -> &mut Self {
+        self.inner.direct(direct);
+        self
+    }
+
     fn open<P: AsRef<std::path::Path>>(&self, path: P) -> Result<Self::File> {
         let path = get_real_path(&self.root, path);
         self.inner.open(path)
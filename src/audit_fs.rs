@@ -0,0 +1,266 @@
+//! This module provides a wrapper that records mutations in an audit log on a filesystem.
+
+use std::{
+    io::Write as _,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{rw_lock::RwLock, Result, SpecialFileKind, UniFs, UniOpenOptions as _, WriteReport};
+
+/// Wraps a filesystem to append a line to an audit log after each mutating operation.
+///
+/// The log is a file on a second filesystem, which may be a [`MemoryFs`](crate::MemoryFs)
+/// as well as the [`PhysicalFs`](crate::PhysicalFs), and is appended to with the open
+/// options of that filesystem. Each line consists of the following fields, separated by
+/// tabs:
+///
+/// - the time of the operation, as seconds since the Unix epoch with microseconds,
+/// - the name of the operation, such as `write` or `rename`,
+/// - the paths passed by the caller, quoted and separated by spaces,
+/// - `ok`, or `error(<kind>)` with the [`std::io::ErrorKind`] of the returned error.
+///
+/// Failing operations are logged as well. Writing to the log never changes the result
+/// of the operation; the first failure is kept until it is retrieved with
+/// [`AuditFs::take_log_error`].
+///
+/// The mutations made through files and directory builders obtained from
+/// [`UniFs::new_openoptions`] and [`UniFs::new_dirbuilder`] are not logged, while
+/// creating a file with [`UniFs::create_file`] or [`UniFs::create_new_file`] is.
+///
+/// # Example
+///
+/// ```
+/// use unifs::{AuditFs, MemoryFs, UniFs as _};
+///
+/// # fn main() -> unifs::Result<()> {
+/// let log = MemoryFs::default();
+/// let fs = AuditFs::new(MemoryFs::default(), &log, "/audit.log");
+/// fs.write("/file.txt", b"Hello, World!")?;
+///
+/// let audit = log.read_to_string("/audit.log")?;
+/// assert!(audit.ends_with("\twrite\t\"/file.txt\"\tok\n"));
+/// # Ok(())
+/// # }
+/// ```
+pub struct AuditFs<FS: UniFs, LOG: UniFs> {
+    fs: FS,
+    log: LOG,
+    log_path: PathBuf,
+    log_error: RwLock<Option<std::io::Error>>,
+}
+
+impl<FS: UniFs, LOG: UniFs> AuditFs<FS, LOG> {
+    /// Creates a new `AuditFs` wrapping the filesystem `fs`, appending to the file at
+    /// `log_path` on the filesystem `log`.
+    ///
+    /// The log file is created with the first logged operation if it does not exist.
+    pub fn new<P: AsRef<Path>>(fs: FS, log: LOG, log_path: P) -> Self {
+        Self {
+            fs,
+            log,
+            log_path: log_path.as_ref().to_path_buf(),
+            log_error: RwLock::new(None),
+        }
+    }
+
+    /// Returns the first error encountered while writing to the log since the last call,
+    /// or `None` if every line was written.
+    pub fn take_log_error(&self) -> Option<std::io::Error> {
+        self.log_error.write().take()
+    }
+
+    /// Returns the wrapped filesystem.
+    pub fn into_inner(self) -> FS {
+        self.fs
+    }
+
+    fn audit<T>(&self, op: &str, paths: &[&Path], result: Result<T>) -> Result<T> {
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let paths = paths
+            .iter()
+            .map(|path| format!("{path:?}"))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let outcome = match &result {
+            Ok(_) => "ok".to_string(),
+            Err(err) => format!("error({:?})", err.kind()),
+        };
+        let line = format!(
+            "{}.{:06}\t{op}\t{paths}\t{outcome}\n",
+            time.as_secs(),
+            time.subsec_micros()
+        );
+
+        if let Err(err) = self.append(&line) {
+            self.log_error.write().get_or_insert(err);
+        }
+        result
+    }
+
+    fn append(&self, line: &str) -> Result<()> {
+        let mut options = self.log.new_openoptions();
+        options.write(true).append(true).create(true);
+        options.open(&self.log_path)?.write_all(line.as_bytes())
+    }
+}
+
+impl<FS: UniFs, LOG: UniFs> UniFs for AuditFs<FS, LOG> {
+    type Metadata = FS::Metadata;
+    type ReadDir = FS::ReadDir;
+    type DirEntry = FS::DirEntry;
+    type Permissions = FS::Permissions;
+    type File = FS::File;
+    type OpenOptions = FS::OpenOptions;
+    type DirBuilder = FS::DirBuilder;
+
+    fn canonicalize<P: AsRef<Path>>(&self, path: P) -> Result<PathBuf> {
+        self.fs.canonicalize(path)
+    }
+
+    fn copy<P: AsRef<Path>, Q: AsRef<Path>>(&self, from: P, to: Q) -> Result<u64> {
+        let (from, to) = (from.as_ref(), to.as_ref());
+        self.audit("copy", &[from, to], self.fs.copy(from, to))
+    }
+
+    fn create_dir<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        self.audit("create_dir", &[path], self.fs.create_dir(path))
+    }
+
+    fn create_dir_all<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        self.audit("create_dir_all", &[path], self.fs.create_dir_all(path))
+    }
+
+    fn exists<P: AsRef<Path>>(&self, path: P) -> Result<bool> {
+        self.fs.exists(path)
+    }
+
+    fn hard_link<P: AsRef<Path>, Q: AsRef<Path>>(&self, original: P, link: Q) -> Result<()> {
+        let (original, link) = (original.as_ref(), link.as_ref());
+        self.audit(
+            "hard_link",
+            &[original, link],
+            self.fs.hard_link(original, link),
+        )
+    }
+
+    fn metadata<P: AsRef<Path>>(&self, path: P) -> Result<Self::Metadata> {
+        self.fs.metadata(path)
+    }
+
+    fn read<P: AsRef<Path>>(&self, path: P) -> Result<Vec<u8>> {
+        self.fs.read(path)
+    }
+
+    fn read_dir<P: AsRef<Path>>(&self, path: P) -> Result<Self::ReadDir> {
+        self.fs.read_dir(path)
+    }
+
+    fn read_link<P: AsRef<Path>>(&self, path: P) -> Result<PathBuf> {
+        self.fs.read_link(path)
+    }
+
+    fn read_to_string<P: AsRef<Path>>(&self, path: P) -> Result<String> {
+        self.fs.read_to_string(path)
+    }
+
+    fn remove_dir<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        self.audit("remove_dir", &[path], self.fs.remove_dir(path))
+    }
+
+    fn remove_dir_all<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        self.audit("remove_dir_all", &[path], self.fs.remove_dir_all(path))
+    }
+
+    fn remove_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        self.audit("remove_file", &[path], self.fs.remove_file(path))
+    }
+
+    fn rename<P: AsRef<Path>, Q: AsRef<Path>>(&self, from: P, to: Q) -> Result<()> {
+        let (from, to) = (from.as_ref(), to.as_ref());
+        self.audit("rename", &[from, to], self.fs.rename(from, to))
+    }
+
+    fn set_permissions<P: AsRef<Path>>(&self, path: P, perm: Self::Permissions) -> Result<()> {
+        let path = path.as_ref();
+        self.audit(
+            "set_permissions",
+            &[path],
+            self.fs.set_permissions(path, perm),
+        )
+    }
+
+    fn symlink_metadata<P: AsRef<Path>>(&self, path: P) -> Result<Self::Metadata> {
+        self.fs.symlink_metadata(path)
+    }
+
+    fn write<P: AsRef<Path>, C: AsRef<[u8]>>(&self, path: P, contents: C) -> Result<()> {
+        let path = path.as_ref();
+        self.audit("write", &[path], self.fs.write(path, contents))
+    }
+
+    fn write_reporting<P: AsRef<Path>, C: AsRef<[u8]>>(
+        &self,
+        path: P,
+        contents: C,
+    ) -> Result<WriteReport> {
+        let path = path.as_ref();
+        self.audit("write", &[path], self.fs.write_reporting(path, contents))
+    }
+
+    fn create_special<P: AsRef<Path>>(&self, path: P, kind: SpecialFileKind) -> Result<()> {
+        let path = path.as_ref();
+        self.audit(
+            "create_special",
+            &[path],
+            self.fs.create_special(path, kind),
+        )
+    }
+
+    fn set_owner<P: AsRef<Path>>(&self, path: P, uid: Option<u32>, gid: Option<u32>) -> Result<()> {
+        let path = path.as_ref();
+        self.audit("set_owner", &[path], self.fs.set_owner(path, uid, gid))
+    }
+
+    fn symlink<P: AsRef<Path>, Q: AsRef<Path>>(&self, original: P, link: Q) -> Result<()> {
+        let (original, link) = (original.as_ref(), link.as_ref());
+        self.audit(
+            "symlink",
+            &[original, link],
+            self.fs.symlink(original, link),
+        )
+    }
+
+    fn physical_path<P: AsRef<Path>>(&self, path: P) -> Option<PathBuf> {
+        self.fs.physical_path(path)
+    }
+
+    fn open_file<P: AsRef<Path>>(&self, path: P) -> Result<Self::File> {
+        self.fs.open_file(path)
+    }
+
+    fn create_file<P: AsRef<Path>>(&self, path: P) -> Result<Self::File> {
+        let path = path.as_ref();
+        self.audit("create_file", &[path], self.fs.create_file(path))
+    }
+
+    fn create_new_file<P: AsRef<Path>>(&self, path: P) -> Result<Self::File> {
+        let path = path.as_ref();
+        self.audit("create_new_file", &[path], self.fs.create_new_file(path))
+    }
+
+    fn new_openoptions(&self) -> Self::OpenOptions {
+        self.fs.new_openoptions()
+    }
+
+    fn new_dirbuilder(&self) -> Self::DirBuilder {
+        self.fs.new_dirbuilder()
+    }
+}
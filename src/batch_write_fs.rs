@@ -0,0 +1,330 @@
+//! This module provides a wrapper that buffers whole-file writes and applies them in bulk.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use crate::{rw_lock::RwLock, Result, SpecialFileKind, UniFile as _, UniFs, WriteReport};
+
+/// Wraps a filesystem to buffer the writes made with [`UniFs::write`] and apply them
+/// in bulk.
+///
+/// Writes are kept in memory until [`BatchWriteFs::flush`] is called, the buffered
+/// contents exceed the configured capacity, or the wrapper is dropped. Repeated writes
+/// to the same path are coalesced into a single one, and the files are written in the
+/// order of their paths. With [`BatchWriteFs::set_sync_on_flush`], the written files are
+/// synced to storage once all of them have been written, instead of after each file.
+///
+/// Every other operation writes the buffered files before it is performed, so it
+/// observes them and the order of operations is preserved. Failures of these writes
+/// are not reported by the unrelated operation, but kept with their paths until the
+/// next call to [`BatchWriteFs::flush`]. Paths are compared as given, so writing to two
+/// different paths that resolve to the same file writes it twice.
+///
+/// Filesystems that keep their files in memory, such as [`MemoryFs`](crate::MemoryFs),
+/// gain nothing from batching. A capacity of zero passes every write through, which
+/// allows generic code to use the wrapper regardless of the filesystem.
+///
+/// # Example
+///
+/// ```
+/// use unifs::{BatchWriteFs, MemoryFs, UniFs as _};
+///
+/// # fn main() -> unifs::Result<()> {
+/// let memory = MemoryFs::default();
+/// let fs = BatchWriteFs::new(&memory);
+/// fs.write("/file.txt", b"Hello, World!")?;
+/// assert!(!memory.exists("/file.txt")?);
+///
+/// fs.flush()?;
+/// assert_eq!(memory.read("/file.txt")?, b"Hello, World!");
+/// # Ok(())
+/// # }
+/// ```
+pub struct BatchWriteFs<FS: UniFs> {
+    fs: FS,
+    pending: RwLock<Pending>,
+    capacity: usize,
+    sync_on_flush: bool,
+}
+
+#[derive(Default)]
+struct Pending {
+    files: BTreeMap<PathBuf, Vec<u8>>,
+    len: usize,
+    /// The buffered writes that failed, with their paths, until they are reported.
+    failed: Vec<(PathBuf, std::io::Error)>,
+}
+
+impl<FS: UniFs> BatchWriteFs<FS> {
+    /// The capacity used by [`BatchWriteFs::new`].
+    pub const DEFAULT_CAPACITY: usize = 8 * 1024 * 1024;
+
+    /// Creates a new `BatchWriteFs` wrapping the specified filesystem, buffering up to
+    /// [`BatchWriteFs::DEFAULT_CAPACITY`] bytes.
+    pub fn new(fs: FS) -> Self {
+        Self::with_capacity(fs, Self::DEFAULT_CAPACITY)
+    }
+
+    /// Creates a new `BatchWriteFs` wrapping the specified filesystem, buffering up to
+    /// `capacity` bytes. A capacity of zero disables buffering.
+    pub fn with_capacity(fs: FS, capacity: usize) -> Self {
+        Self {
+            fs,
+            pending: RwLock::new(Pending::default()),
+            capacity,
+            sync_on_flush: false,
+        }
+    }
+
+    /// Sets whether the written files are synced to storage with
+    /// [`UniFile::sync_all`](crate::UniFile::sync_all) when they are flushed.
+    pub fn set_sync_on_flush(&mut self, sync_on_flush: bool) {
+        self.sync_on_flush = sync_on_flush;
+    }
+
+    /// Returns the number of files with buffered writes.
+    pub fn pending_len(&self) -> usize {
+        self.pending.read().files.len()
+    }
+
+    /// Writes all buffered files to the wrapped filesystem.
+    ///
+    /// # Errors
+    /// Returns the first failed write, including the writes made before other
+    /// operations since the last flush, with its path in the message. Failed writes
+    /// are discarded once they have been reported, the other files are written
+    /// regardless.
+    pub fn flush(&self) -> Result<()> {
+        self.write_pending();
+
+        let failed = std::mem::take(&mut self.pending.write().failed);
+        let more = failed.len().saturating_sub(1);
+        match failed.into_iter().next() {
+            None => Ok(()),
+            Some((path, err)) if more == 0 => Err(std::io::Error::new(
+                err.kind(),
+                format!("Failed to write '{}': {err}", path.display()),
+            )),
+            Some((path, err)) => Err(std::io::Error::new(
+                err.kind(),
+                format!(
+                    "Failed to write '{}': {err} ({more} more buffered writes failed)",
+                    path.display()
+                ),
+            )),
+        }
+    }
+
+    /// Writes all buffered files to the wrapped filesystem, keeping the failures to be
+    /// reported by [`BatchWriteFs::flush`].
+    fn write_pending(&self) {
+        let mut pending = self.pending.write();
+        if pending.files.is_empty() {
+            return;
+        }
+
+        let files = std::mem::take(&mut pending.files);
+        pending.len = 0;
+        for (path, contents) in files {
+            let result = self.fs.write(&path, contents).and_then(|()| {
+                if self.sync_on_flush {
+                    self.fs.open_file(&path)?.sync_all()?;
+                }
+                Ok(())
+            });
+            if let Err(err) = result {
+                pending.failed.push((path, err));
+            }
+        }
+    }
+}
+
+impl<FS: UniFs> Drop for BatchWriteFs<FS> {
+    /// Flushes the buffered writes, ignoring any errors.
+    ///
+    /// Call [`BatchWriteFs::flush`] before dropping the wrapper to handle them.
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+impl<FS: UniFs> UniFs for BatchWriteFs<FS> {
+    type Metadata = FS::Metadata;
+    type ReadDir = FS::ReadDir;
+    type DirEntry = FS::DirEntry;
+    type Permissions = FS::Permissions;
+    type File = FS::File;
+    type OpenOptions = FS::OpenOptions;
+    type DirBuilder = FS::DirBuilder;
+
+    fn canonicalize<P: AsRef<Path>>(&self, path: P) -> Result<PathBuf> {
+        self.write_pending();
+        self.fs.canonicalize(path)
+    }
+
+    fn copy<P: AsRef<Path>, Q: AsRef<Path>>(&self, from: P, to: Q) -> Result<u64> {
+        self.write_pending();
+        self.fs.copy(from, to)
+    }
+
+    fn create_dir<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        self.write_pending();
+        self.fs.create_dir(path)
+    }
+
+    fn create_dir_all<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        self.write_pending();
+        self.fs.create_dir_all(path)
+    }
+
+    fn exists<P: AsRef<Path>>(&self, path: P) -> Result<bool> {
+        self.write_pending();
+        self.fs.exists(path)
+    }
+
+    fn hard_link<P: AsRef<Path>, Q: AsRef<Path>>(&self, original: P, link: Q) -> Result<()> {
+        self.write_pending();
+        self.fs.hard_link(original, link)
+    }
+
+    fn metadata<P: AsRef<Path>>(&self, path: P) -> Result<Self::Metadata> {
+        self.write_pending();
+        self.fs.metadata(path)
+    }
+
+    fn read<P: AsRef<Path>>(&self, path: P) -> Result<Vec<u8>> {
+        self.write_pending();
+        self.fs.read(path)
+    }
+
+    fn read_dir<P: AsRef<Path>>(&self, path: P) -> Result<Self::ReadDir> {
+        self.write_pending();
+        self.fs.read_dir(path)
+    }
+
+    fn read_link<P: AsRef<Path>>(&self, path: P) -> Result<PathBuf> {
+        self.write_pending();
+        self.fs.read_link(path)
+    }
+
+    fn read_to_string<P: AsRef<Path>>(&self, path: P) -> Result<String> {
+        self.write_pending();
+        self.fs.read_to_string(path)
+    }
+
+    fn remove_dir<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        self.write_pending();
+        self.fs.remove_dir(path)
+    }
+
+    fn remove_dir_all<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        self.write_pending();
+        self.fs.remove_dir_all(path)
+    }
+
+    fn remove_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        self.write_pending();
+        self.fs.remove_file(path)
+    }
+
+    fn rename<P: AsRef<Path>, Q: AsRef<Path>>(&self, from: P, to: Q) -> Result<()> {
+        self.write_pending();
+        self.fs.rename(from, to)
+    }
+
+    fn set_permissions<P: AsRef<Path>>(&self, path: P, perm: Self::Permissions) -> Result<()> {
+        self.write_pending();
+        self.fs.set_permissions(path, perm)
+    }
+
+    fn symlink_metadata<P: AsRef<Path>>(&self, path: P) -> Result<Self::Metadata> {
+        self.write_pending();
+        self.fs.symlink_metadata(path)
+    }
+
+    /// Buffers the write, or writes all buffered files once they exceed the capacity.
+    ///
+    /// Errors of buffered writes are reported by [`BatchWriteFs::flush`]. With a
+    /// capacity of zero, the file is written directly and errors are returned.
+    fn write<P: AsRef<Path>, C: AsRef<[u8]>>(&self, path: P, contents: C) -> Result<()> {
+        if self.capacity == 0 {
+            self.write_pending();
+            return self.fs.write(path, contents);
+        }
+
+        let contents = contents.as_ref();
+        {
+            let mut pending = self.pending.write();
+            let previous = pending
+                .files
+                .insert(path.as_ref().to_path_buf(), contents.to_vec());
+            pending.len =
+                pending.len - previous.map_or(0, |previous| previous.len()) + contents.len();
+            if pending.len <= self.capacity {
+                return Ok(());
+            }
+        }
+        self.write_pending();
+        Ok(())
+    }
+
+    fn write_reporting<P: AsRef<Path>, C: AsRef<[u8]>>(
+        &self,
+        path: P,
+        contents: C,
+    ) -> Result<WriteReport> {
+        // The previous length is only known once the file is written.
+        self.write_pending();
+        self.fs.write_reporting(path, contents)
+    }
+
+    fn create_special<P: AsRef<Path>>(&self, path: P, kind: SpecialFileKind) -> Result<()> {
+        self.write_pending();
+        self.fs.create_special(path, kind)
+    }
+
+    fn set_owner<P: AsRef<Path>>(&self, path: P, uid: Option<u32>, gid: Option<u32>) -> Result<()> {
+        self.write_pending();
+        self.fs.set_owner(path, uid, gid)
+    }
+
+    fn symlink<P: AsRef<Path>, Q: AsRef<Path>>(&self, original: P, link: Q) -> Result<()> {
+        self.write_pending();
+        self.fs.symlink(original, link)
+    }
+
+    fn physical_path<P: AsRef<Path>>(&self, path: P) -> Option<PathBuf> {
+        self.fs.physical_path(path)
+    }
+
+    fn open_file<P: AsRef<Path>>(&self, path: P) -> Result<Self::File> {
+        self.write_pending();
+        self.fs.open_file(path)
+    }
+
+    fn create_file<P: AsRef<Path>>(&self, path: P) -> Result<Self::File> {
+        self.write_pending();
+        self.fs.create_file(path)
+    }
+
+    fn create_new_file<P: AsRef<Path>>(&self, path: P) -> Result<Self::File> {
+        self.write_pending();
+        self.fs.create_new_file(path)
+    }
+
+    /// Writes the buffered files and returns the open options of the wrapped
+    /// filesystem.
+    fn new_openoptions(&self) -> Self::OpenOptions {
+        self.write_pending();
+        self.fs.new_openoptions()
+    }
+
+    /// Writes the buffered files and returns the directory builder of the wrapped
+    /// filesystem.
+    fn new_dirbuilder(&self) -> Self::DirBuilder {
+        self.write_pending();
+        self.fs.new_dirbuilder()
+    }
+}
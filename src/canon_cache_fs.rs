@@ -0,0 +1,225 @@
+//! This module provides a wrapper that caches the results of `canonicalize`.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use crate::{rw_lock::RwLock, Result, SpecialFileKind, UniFs, WriteReport};
+
+/// Wraps a filesystem to memoize the results of [`UniFs::canonicalize`].
+///
+/// Only successful results for absolute paths are cached, keyed by the path as passed
+/// by the caller. Relative paths depend on the current directory of the wrapped
+/// filesystem, which this wrapper cannot observe, so they are always passed through.
+/// The cache holds at most `capacity` entries and is cleared entirely once it is full.
+///
+/// Invalidation is conservative: every operation that could change how a path is
+/// resolved (creating symbolic links, renaming, removing and changing permissions)
+/// clears the whole cache, regardless of the paths involved. Changes made to the
+/// wrapped filesystem without going through this wrapper are not detected.
+///
+/// # Example
+///
+/// ```
+/// use unifs::{CanonCacheFs, MemoryFs, UniFs as _};
+///
+/// # fn main() -> unifs::Result<()> {
+/// let fs = CanonCacheFs::new(MemoryFs::default());
+/// fs.create_dir("/dir")?;
+/// assert_eq!(fs.canonicalize("/dir/../dir")?, fs.canonicalize("/dir/../dir")?);
+/// assert_eq!(fs.cached_len(), 1);
+/// # Ok(())
+/// # }
+/// ```
+pub struct CanonCacheFs<FS: UniFs> {
+    fs: FS,
+    cache: RwLock<HashMap<PathBuf, PathBuf>>,
+    capacity: usize,
+}
+
+impl<FS: UniFs> CanonCacheFs<FS> {
+    /// The capacity used by [`CanonCacheFs::new`].
+    pub const DEFAULT_CAPACITY: usize = 1024;
+
+    /// Creates a new `CanonCacheFs` wrapping the specified filesystem, caching up to
+    /// [`CanonCacheFs::DEFAULT_CAPACITY`] paths.
+    pub fn new(fs: FS) -> Self {
+        Self::with_capacity(fs, Self::DEFAULT_CAPACITY)
+    }
+
+    /// Creates a new `CanonCacheFs` wrapping the specified filesystem, caching up to
+    /// `capacity` paths. A capacity of zero disables caching.
+    pub fn with_capacity(fs: FS, capacity: usize) -> Self {
+        Self {
+            fs,
+            cache: RwLock::new(HashMap::new()),
+            capacity,
+        }
+    }
+
+    /// Returns the number of currently cached paths.
+    pub fn cached_len(&self) -> usize {
+        self.cache.read().len()
+    }
+
+    /// Removes all cached paths.
+    pub fn clear_cache(&self) {
+        self.cache.write().clear();
+    }
+
+    /// Returns the wrapped filesystem.
+    pub fn into_inner(self) -> FS {
+        self.fs
+    }
+
+    fn invalidate<T>(&self, res: Result<T>) -> Result<T> {
+        // Clear even if the operation failed, as it may have been partially applied.
+        self.clear_cache();
+        res
+    }
+}
+
+impl<FS: UniFs> UniFs for CanonCacheFs<FS> {
+    type Metadata = FS::Metadata;
+    type ReadDir = FS::ReadDir;
+    type DirEntry = FS::DirEntry;
+    type Permissions = FS::Permissions;
+    type File = FS::File;
+    type OpenOptions = FS::OpenOptions;
+    type DirBuilder = FS::DirBuilder;
+
+    fn canonicalize<P: AsRef<Path>>(&self, path: P) -> Result<PathBuf> {
+        let path = path.as_ref();
+        if path.is_relative() {
+            return self.fs.canonicalize(path);
+        }
+
+        if let Some(cached) = self.cache.read().get(path) {
+            return Ok(cached.clone());
+        }
+
+        let canonical = self.fs.canonicalize(path)?;
+        if self.capacity > 0 {
+            let mut cache = self.cache.write();
+            if cache.len() >= self.capacity {
+                cache.clear();
+            }
+            cache.insert(path.to_path_buf(), canonical.clone());
+        }
+
+        Ok(canonical)
+    }
+
+    fn copy<P: AsRef<Path>, Q: AsRef<Path>>(&self, from: P, to: Q) -> Result<u64> {
+        self.fs.copy(from, to)
+    }
+
+    fn create_dir<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        self.fs.create_dir(path)
+    }
+
+    fn create_dir_all<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        self.fs.create_dir_all(path)
+    }
+
+    fn exists<P: AsRef<Path>>(&self, path: P) -> Result<bool> {
+        self.fs.exists(path)
+    }
+
+    fn hard_link<P: AsRef<Path>, Q: AsRef<Path>>(&self, original: P, link: Q) -> Result<()> {
+        self.fs.hard_link(original, link)
+    }
+
+    fn metadata<P: AsRef<Path>>(&self, path: P) -> Result<Self::Metadata> {
+        self.fs.metadata(path)
+    }
+
+    fn read<P: AsRef<Path>>(&self, path: P) -> Result<Vec<u8>> {
+        self.fs.read(path)
+    }
+
+    fn read_dir<P: AsRef<Path>>(&self, path: P) -> Result<Self::ReadDir> {
+        self.fs.read_dir(path)
+    }
+
+    fn read_link<P: AsRef<Path>>(&self, path: P) -> Result<PathBuf> {
+        self.fs.read_link(path)
+    }
+
+    fn read_to_string<P: AsRef<Path>>(&self, path: P) -> Result<String> {
+        self.fs.read_to_string(path)
+    }
+
+    fn remove_dir<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        self.invalidate(self.fs.remove_dir(path))
+    }
+
+    fn remove_dir_all<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        self.invalidate(self.fs.remove_dir_all(path))
+    }
+
+    fn remove_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        self.invalidate(self.fs.remove_file(path))
+    }
+
+    fn rename<P: AsRef<Path>, Q: AsRef<Path>>(&self, from: P, to: Q) -> Result<()> {
+        self.invalidate(self.fs.rename(from, to))
+    }
+
+    fn set_permissions<P: AsRef<Path>>(&self, path: P, perm: Self::Permissions) -> Result<()> {
+        self.invalidate(self.fs.set_permissions(path, perm))
+    }
+
+    fn symlink_metadata<P: AsRef<Path>>(&self, path: P) -> Result<Self::Metadata> {
+        self.fs.symlink_metadata(path)
+    }
+
+    fn write<P: AsRef<Path>, C: AsRef<[u8]>>(&self, path: P, contents: C) -> Result<()> {
+        self.fs.write(path, contents)
+    }
+
+    fn write_reporting<P: AsRef<Path>, C: AsRef<[u8]>>(
+        &self,
+        path: P,
+        contents: C,
+    ) -> Result<WriteReport> {
+        self.fs.write_reporting(path, contents)
+    }
+
+    fn create_special<P: AsRef<Path>>(&self, path: P, kind: SpecialFileKind) -> Result<()> {
+        self.fs.create_special(path, kind)
+    }
+
+    fn set_owner<P: AsRef<Path>>(&self, path: P, uid: Option<u32>, gid: Option<u32>) -> Result<()> {
+        self.fs.set_owner(path, uid, gid)
+    }
+
+    fn symlink<P: AsRef<Path>, Q: AsRef<Path>>(&self, original: P, link: Q) -> Result<()> {
+        self.invalidate(self.fs.symlink(original, link))
+    }
+
+    fn physical_path<P: AsRef<Path>>(&self, path: P) -> Option<PathBuf> {
+        self.fs.physical_path(path)
+    }
+
+    fn open_file<P: AsRef<Path>>(&self, path: P) -> Result<Self::File> {
+        self.fs.open_file(path)
+    }
+
+    fn create_file<P: AsRef<Path>>(&self, path: P) -> Result<Self::File> {
+        self.fs.create_file(path)
+    }
+
+    fn create_new_file<P: AsRef<Path>>(&self, path: P) -> Result<Self::File> {
+        self.fs.create_new_file(path)
+    }
+
+    fn new_openoptions(&self) -> Self::OpenOptions {
+        self.fs.new_openoptions()
+    }
+
+    fn new_dirbuilder(&self) -> Self::DirBuilder {
+        self.fs.new_dirbuilder()
+    }
+}
@@ -0,0 +1,352 @@
+//! This module provides a wrapper that annotates errors with the operation and path.
+
+use std::path::{Path, PathBuf};
+
+use crate::{Result, SpecialFileKind, UniDirBuilder, UniFs, UniOpenOptions, WriteReport};
+
+/// Wraps a filesystem to annotate every returned error with the name of the
+/// operation and the path(s) passed by the caller.
+///
+/// The [`std::io::ErrorKind`] of the original error is preserved. This is useful
+/// for debugging composed filesystems, where paths are often rewritten before
+/// reaching the filesystem that produces the error.
+///
+/// # Example
+///
+/// ```
+/// use unifs::{ContextFs, MemoryFs, UniFs as _};
+///
+/// let fs = ContextFs::new(MemoryFs::default());
+/// let err = fs.read("/missing.txt").unwrap_err();
+/// assert!(err.to_string().starts_with("read '/missing.txt'"));
+/// ```
+pub struct ContextFs<FS: UniFs> {
+    fs: FS,
+}
+
+/// Represents options for opening files in the context filesystem.
+pub struct ContextOpenOptions<O: UniOpenOptions> {
+    inner: O,
+}
+
+/// Represents a directory builder for the context filesystem.
+pub struct ContextDirBuilder<T: UniDirBuilder> {
+    inner: T,
+}
+
+fn context(op: &str, path: &Path, err: std::io::Error) -> std::io::Error {
+    std::io::Error::new(err.kind(), format!("{op} '{}': {err}", path.display()))
+}
+
+fn context2(op: &str, from: &Path, to: &Path, err: std::io::Error) -> std::io::Error {
+    std::io::Error::new(
+        err.kind(),
+        format!("{op} '{}' to '{}': {err}", from.display(), to.display()),
+    )
+}
+
+impl<FS: UniFs> ContextFs<FS> {
+    /// Creates a new `ContextFs` wrapping the specified filesystem.
+    pub fn new(fs: FS) -> Self {
+        Self { fs }
+    }
+
+    /// Returns the wrapped filesystem.
+    pub fn into_inner(self) -> FS {
+        self.fs
+    }
+}
+
+impl<FS: UniFs> UniFs for ContextFs<FS> {
+    type Metadata = FS::Metadata;
+    type ReadDir = FS::ReadDir;
+    type DirEntry = FS::DirEntry;
+    type Permissions = FS::Permissions;
+    type File = FS::File;
+    type OpenOptions = ContextOpenOptions<FS::OpenOptions>;
+    type DirBuilder = ContextDirBuilder<FS::DirBuilder>;
+
+    fn canonicalize<P: AsRef<Path>>(&self, path: P) -> Result<PathBuf> {
+        let path = path.as_ref();
+
+        self.fs
+            .canonicalize(path)
+            .map_err(|err| context("canonicalize", path, err))
+    }
+
+    fn copy<P: AsRef<Path>, Q: AsRef<Path>>(&self, from: P, to: Q) -> Result<u64> {
+        let from = from.as_ref();
+        let to = to.as_ref();
+
+        self.fs
+            .copy(from, to)
+            .map_err(|err| context2("copy", from, to, err))
+    }
+
+    fn create_dir<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+
+        self.fs
+            .create_dir(path)
+            .map_err(|err| context("create_dir", path, err))
+    }
+
+    fn create_dir_all<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+
+        self.fs
+            .create_dir_all(path)
+            .map_err(|err| context("create_dir_all", path, err))
+    }
+
+    fn exists<P: AsRef<Path>>(&self, path: P) -> Result<bool> {
+        let path = path.as_ref();
+
+        self.fs
+            .exists(path)
+            .map_err(|err| context("exists", path, err))
+    }
+
+    fn hard_link<P: AsRef<Path>, Q: AsRef<Path>>(&self, original: P, link: Q) -> Result<()> {
+        let original = original.as_ref();
+        let link = link.as_ref();
+
+        self.fs
+            .hard_link(original, link)
+            .map_err(|err| context2("hard_link", original, link, err))
+    }
+
+    fn metadata<P: AsRef<Path>>(&self, path: P) -> Result<Self::Metadata> {
+        let path = path.as_ref();
+
+        self.fs
+            .metadata(path)
+            .map_err(|err| context("metadata", path, err))
+    }
+
+    fn read<P: AsRef<Path>>(&self, path: P) -> Result<Vec<u8>> {
+        let path = path.as_ref();
+
+        self.fs.read(path).map_err(|err| context("read", path, err))
+    }
+
+    fn read_dir<P: AsRef<Path>>(&self, path: P) -> Result<Self::ReadDir> {
+        let path = path.as_ref();
+
+        self.fs
+            .read_dir(path)
+            .map_err(|err| context("read_dir", path, err))
+    }
+
+    fn read_link<P: AsRef<Path>>(&self, path: P) -> Result<PathBuf> {
+        let path = path.as_ref();
+
+        self.fs
+            .read_link(path)
+            .map_err(|err| context("read_link", path, err))
+    }
+
+    fn read_to_string<P: AsRef<Path>>(&self, path: P) -> Result<String> {
+        let path = path.as_ref();
+
+        self.fs
+            .read_to_string(path)
+            .map_err(|err| context("read_to_string", path, err))
+    }
+
+    fn remove_dir<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+
+        self.fs
+            .remove_dir(path)
+            .map_err(|err| context("remove_dir", path, err))
+    }
+
+    fn remove_dir_all<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+
+        self.fs
+            .remove_dir_all(path)
+            .map_err(|err| context("remove_dir_all", path, err))
+    }
+
+    fn remove_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+
+        self.fs
+            .remove_file(path)
+            .map_err(|err| context("remove_file", path, err))
+    }
+
+    fn rename<P: AsRef<Path>, Q: AsRef<Path>>(&self, from: P, to: Q) -> Result<()> {
+        let from = from.as_ref();
+        let to = to.as_ref();
+
+        self.fs
+            .rename(from, to)
+            .map_err(|err| context2("rename", from, to, err))
+    }
+
+    fn set_permissions<P: AsRef<Path>>(&self, path: P, perm: Self::Permissions) -> Result<()> {
+        let path = path.as_ref();
+
+        self.fs
+            .set_permissions(path, perm)
+            .map_err(|err| context("set_permissions", path, err))
+    }
+
+    fn symlink_metadata<P: AsRef<Path>>(&self, path: P) -> Result<Self::Metadata> {
+        let path = path.as_ref();
+
+        self.fs
+            .symlink_metadata(path)
+            .map_err(|err| context("symlink_metadata", path, err))
+    }
+
+    fn write<P: AsRef<Path>, C: AsRef<[u8]>>(&self, path: P, contents: C) -> Result<()> {
+        let path = path.as_ref();
+
+        self.fs
+            .write(path, contents)
+            .map_err(|err| context("write", path, err))
+    }
+
+    fn write_reporting<P: AsRef<Path>, C: AsRef<[u8]>>(
+        &self,
+        path: P,
+        contents: C,
+    ) -> Result<WriteReport> {
+        let path = path.as_ref();
+
+        self.fs
+            .write_reporting(path, contents)
+            .map_err(|err| context("write_reporting", path, err))
+    }
+
+    fn create_special<P: AsRef<Path>>(&self, path: P, kind: SpecialFileKind) -> Result<()> {
+        let path = path.as_ref();
+
+        self.fs
+            .create_special(path, kind)
+            .map_err(|err| context("create_special", path, err))
+    }
+
+    fn set_owner<P: AsRef<Path>>(&self, path: P, uid: Option<u32>, gid: Option<u32>) -> Result<()> {
+        let path = path.as_ref();
+
+        self.fs
+            .set_owner(path, uid, gid)
+            .map_err(|err| context("set_owner", path, err))
+    }
+
+    fn symlink<P: AsRef<Path>, Q: AsRef<Path>>(&self, original: P, link: Q) -> Result<()> {
+        let original = original.as_ref();
+        let link = link.as_ref();
+
+        self.fs
+            .symlink(original, link)
+            .map_err(|err| context2("symlink", original, link, err))
+    }
+
+    fn physical_path<P: AsRef<Path>>(&self, path: P) -> Option<PathBuf> {
+        self.fs.physical_path(path)
+    }
+
+    fn open_file<P: AsRef<Path>>(&self, path: P) -> Result<Self::File> {
+        let path = path.as_ref();
+
+        self.fs
+            .open_file(path)
+            .map_err(|err| context("open_file", path, err))
+    }
+
+    fn create_file<P: AsRef<Path>>(&self, path: P) -> Result<Self::File> {
+        let path = path.as_ref();
+
+        self.fs
+            .create_file(path)
+            .map_err(|err| context("create_file", path, err))
+    }
+
+    fn create_new_file<P: AsRef<Path>>(&self, path: P) -> Result<Self::File> {
+        let path = path.as_ref();
+
+        self.fs
+            .create_new_file(path)
+            .map_err(|err| context("create_new_file", path, err))
+    }
+
+    fn new_openoptions(&self) -> Self::OpenOptions {
+        ContextOpenOptions {
+            inner: self.fs.new_openoptions(),
+        }
+    }
+
+    fn new_dirbuilder(&self) -> Self::DirBuilder {
+        ContextDirBuilder {
+            inner: self.fs.new_dirbuilder(),
+        }
+    }
+}
+
+impl<O: UniOpenOptions> UniOpenOptions for ContextOpenOptions<O> {
+    type File = O::File;
+
+    fn read(&mut self, read: bool) -> &mut Self {
+        self.inner.read(read);
+        self
+    }
+
+    fn write(&mut self, write: bool) -> &mut Self {
+        self.inner.write(write);
+        self
+    }
+
+    fn append(&mut self, append: bool) -> &mut Self {
+        self.inner.append(append);
+        self
+    }
+
+    fn truncate(&mut self, truncate: bool) -> &mut Self {
+        self.inner.truncate(truncate);
+        self
+    }
+
+    fn create(&mut self, create: bool) -> &mut Self {
+        self.inner.create(create);
+        self
+    }
+
+    fn create_new(&mut self, create_new: bool) -> &mut Self {
+        self.inner.create_new(create_new);
+        self
+    }
+
+    fn direct(&mut self, direct: bool) -> &mut Self {
+        self.inner.direct(direct);
+        self
+    }
+
+    fn open<P: AsRef<Path>>(&self, path: P) -> Result<Self::File> {
+        let path = path.as_ref();
+
+        self.inner
+            .open(path)
+            .map_err(|err| context("open", path, err))
+    }
+}
+
+impl<T: UniDirBuilder> UniDirBuilder for ContextDirBuilder<T> {
+    fn recursive(&mut self, recursive: bool) -> &mut Self {
+        self.inner.recursive(recursive);
+        self
+    }
+
+    fn create<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+
+        self.inner
+            .create(path)
+            .map_err(|err| context("create_dir", path, err))
+    }
+}
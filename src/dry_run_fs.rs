@@ -0,0 +1,593 @@
+//! This module provides a wrapper that records mutations instead of performing them.
+
+use std::{
+    io::{Cursor, ErrorKind, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{
+    rw_lock::RwLock, Result, SpecialFileKind, UniDirBuilder, UniFile, UniFs, UniMetadata as _,
+    UniOpenOptions, UniPermissions,
+};
+
+/// A mutating operation recorded by a [`DryRunFs`] instead of being performed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlannedOp {
+    /// The entire contents of a file would be replaced by `len` bytes.
+    Write {
+        /// The path of the file.
+        path: PathBuf,
+        /// The number of bytes that would be written.
+        len: u64,
+    },
+    /// A file would be opened for writing, appending or truncating.
+    OpenWrite {
+        /// The path of the file.
+        path: PathBuf,
+        /// Whether the file would be truncated when opened.
+        truncate: bool,
+    },
+    /// A directory would be created.
+    CreateDir {
+        /// The path of the directory.
+        path: PathBuf,
+        /// Whether missing parents would be created as well.
+        recursive: bool,
+    },
+    /// A file would be removed.
+    RemoveFile {
+        /// The path of the file.
+        path: PathBuf,
+    },
+    /// An empty directory would be removed.
+    RemoveDir {
+        /// The path of the directory.
+        path: PathBuf,
+    },
+    /// A directory would be removed with all of its contents.
+    RemoveDirAll {
+        /// The path of the directory.
+        path: PathBuf,
+    },
+    /// A file or directory would be renamed.
+    Rename {
+        /// The current path.
+        from: PathBuf,
+        /// The new path.
+        to: PathBuf,
+    },
+    /// The contents of a file would be copied to another file.
+    Copy {
+        /// The path of the source file.
+        from: PathBuf,
+        /// The path of the destination file.
+        to: PathBuf,
+    },
+    /// A hard link would be created.
+    HardLink {
+        /// The path of the linked file.
+        original: PathBuf,
+        /// The path of the new link.
+        link: PathBuf,
+    },
+    /// A symbolic link would be created.
+    Symlink {
+        /// The target of the link.
+        original: PathBuf,
+        /// The path of the new link.
+        link: PathBuf,
+    },
+    /// The permissions of a file or directory would be changed.
+    SetPermissions {
+        /// The path of the file or directory.
+        path: PathBuf,
+        /// Whether the entry would be read-only.
+        readonly: bool,
+    },
+    /// A special file would be created.
+    CreateSpecial {
+        /// The path of the special file.
+        path: PathBuf,
+        /// The kind of the special file.
+        kind: SpecialFileKind,
+    },
+    /// The owner of a file or directory would be changed.
+    SetOwner {
+        /// The path of the file or directory.
+        path: PathBuf,
+        /// The new user ID, or `None` if it would be left unchanged.
+        uid: Option<u32>,
+        /// The new group ID, or `None` if it would be left unchanged.
+        gid: Option<u32>,
+    },
+}
+
+type Plan = Arc<RwLock<Vec<PlannedOp>>>;
+
+/// Wraps a filesystem to record mutating operations instead of performing them.
+///
+/// Every mutation is appended to a plan, retrievable with [`DryRunFs::planned`], and
+/// reported as successful without touching the wrapped filesystem. Read operations
+/// pass through, so they do not observe the planned changes. Unlike
+/// [`crate::ReadonlyFs`], which rejects mutations, this allows running a migration to
+/// see what it would do.
+///
+/// Files opened for writing are backed by an in-memory buffer, seeded with the current
+/// contents of the file unless it is truncated, so the writes to them are discarded.
+///
+/// # Example
+///
+/// ```
+/// use unifs::{dry_run_fs::PlannedOp, DryRunFs, MemoryFs, UniFs as _};
+///
+/// # fn main() -> unifs::Result<()> {
+/// let memory_fs = MemoryFs::default();
+/// memory_fs.write("/old.txt", "data")?;
+///
+/// let fs = DryRunFs::new(&memory_fs);
+/// fs.rename("/old.txt", "/new.txt")?;
+///
+/// assert!(memory_fs.exists("/old.txt")?);
+/// assert_eq!(
+///     fs.planned(),
+///     [PlannedOp::Rename {
+///         from: "/old.txt".into(),
+///         to: "/new.txt".into(),
+///     }]
+/// );
+/// # Ok(())
+/// # }
+/// ```
+pub struct DryRunFs<FS: UniFs> {
+    fs: FS,
+    plan: Plan,
+}
+
+/// Represents options for opening files in the dry-run filesystem.
+pub struct DryRunOpenOptions<O: UniOpenOptions> {
+    plan: Plan,
+    write: bool,
+    append: bool,
+    truncate: bool,
+    create: bool,
+    create_new: bool,
+    /// Always opens for reading only, as the real file is never written.
+    inner: O,
+}
+
+/// Represents a directory builder for the dry-run filesystem.
+pub struct DryRunDirBuilder {
+    plan: Plan,
+    recursive: bool,
+}
+
+/// Represents a file in the dry-run filesystem.
+#[derive(Debug)]
+pub struct DryRunFile<F: UniFile> {
+    path: PathBuf,
+    plan: Plan,
+    /// The real file, if it exists. It is only ever read from.
+    real: Option<F>,
+    /// The discarded contents of a file opened for writing.
+    buffer: Option<Arc<RwLock<Cursor<Vec<u8>>>>>,
+    append: bool,
+}
+
+fn not_writable(path: &Path) -> std::io::Error {
+    std::io::Error::new(
+        ErrorKind::PermissionDenied,
+        format!("File '{}' was not opened for writing", path.display()),
+    )
+}
+
+impl<FS: UniFs> DryRunFs<FS> {
+    /// Creates a new `DryRunFs` wrapping the specified filesystem, with an empty plan.
+    pub fn new(fs: FS) -> Self {
+        Self {
+            fs,
+            plan: Arc::new(RwLock::new(Vec::new())),
+        }
+    }
+
+    /// Returns the recorded operations in the order they were requested.
+    pub fn planned(&self) -> Vec<PlannedOp> {
+        self.plan.read().clone()
+    }
+
+    /// Returns the wrapped filesystem.
+    pub fn into_inner(self) -> FS {
+        self.fs
+    }
+
+    fn record(&self, op: PlannedOp) -> Result<()> {
+        self.plan.write().push(op);
+        Ok(())
+    }
+}
+
+impl<FS: UniFs> UniFs for DryRunFs<FS> {
+    type Metadata = FS::Metadata;
+    type ReadDir = FS::ReadDir;
+    type DirEntry = FS::DirEntry;
+    type Permissions = FS::Permissions;
+    type File = DryRunFile<FS::File>;
+    type OpenOptions = DryRunOpenOptions<FS::OpenOptions>;
+    type DirBuilder = DryRunDirBuilder;
+
+    fn canonicalize<P: AsRef<Path>>(&self, path: P) -> Result<PathBuf> {
+        self.fs.canonicalize(path)
+    }
+
+    /// Records copying a file and returns the length of the source file, or `0` if it
+    /// does not exist.
+    fn copy<P: AsRef<Path>, Q: AsRef<Path>>(&self, from: P, to: Q) -> Result<u64> {
+        let from = from.as_ref();
+        let len = self.fs.metadata(from).map_or(0, |metadata| metadata.len());
+        self.record(PlannedOp::Copy {
+            from: from.to_path_buf(),
+            to: to.as_ref().to_path_buf(),
+        })?;
+
+        Ok(len)
+    }
+
+    fn create_dir<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        self.record(PlannedOp::CreateDir {
+            path: path.as_ref().to_path_buf(),
+            recursive: false,
+        })
+    }
+
+    fn create_dir_all<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        self.record(PlannedOp::CreateDir {
+            path: path.as_ref().to_path_buf(),
+            recursive: true,
+        })
+    }
+
+    fn exists<P: AsRef<Path>>(&self, path: P) -> Result<bool> {
+        self.fs.exists(path)
+    }
+
+    fn hard_link<P: AsRef<Path>, Q: AsRef<Path>>(&self, original: P, link: Q) -> Result<()> {
+        self.record(PlannedOp::HardLink {
+            original: original.as_ref().to_path_buf(),
+            link: link.as_ref().to_path_buf(),
+        })
+    }
+
+    fn metadata<P: AsRef<Path>>(&self, path: P) -> Result<Self::Metadata> {
+        self.fs.metadata(path)
+    }
+
+    fn read<P: AsRef<Path>>(&self, path: P) -> Result<Vec<u8>> {
+        self.fs.read(path)
+    }
+
+    fn read_dir<P: AsRef<Path>>(&self, path: P) -> Result<Self::ReadDir> {
+        self.fs.read_dir(path)
+    }
+
+    fn read_link<P: AsRef<Path>>(&self, path: P) -> Result<PathBuf> {
+        self.fs.read_link(path)
+    }
+
+    fn read_to_string<P: AsRef<Path>>(&self, path: P) -> Result<String> {
+        self.fs.read_to_string(path)
+    }
+
+    fn remove_dir<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        self.record(PlannedOp::RemoveDir {
+            path: path.as_ref().to_path_buf(),
+        })
+    }
+
+    fn remove_dir_all<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        self.record(PlannedOp::RemoveDirAll {
+            path: path.as_ref().to_path_buf(),
+        })
+    }
+
+    fn remove_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        self.record(PlannedOp::RemoveFile {
+            path: path.as_ref().to_path_buf(),
+        })
+    }
+
+    fn rename<P: AsRef<Path>, Q: AsRef<Path>>(&self, from: P, to: Q) -> Result<()> {
+        self.record(PlannedOp::Rename {
+            from: from.as_ref().to_path_buf(),
+            to: to.as_ref().to_path_buf(),
+        })
+    }
+
+    fn set_permissions<P: AsRef<Path>>(&self, path: P, perm: Self::Permissions) -> Result<()> {
+        self.record(PlannedOp::SetPermissions {
+            path: path.as_ref().to_path_buf(),
+            readonly: perm.readonly(),
+        })
+    }
+
+    fn symlink_metadata<P: AsRef<Path>>(&self, path: P) -> Result<Self::Metadata> {
+        self.fs.symlink_metadata(path)
+    }
+
+    fn write<P: AsRef<Path>, C: AsRef<[u8]>>(&self, path: P, contents: C) -> Result<()> {
+        self.record(PlannedOp::Write {
+            path: path.as_ref().to_path_buf(),
+            len: contents.as_ref().len() as u64,
+        })
+    }
+
+    fn create_special<P: AsRef<Path>>(&self, path: P, kind: SpecialFileKind) -> Result<()> {
+        self.record(PlannedOp::CreateSpecial {
+            path: path.as_ref().to_path_buf(),
+            kind,
+        })
+    }
+
+    fn set_owner<P: AsRef<Path>>(&self, path: P, uid: Option<u32>, gid: Option<u32>) -> Result<()> {
+        self.record(PlannedOp::SetOwner {
+            path: path.as_ref().to_path_buf(),
+            uid,
+            gid,
+        })
+    }
+
+    fn symlink<P: AsRef<Path>, Q: AsRef<Path>>(&self, original: P, link: Q) -> Result<()> {
+        self.record(PlannedOp::Symlink {
+            original: original.as_ref().to_path_buf(),
+            link: link.as_ref().to_path_buf(),
+        })
+    }
+
+    fn physical_path<P: AsRef<Path>>(&self, path: P) -> Option<PathBuf> {
+        self.fs.physical_path(path)
+    }
+
+    fn new_openoptions(&self) -> Self::OpenOptions {
+        let mut inner = self.fs.new_openoptions();
+        inner.read(true);
+
+        DryRunOpenOptions {
+            plan: self.plan.clone(),
+            write: false,
+            append: false,
+            truncate: false,
+            create: false,
+            create_new: false,
+            inner,
+        }
+    }
+
+    fn new_dirbuilder(&self) -> Self::DirBuilder {
+        DryRunDirBuilder {
+            plan: self.plan.clone(),
+            recursive: false,
+        }
+    }
+}
+
+impl<O: UniOpenOptions> UniOpenOptions for DryRunOpenOptions<O> {
+    type File = DryRunFile<O::File>;
+
+    fn read(&mut self, _read: bool) -> &mut Self {
+        self
+    }
+
+    fn write(&mut self, write: bool) -> &mut Self {
+        self.write = write;
+        self
+    }
+
+    fn append(&mut self, append: bool) -> &mut Self {
+        self.append = append;
+        self
+    }
+
+    fn truncate(&mut self, truncate: bool) -> &mut Self {
+        self.truncate = truncate;
+        self
+    }
+
+    fn create(&mut self, create: bool) -> &mut Self {
+        self.create = create;
+        self
+    }
+
+    fn create_new(&mut self, create_new: bool) -> &mut Self {
+        self.create_new = create_new;
+        self
+    }
+
+    fn direct(&mut self, direct: bool) -> &mut Self {
+        self.inner.direct(direct);
+        self
+    }
+
+    /// Opens the real file for reading, or records opening it for writing.
+    ///
+    /// Opening for writing fails like it would on the wrapped filesystem if the file
+    /// exists but `create_new` is set, or does not exist and neither `create` nor
+    /// `create_new` is set.
+    fn open<P: AsRef<Path>>(&self, path: P) -> Result<Self::File> {
+        let path = path.as_ref();
+
+        if !self.write && !self.append {
+            return Ok(DryRunFile {
+                path: path.to_path_buf(),
+                plan: self.plan.clone(),
+                real: Some(self.inner.open(path)?),
+                buffer: None,
+                append: false,
+            });
+        }
+
+        let mut real = match self.inner.open(path) {
+            Ok(file) => Some(file),
+            Err(err) if err.kind() == ErrorKind::NotFound => None,
+            Err(err) => return Err(err),
+        };
+        if self.create_new && real.is_some() {
+            return Err(std::io::Error::new(
+                ErrorKind::AlreadyExists,
+                format!("File '{}' already exists", path.display()),
+            ));
+        }
+        if real.is_none() && !self.create && !self.create_new {
+            return Err(std::io::Error::new(
+                ErrorKind::NotFound,
+                format!("File '{}' does not exist", path.display()),
+            ));
+        }
+
+        let mut contents = Vec::new();
+        if let Some(real) = real.as_mut().filter(|_| !self.truncate) {
+            real.read_to_end(&mut contents)?;
+            real.seek(SeekFrom::Start(0))?;
+        }
+
+        self.plan.write().push(PlannedOp::OpenWrite {
+            path: path.to_path_buf(),
+            truncate: self.truncate,
+        });
+
+        Ok(DryRunFile {
+            path: path.to_path_buf(),
+            plan: self.plan.clone(),
+            real,
+            buffer: Some(Arc::new(RwLock::new(Cursor::new(contents)))),
+            append: self.append,
+        })
+    }
+}
+
+impl UniDirBuilder for DryRunDirBuilder {
+    fn recursive(&mut self, recursive: bool) -> &mut Self {
+        self.recursive = recursive;
+        self
+    }
+
+    fn create<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        self.plan.write().push(PlannedOp::CreateDir {
+            path: path.as_ref().to_path_buf(),
+            recursive: self.recursive,
+        });
+        Ok(())
+    }
+}
+
+impl<F: UniFile> Read for DryRunFile<F> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match (&self.buffer, &mut self.real) {
+            (Some(buffer), _) => buffer.write().read(buf),
+            (None, Some(real)) => real.read(buf),
+            (None, None) => Ok(0),
+        }
+    }
+}
+
+impl<F: UniFile> Seek for DryRunFile<F> {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        match (&self.buffer, &mut self.real) {
+            (Some(buffer), _) => buffer.write().seek(pos),
+            (None, Some(real)) => real.seek(pos),
+            (None, None) => Ok(0),
+        }
+    }
+}
+
+impl<F: UniFile> Write for DryRunFile<F> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let Some(buffer) = &self.buffer else {
+            return Err(not_writable(&self.path));
+        };
+        let mut buffer = buffer.write();
+        if self.append {
+            buffer.seek(SeekFrom::End(0))?;
+        }
+        buffer.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<F: UniFile> UniFile for DryRunFile<F> {
+    type Metadata = F::Metadata;
+    type Permissions = F::Permissions;
+    type FileTimes = F::FileTimes;
+
+    fn sync_all(&self) -> Result<()> {
+        match &self.real {
+            Some(real) if self.buffer.is_none() => real.sync_all(),
+            _ => Ok(()),
+        }
+    }
+
+    fn sync_data(&self) -> Result<()> {
+        match &self.real {
+            Some(real) if self.buffer.is_none() => real.sync_data(),
+            _ => Ok(()),
+        }
+    }
+
+    fn set_len(&self, size: u64) -> Result<()> {
+        let Some(buffer) = &self.buffer else {
+            return Err(not_writable(&self.path));
+        };
+        buffer.write().get_mut().resize(size as usize, 0);
+        Ok(())
+    }
+
+    /// Queries the metadata of the real file, which does not reflect planned writes.
+    ///
+    /// # Errors
+    /// - [`ErrorKind::NotFound`] if the file would only be created by the plan.
+    fn metadata(&self) -> Result<Self::Metadata> {
+        match &self.real {
+            Some(real) => real.metadata(),
+            None => Err(std::io::Error::new(
+                ErrorKind::NotFound,
+                format!("File '{}' does not exist yet", self.path.display()),
+            )),
+        }
+    }
+
+    fn try_clone(&self) -> Result<Self> {
+        Ok(DryRunFile {
+            path: self.path.clone(),
+            plan: self.plan.clone(),
+            real: self.real.as_ref().map(F::try_clone).transpose()?,
+            buffer: self.buffer.clone(),
+            append: self.append,
+        })
+    }
+
+    fn set_permissions(&self, perm: Self::Permissions) -> Result<()> {
+        self.plan.write().push(PlannedOp::SetPermissions {
+            path: self.path.clone(),
+            readonly: perm.readonly(),
+        });
+        Ok(())
+    }
+
+    fn set_times(&self, _times: Self::FileTimes) -> Result<()> {
+        Ok(())
+    }
+
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> Result<usize> {
+        match (&self.buffer, &self.real) {
+            (Some(buffer), _) => {
+                let buffer = buffer.read();
+                let mut data = Cursor::new(buffer.get_ref().as_slice());
+                data.set_position(offset);
+                data.read(buf)
+            }
+            (None, Some(real)) => real.read_at(buf, offset),
+            (None, None) => Ok(0),
+        }
+    }
+}
@@ -0,0 +1,171 @@
+//! This module provides the extraction of zip and tar archives into any filesystem.
+
+use std::{
+    io::{ErrorKind, Read},
+    path::{Component, Path, PathBuf},
+};
+
+use crate::{Result, UniFs, UniMetadata as _};
+
+/// Extracts the zip archive `archive` into the directory `dest` of `fs`.
+///
+/// Directories and files are created with the methods of `fs`, so the archive can be
+/// extracted onto the [`crate::PhysicalFs`] as well as into a sandbox like an
+/// [`crate::AltrootFs`]. `dest` and the missing directories of each entry are created,
+/// and existing files are overwritten. An existing symbolic link at the path of an entry
+/// is replaced by the entry instead of being followed.
+///
+/// # Errors
+/// - [`ErrorKind::InvalidData`] if the archive is malformed, or an entry would be
+///   written outside of `dest`, either because its path contains `..` or is absolute,
+///   or because it leads through a symbolic link. Entries before it are extracted
+///   already.
+/// - Any error of creating the directories and files.
+#[cfg(feature = "zip")]
+pub fn extract_zip<FS, R, P>(archive: R, fs: &FS, dest: P) -> Result<()>
+where
+    FS: UniFs,
+    R: Read + std::io::Seek,
+    P: AsRef<Path>,
+{
+    let dest = dest.as_ref();
+    let mut archive = zip::ZipArchive::new(archive).map_err(|err| {
+        std::io::Error::new(
+            ErrorKind::InvalidData,
+            format!("Failed to read zip archive: {}", err),
+        )
+    })?;
+    fs.create_dir_all(dest)?;
+
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i).map_err(|err| {
+            std::io::Error::new(
+                ErrorKind::InvalidData,
+                format!("Failed to read zip entry: {}", err),
+            )
+        })?;
+        let path = file
+            .enclosed_name()
+            .and_then(|name| entry_path(fs, dest, &name))
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Invalid path in zip archive: {}", file.name()),
+                )
+            })?;
+
+        remove_symlink(fs, &path)?;
+        if file.is_dir() {
+            fs.create_dir_all(&path)?;
+        } else {
+            if let Some(parent) = path.parent() {
+                fs.create_dir_all(parent)?;
+            }
+            let mut data = Vec::new();
+            file.read_to_end(&mut data)?;
+            fs.write(&path, data)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Extracts the tar archive `archive` into the directory `dest` of `fs`.
+///
+/// The archive is read as a stream. Directories, regular files and symbolic links are
+/// created with the methods of `fs`, other kinds of entries are skipped. `dest` and the
+/// missing directories of each entry are created, and existing files are overwritten.
+/// An existing symbolic link at the path of an entry, including one created by an
+/// earlier entry, is replaced by the entry instead of being followed. Times, permissions
+/// and owners are not applied.
+///
+/// # Errors
+/// - [`ErrorKind::InvalidData`] if the archive is malformed, or an entry would be
+///   written outside of `dest`, either because its path contains `..` or is absolute,
+///   or because it leads through a symbolic link. Entries before it are extracted
+///   already.
+/// - Any error of creating the directories, files and links.
+#[cfg(feature = "tar")]
+pub fn extract_tar<FS, R, P>(archive: R, fs: &FS, dest: P) -> Result<()>
+where
+    FS: UniFs,
+    R: Read,
+    P: AsRef<Path>,
+{
+    let dest = dest.as_ref();
+    let mut archive = tar::Archive::new(archive);
+    fs.create_dir_all(dest)?;
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let path = entry_path(fs, dest, &entry.path()?).ok_or_else(|| {
+            std::io::Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "Invalid path in tar archive: {}",
+                    String::from_utf8_lossy(&entry.path_bytes())
+                ),
+            )
+        })?;
+
+        remove_symlink(fs, &path)?;
+        let entry_type = entry.header().entry_type();
+        if entry_type.is_dir() {
+            fs.create_dir_all(&path)?;
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            fs.create_dir_all(parent)?;
+        }
+        if entry_type.is_file() {
+            let mut data = Vec::new();
+            entry.read_to_end(&mut data)?;
+            fs.write(&path, data)?;
+        } else if entry_type.is_symlink() {
+            let target = entry.link_name()?.ok_or_else(|| {
+                std::io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Missing link target in tar archive: {}", path.display()),
+                )
+            })?;
+            fs.symlink(target, &path)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the path of the archive entry `name` below `dest`, or `None` if it would
+/// escape `dest`.
+///
+/// Besides `..` and absolute paths, entries below a symbolic link are rejected, as an
+/// earlier entry of the archive may have created the link to point outside of `dest`.
+fn entry_path<FS: UniFs>(fs: &FS, dest: &Path, name: &Path) -> Option<PathBuf> {
+    let mut path = dest.to_path_buf();
+    for component in name.components() {
+        match component {
+            Component::Normal(part) => {
+                let is_symlink = path != dest
+                    && fs
+                        .symlink_metadata(&path)
+                        .is_ok_and(|metadata| metadata.is_symlink());
+                if is_symlink {
+                    return None;
+                }
+                path.push(part);
+            }
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    Some(path)
+}
+
+/// Removes the symbolic link at `path`, if there is one, so that the entry extracted to
+/// `path` replaces the link instead of being written to its target.
+fn remove_symlink<FS: UniFs>(fs: &FS, path: &Path) -> Result<()> {
+    match fs.symlink_metadata(path) {
+        Ok(metadata) if metadata.is_symlink() => fs.remove_file(path),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,873 @@
+//! Fallback file system module
+
+use std::{
+    collections::HashSet,
+    ffi::OsString,
+    fmt::Debug,
+    hash::{Hash, Hasher},
+    io::{ErrorKind, Read, Seek, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    SpecialFileKind, UniDirEntry, UniFile, UniFileTimes, UniFileType, UniFs, UniMetadata,
+    UniOpenOptions, UniPermissions,
+};
+
+/// A file system that reads from a primary file system and falls back to a
+/// secondary file system for paths that do not exist in the primary one.
+///
+/// Unlike [`crate::StackedFs`], no path rewriting takes place: both file systems
+/// are addressed with the same paths. All mutating operations are performed on the
+/// primary file system only, so entries that only exist in the fallback file system
+/// cannot be removed or renamed.
+pub struct FallbackFs<A, B>
+where
+    A: UniFs,
+    B: UniFs,
+{
+    primary: A,
+    fallback: B,
+}
+
+/// Metadata for a fallback file system, which can represent metadata from either the primary or fallback file system.
+pub enum FallbackMetadata<A, B>
+where
+    A: UniMetadata,
+    B: UniMetadata,
+{
+    /// Metadata from the primary file system.
+    Primary(A),
+    /// Metadata from the fallback file system.
+    Fallback(B),
+}
+
+/// Permissions for a fallback file system, which can represent permissions from either the primary or fallback file system.
+pub enum FallbackPermissions<A, B>
+where
+    A: UniPermissions,
+    B: UniPermissions,
+{
+    /// Permissions from the primary file system.
+    Primary(A),
+    /// Permissions from the fallback file system.
+    Fallback(B),
+}
+
+/// File type for a fallback file system, which can represent file types from either the primary or fallback file system.
+pub enum FallbackFileType<A, B>
+where
+    A: UniMetadata,
+    B: UniMetadata,
+{
+    /// File type from the primary file system.
+    Primary(A::FileType),
+    /// File type from the fallback file system.
+    Fallback(B::FileType),
+}
+
+/// Directory entry for a fallback file system, which can represent directory entries from either the primary or fallback file system.
+pub enum FallbackDirEntry<A, B>
+where
+    A: UniDirEntry,
+    B: UniDirEntry,
+{
+    /// Directory entry from the primary file system.
+    Primary(A),
+    /// Directory entry from the fallback file system.
+    Fallback(B),
+}
+
+/// Read directory iterator for a fallback file system, which merges the entries of
+/// the primary and fallback file systems.
+///
+/// Entries of the primary file system are yielded first. Entries of the fallback file
+/// system are skipped if an entry with the same name exists in the primary file system.
+pub struct FallbackReadDir<A, B>
+where
+    A: UniFs,
+    B: UniFs,
+{
+    primary: Option<A::ReadDir>,
+    fallback: Option<B::ReadDir>,
+    seen: HashSet<OsString>,
+}
+
+/// File for a fallback file system, which can represent files from either the primary or fallback file system.
+pub enum FallbackFile<A, B>
+where
+    A: UniFs,
+    B: UniFs,
+{
+    /// File from the primary file system.
+    Primary(A::File),
+    /// File from the fallback file system.
+    Fallback(B::File),
+}
+
+/// File times for a fallback file system, which can represent file times from either the primary or fallback file system.
+pub enum FallbackFileTimes<A, B>
+where
+    A: UniFileTimes,
+    B: UniFileTimes,
+{
+    /// File times from the primary file system.
+    Primary(A),
+    /// File times from the fallback file system.
+    Fallback(B),
+}
+
+/// Open options for a fallback file system, which contains open options for both the primary and fallback file systems.
+///
+/// Files opened with any kind of write access are always opened in the primary file system.
+pub struct FallbackOpenOptions<A, B>
+where
+    A: UniFs,
+    B: UniFs,
+{
+    primary: A::OpenOptions,
+    fallback: B::OpenOptions,
+    write: bool,
+    append: bool,
+    truncate: bool,
+    create: bool,
+    create_new: bool,
+}
+
+impl<A, B> FallbackFs<A, B>
+where
+    A: UniFs,
+    B: UniFs,
+{
+    /// Creates a new fallback file system with the given primary and fallback file systems.
+    pub fn new(primary: A, fallback: B) -> Self {
+        Self { primary, fallback }
+    }
+}
+
+impl<A, B> UniFs for FallbackFs<A, B>
+where
+    A: UniFs,
+    B: UniFs,
+{
+    type Metadata = FallbackMetadata<A::Metadata, B::Metadata>;
+    type ReadDir = FallbackReadDir<A, B>;
+    type DirEntry = FallbackDirEntry<A::DirEntry, B::DirEntry>;
+    type Permissions = FallbackPermissions<A::Permissions, B::Permissions>;
+    type File = FallbackFile<A, B>;
+    type OpenOptions = FallbackOpenOptions<A, B>;
+    type DirBuilder = A::DirBuilder;
+
+    fn canonicalize<P: AsRef<Path>>(&self, path: P) -> crate::Result<PathBuf> {
+        let path = path.as_ref();
+        if self.primary.exists(path)? {
+            self.primary.canonicalize(path)
+        } else {
+            self.fallback.canonicalize(path)
+        }
+    }
+
+    fn copy<P: AsRef<Path>, Q: AsRef<Path>>(&self, from: P, to: Q) -> crate::Result<u64> {
+        let from = from.as_ref();
+        let to = to.as_ref();
+        if self.primary.exists(from)? {
+            self.primary.copy(from, to)
+        } else {
+            let mut from_file = self.fallback.new_openoptions().read(true).open(from)?;
+            let mut to_file = self.primary.create_file(to)?;
+
+            std::io::copy(&mut from_file, &mut to_file)
+        }
+    }
+
+    fn create_dir<P: AsRef<Path>>(&self, path: P) -> crate::Result<()> {
+        self.primary.create_dir(path)
+    }
+
+    fn create_dir_all<P: AsRef<Path>>(&self, path: P) -> crate::Result<()> {
+        self.primary.create_dir_all(path)
+    }
+
+    fn create_special<P: AsRef<Path>>(&self, path: P, kind: SpecialFileKind) -> crate::Result<()> {
+        self.primary.create_special(path, kind)
+    }
+
+    fn set_owner<P: AsRef<Path>>(
+        &self,
+        path: P,
+        uid: Option<u32>,
+        gid: Option<u32>,
+    ) -> crate::Result<()> {
+        self.primary.set_owner(path, uid, gid)
+    }
+
+    fn symlink<P: AsRef<Path>, Q: AsRef<Path>>(&self, original: P, link: Q) -> crate::Result<()> {
+        self.primary.symlink(original, link)
+    }
+
+    fn exists<P: AsRef<Path>>(&self, path: P) -> crate::Result<bool> {
+        let path = path.as_ref();
+        if self.primary.exists(path)? {
+            return Ok(true);
+        }
+
+        self.fallback.exists(path)
+    }
+
+    fn hard_link<P: AsRef<Path>, Q: AsRef<Path>>(&self, original: P, link: Q) -> crate::Result<()> {
+        self.primary.hard_link(original, link)
+    }
+
+    fn metadata<P: AsRef<Path>>(&self, path: P) -> crate::Result<Self::Metadata> {
+        let path = path.as_ref();
+        if self.primary.exists(path)? {
+            return self.primary.metadata(path).map(FallbackMetadata::Primary);
+        }
+
+        self.fallback.metadata(path).map(FallbackMetadata::Fallback)
+    }
+
+    fn read<P: AsRef<Path>>(&self, path: P) -> crate::Result<Vec<u8>> {
+        let path = path.as_ref();
+        if self.primary.exists(path)? {
+            return self.primary.read(path);
+        }
+
+        self.fallback.read(path)
+    }
+
+    fn read_dir<P: AsRef<Path>>(&self, path: P) -> crate::Result<Self::ReadDir> {
+        let path = path.as_ref();
+        let primary = if self.primary.exists(path)? {
+            Some(self.primary.read_dir(path)?)
+        } else {
+            None
+        };
+        let fallback = match self.fallback.read_dir(path) {
+            Ok(read_dir) => Some(read_dir),
+            Err(err) if primary.is_none() || err.kind() != ErrorKind::NotFound => return Err(err),
+            Err(_) => None,
+        };
+
+        Ok(FallbackReadDir {
+            primary,
+            fallback,
+            seen: HashSet::new(),
+        })
+    }
+
+    fn physical_path<P: AsRef<Path>>(&self, path: P) -> Option<PathBuf> {
+        let path = path.as_ref();
+        if self.primary.exists(path).unwrap_or_default() {
+            self.primary.physical_path(path)
+        } else {
+            self.fallback.physical_path(path)
+        }
+    }
+
+    fn read_link<P: AsRef<Path>>(&self, path: P) -> crate::Result<PathBuf> {
+        let path = path.as_ref();
+        if self.primary.exists(path)? {
+            return self.primary.read_link(path);
+        }
+
+        self.fallback.read_link(path)
+    }
+
+    fn read_to_string<P: AsRef<Path>>(&self, path: P) -> crate::Result<String> {
+        let path = path.as_ref();
+        if self.primary.exists(path)? {
+            return self.primary.read_to_string(path);
+        }
+
+        self.fallback.read_to_string(path)
+    }
+
+    fn remove_dir<P: AsRef<Path>>(&self, path: P) -> crate::Result<()> {
+        self.primary.remove_dir(path)
+    }
+
+    fn remove_dir_all<P: AsRef<Path>>(&self, path: P) -> crate::Result<()> {
+        self.primary.remove_dir_all(path)
+    }
+
+    fn remove_file<P: AsRef<Path>>(&self, path: P) -> crate::Result<()> {
+        self.primary.remove_file(path)
+    }
+
+    fn rename<P: AsRef<Path>, Q: AsRef<Path>>(&self, from: P, to: Q) -> crate::Result<()> {
+        self.primary.rename(from, to)
+    }
+
+    fn set_permissions<P: AsRef<Path>>(
+        &self,
+        path: P,
+        perm: Self::Permissions,
+    ) -> crate::Result<()> {
+        match perm {
+            FallbackPermissions::Primary(perm) => self.primary.set_permissions(path, perm),
+            FallbackPermissions::Fallback(_) => Err(std::io::Error::other(
+                "Cannot set permissions in the fallback filesystem",
+            )),
+        }
+    }
+
+    fn symlink_metadata<P: AsRef<Path>>(&self, path: P) -> crate::Result<Self::Metadata> {
+        let path = path.as_ref();
+        if self.primary.exists(path)? {
+            return self
+                .primary
+                .symlink_metadata(path)
+                .map(FallbackMetadata::Primary);
+        }
+
+        self.fallback
+            .symlink_metadata(path)
+            .map(FallbackMetadata::Fallback)
+    }
+
+    fn write<P: AsRef<Path>, C: AsRef<[u8]>>(&self, path: P, contents: C) -> crate::Result<()> {
+        self.primary.write(path, contents)
+    }
+
+    fn new_openoptions(&self) -> Self::OpenOptions {
+        FallbackOpenOptions {
+            primary: self.primary.new_openoptions(),
+            fallback: self.fallback.new_openoptions(),
+            write: false,
+            append: false,
+            truncate: false,
+            create: false,
+            create_new: false,
+        }
+    }
+
+    fn new_dirbuilder(&self) -> Self::DirBuilder {
+        self.primary.new_dirbuilder()
+    }
+}
+
+impl<A, B> UniMetadata for FallbackMetadata<A, B>
+where
+    A: UniMetadata,
+    B: UniMetadata,
+{
+    type Permissions = FallbackPermissions<A::Permissions, B::Permissions>;
+    type FileType = FallbackFileType<A, B>;
+
+    fn file_type(&self) -> Self::FileType {
+        match self {
+            FallbackMetadata::Primary(meta) => FallbackFileType::Primary(meta.file_type()),
+            FallbackMetadata::Fallback(meta) => FallbackFileType::Fallback(meta.file_type()),
+        }
+    }
+
+    fn is_dir(&self) -> bool {
+        match self {
+            FallbackMetadata::Primary(meta) => meta.is_dir(),
+            FallbackMetadata::Fallback(meta) => meta.is_dir(),
+        }
+    }
+
+    fn is_file(&self) -> bool {
+        match self {
+            FallbackMetadata::Primary(meta) => meta.is_file(),
+            FallbackMetadata::Fallback(meta) => meta.is_file(),
+        }
+    }
+
+    fn is_symlink(&self) -> bool {
+        match self {
+            FallbackMetadata::Primary(meta) => meta.is_symlink(),
+            FallbackMetadata::Fallback(meta) => meta.is_symlink(),
+        }
+    }
+
+    fn len(&self) -> u64 {
+        match self {
+            FallbackMetadata::Primary(meta) => meta.len(),
+            FallbackMetadata::Fallback(meta) => meta.len(),
+        }
+    }
+
+    fn permissions(&self) -> Self::Permissions {
+        match self {
+            FallbackMetadata::Primary(meta) => FallbackPermissions::Primary(meta.permissions()),
+            FallbackMetadata::Fallback(meta) => FallbackPermissions::Fallback(meta.permissions()),
+        }
+    }
+
+    fn modified(&self) -> crate::Result<std::time::SystemTime> {
+        match self {
+            FallbackMetadata::Primary(meta) => meta.modified(),
+            FallbackMetadata::Fallback(meta) => meta.modified(),
+        }
+    }
+
+    fn accessed(&self) -> crate::Result<std::time::SystemTime> {
+        match self {
+            FallbackMetadata::Primary(meta) => meta.accessed(),
+            FallbackMetadata::Fallback(meta) => meta.accessed(),
+        }
+    }
+
+    fn created(&self) -> crate::Result<std::time::SystemTime> {
+        match self {
+            FallbackMetadata::Primary(meta) => meta.created(),
+            FallbackMetadata::Fallback(meta) => meta.created(),
+        }
+    }
+
+    fn nlink(&self) -> Option<u64> {
+        match self {
+            FallbackMetadata::Primary(meta) => meta.nlink(),
+            FallbackMetadata::Fallback(meta) => meta.nlink(),
+        }
+    }
+
+    fn uid(&self) -> Option<u32> {
+        match self {
+            FallbackMetadata::Primary(meta) => meta.uid(),
+            FallbackMetadata::Fallback(meta) => meta.uid(),
+        }
+    }
+
+    fn gid(&self) -> Option<u32> {
+        match self {
+            FallbackMetadata::Primary(meta) => meta.gid(),
+            FallbackMetadata::Fallback(meta) => meta.gid(),
+        }
+    }
+}
+
+impl<A, B> PartialEq for FallbackPermissions<A, B>
+where
+    A: UniPermissions,
+    B: UniPermissions,
+{
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (FallbackPermissions::Primary(a), FallbackPermissions::Primary(b)) => a == b,
+            (FallbackPermissions::Fallback(a), FallbackPermissions::Fallback(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl<A, B> Eq for FallbackPermissions<A, B>
+where
+    A: UniPermissions,
+    B: UniPermissions,
+{
+}
+
+impl<A, B> Hash for FallbackPermissions<A, B>
+where
+    A: UniPermissions + Hash,
+    B: UniPermissions + Hash,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            FallbackPermissions::Primary(perm) => perm.hash(state),
+            FallbackPermissions::Fallback(perm) => perm.hash(state),
+        }
+    }
+}
+
+impl<A, B> UniPermissions for FallbackPermissions<A, B>
+where
+    A: UniPermissions,
+    B: UniPermissions,
+{
+    fn readonly(&self) -> bool {
+        match self {
+            FallbackPermissions::Primary(perm) => perm.readonly(),
+            FallbackPermissions::Fallback(perm) => perm.readonly(),
+        }
+    }
+
+    fn set_readonly(&mut self, readonly: bool) {
+        match self {
+            FallbackPermissions::Primary(perm) => perm.set_readonly(readonly),
+            FallbackPermissions::Fallback(perm) => perm.set_readonly(readonly),
+        }
+    }
+}
+
+impl<A, B> UniFileType for FallbackFileType<A, B>
+where
+    A: UniMetadata,
+    B: UniMetadata,
+{
+    fn is_dir(&self) -> bool {
+        match self {
+            FallbackFileType::Primary(ft) => ft.is_dir(),
+            FallbackFileType::Fallback(ft) => ft.is_dir(),
+        }
+    }
+
+    fn is_file(&self) -> bool {
+        match self {
+            FallbackFileType::Primary(ft) => ft.is_file(),
+            FallbackFileType::Fallback(ft) => ft.is_file(),
+        }
+    }
+
+    fn is_symlink(&self) -> bool {
+        match self {
+            FallbackFileType::Primary(ft) => ft.is_symlink(),
+            FallbackFileType::Fallback(ft) => ft.is_symlink(),
+        }
+    }
+}
+
+impl<A, B> UniDirEntry for FallbackDirEntry<A, B>
+where
+    A: UniDirEntry,
+    B: UniDirEntry,
+{
+    type Metadata = FallbackMetadata<A::Metadata, B::Metadata>;
+    type FileType = FallbackFileType<A::Metadata, B::Metadata>;
+
+    fn path(&self) -> PathBuf {
+        match self {
+            FallbackDirEntry::Primary(entry) => entry.path(),
+            FallbackDirEntry::Fallback(entry) => entry.path(),
+        }
+    }
+
+    fn metadata(&self) -> crate::Result<Self::Metadata> {
+        match self {
+            FallbackDirEntry::Primary(entry) => entry.metadata().map(FallbackMetadata::Primary),
+            FallbackDirEntry::Fallback(entry) => entry.metadata().map(FallbackMetadata::Fallback),
+        }
+    }
+
+    fn file_type(&self) -> crate::Result<Self::FileType> {
+        match self {
+            FallbackDirEntry::Primary(entry) => entry.file_type().map(FallbackFileType::Primary),
+            FallbackDirEntry::Fallback(entry) => entry.file_type().map(FallbackFileType::Fallback),
+        }
+    }
+
+    fn file_name(&self) -> OsString {
+        match self {
+            FallbackDirEntry::Primary(entry) => entry.file_name(),
+            FallbackDirEntry::Fallback(entry) => entry.file_name(),
+        }
+    }
+}
+
+impl<A, B> Iterator for FallbackReadDir<A, B>
+where
+    A: UniFs,
+    B: UniFs,
+{
+    type Item = crate::Result<FallbackDirEntry<A::DirEntry, B::DirEntry>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(primary) = &mut self.primary {
+            match primary.next() {
+                Some(Ok(entry)) => {
+                    self.seen.insert(entry.file_name());
+                    return Some(Ok(FallbackDirEntry::Primary(entry)));
+                }
+                Some(Err(err)) => return Some(Err(err)),
+                None => self.primary = None,
+            }
+        }
+
+        let fallback = self.fallback.as_mut()?;
+        loop {
+            match fallback.next()? {
+                Ok(entry) if self.seen.contains(&entry.file_name()) => continue,
+                res => return Some(res.map(FallbackDirEntry::Fallback)),
+            }
+        }
+    }
+}
+
+impl<A, B> Debug for FallbackFile<A, B>
+where
+    A: UniFs,
+    B: UniFs,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FallbackFile::Primary(file) => f.debug_tuple("Primary").field(file).finish(),
+            FallbackFile::Fallback(file) => f.debug_tuple("Fallback").field(file).finish(),
+        }
+    }
+}
+
+impl<A, B> Read for FallbackFile<A, B>
+where
+    A: UniFs,
+    B: UniFs,
+{
+    fn read(&mut self, buf: &mut [u8]) -> crate::Result<usize> {
+        match self {
+            FallbackFile::Primary(file) => file.read(buf),
+            FallbackFile::Fallback(file) => file.read(buf),
+        }
+    }
+}
+
+impl<A, B> Write for FallbackFile<A, B>
+where
+    A: UniFs,
+    B: UniFs,
+{
+    fn write(&mut self, buf: &[u8]) -> crate::Result<usize> {
+        match self {
+            FallbackFile::Primary(file) => file.write(buf),
+            FallbackFile::Fallback(file) => file.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> crate::Result<()> {
+        match self {
+            FallbackFile::Primary(file) => file.flush(),
+            FallbackFile::Fallback(file) => file.flush(),
+        }
+    }
+}
+
+impl<A, B> Seek for FallbackFile<A, B>
+where
+    A: UniFs,
+    B: UniFs,
+{
+    fn seek(&mut self, pos: std::io::SeekFrom) -> crate::Result<u64> {
+        match self {
+            FallbackFile::Primary(file) => file.seek(pos),
+            FallbackFile::Fallback(file) => file.seek(pos),
+        }
+    }
+}
+
+impl<A, B> UniFile for FallbackFile<A, B>
+where
+    A: UniFs,
+    B: UniFs,
+{
+    type Metadata = FallbackMetadata<A::Metadata, B::Metadata>;
+    type Permissions = FallbackPermissions<A::Permissions, B::Permissions>;
+    type FileTimes =
+        FallbackFileTimes<<A::File as UniFile>::FileTimes, <B::File as UniFile>::FileTimes>;
+
+    fn sync_all(&self) -> crate::Result<()> {
+        match self {
+            FallbackFile::Primary(file) => file.sync_all(),
+            FallbackFile::Fallback(file) => file.sync_all(),
+        }
+    }
+
+    fn sync_data(&self) -> crate::Result<()> {
+        match self {
+            FallbackFile::Primary(file) => file.sync_data(),
+            FallbackFile::Fallback(file) => file.sync_data(),
+        }
+    }
+
+    fn set_len(&self, size: u64) -> crate::Result<()> {
+        match self {
+            FallbackFile::Primary(file) => file.set_len(size),
+            FallbackFile::Fallback(file) => file.set_len(size),
+        }
+    }
+
+    fn metadata(&self) -> crate::Result<Self::Metadata> {
+        match self {
+            FallbackFile::Primary(file) => file.metadata().map(FallbackMetadata::Primary),
+            FallbackFile::Fallback(file) => file.metadata().map(FallbackMetadata::Fallback),
+        }
+    }
+
+    fn try_clone(&self) -> crate::Result<Self> {
+        match self {
+            FallbackFile::Primary(file) => file.try_clone().map(FallbackFile::Primary),
+            FallbackFile::Fallback(file) => file.try_clone().map(FallbackFile::Fallback),
+        }
+    }
+
+    fn set_permissions(&self, perm: Self::Permissions) -> crate::Result<()> {
+        match (self, perm) {
+            (FallbackFile::Primary(file), FallbackPermissions::Primary(perm)) => {
+                file.set_permissions(perm)
+            }
+            (FallbackFile::Fallback(file), FallbackPermissions::Fallback(perm)) => {
+                file.set_permissions(perm)
+            }
+            _ => Err(std::io::Error::other(
+                "Permission type does not match file type",
+            )),
+        }
+    }
+
+    fn set_times(&self, times: Self::FileTimes) -> crate::Result<()> {
+        match (self, times) {
+            (FallbackFile::Primary(file), FallbackFileTimes::Primary(times)) => {
+                file.set_times(times)
+            }
+            (FallbackFile::Fallback(file), FallbackFileTimes::Fallback(times)) => {
+                file.set_times(times)
+            }
+            _ => Err(std::io::Error::other(
+                "FileTimes type does not match file type",
+            )),
+        }
+    }
+
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> crate::Result<usize> {
+        match self {
+            FallbackFile::Primary(file) => file.read_at(buf, offset),
+            FallbackFile::Fallback(file) => file.read_at(buf, offset),
+        }
+    }
+
+    fn lock_exclusive(&self) -> crate::Result<()> {
+        match self {
+            FallbackFile::Primary(file) => file.lock_exclusive(),
+            FallbackFile::Fallback(file) => file.lock_exclusive(),
+        }
+    }
+
+    fn lock_shared(&self) -> crate::Result<()> {
+        match self {
+            FallbackFile::Primary(file) => file.lock_shared(),
+            FallbackFile::Fallback(file) => file.lock_shared(),
+        }
+    }
+
+    fn try_lock_exclusive(&self) -> crate::Result<()> {
+        match self {
+            FallbackFile::Primary(file) => file.try_lock_exclusive(),
+            FallbackFile::Fallback(file) => file.try_lock_exclusive(),
+        }
+    }
+
+    fn try_lock_shared(&self) -> crate::Result<()> {
+        match self {
+            FallbackFile::Primary(file) => file.try_lock_shared(),
+            FallbackFile::Fallback(file) => file.try_lock_shared(),
+        }
+    }
+
+    fn unlock(&self) -> crate::Result<()> {
+        match self {
+            FallbackFile::Primary(file) => file.unlock(),
+            FallbackFile::Fallback(file) => file.unlock(),
+        }
+    }
+}
+
+impl<A, B> Default for FallbackFileTimes<A, B>
+where
+    A: UniFileTimes,
+    B: UniFileTimes,
+{
+    fn default() -> Self {
+        FallbackFileTimes::Primary(A::default())
+    }
+}
+
+impl<A, B> UniFileTimes for FallbackFileTimes<A, B>
+where
+    A: UniFileTimes,
+    B: UniFileTimes,
+{
+    fn set_accessed(self, t: std::time::SystemTime) -> Self {
+        match self {
+            Self::Primary(at) => Self::Primary(at.set_accessed(t)),
+            Self::Fallback(bt) => Self::Fallback(bt.set_accessed(t)),
+        }
+    }
+
+    fn set_modified(self, t: std::time::SystemTime) -> Self {
+        match self {
+            Self::Primary(at) => Self::Primary(at.set_modified(t)),
+            Self::Fallback(bt) => Self::Fallback(bt.set_modified(t)),
+        }
+    }
+}
+
+impl<A, B> FallbackOpenOptions<A, B>
+where
+    A: UniFs,
+    B: UniFs,
+{
+    /// Returns whether the options, as currently set, may modify the file.
+    fn mutating(&self) -> bool {
+        self.write || self.append || self.truncate || self.create || self.create_new
+    }
+}
+
+impl<A, B> UniOpenOptions for FallbackOpenOptions<A, B>
+where
+    A: UniFs,
+    B: UniFs,
+{
+    type File = FallbackFile<A, B>;
+
+    fn read(&mut self, read: bool) -> &mut Self {
+        self.primary.read(read);
+        self.fallback.read(read);
+
+        self
+    }
+
+    fn write(&mut self, write: bool) -> &mut Self {
+        self.primary.write(write);
+        self.write = write;
+
+        self
+    }
+
+    fn append(&mut self, append: bool) -> &mut Self {
+        self.primary.append(append);
+        self.append = append;
+
+        self
+    }
+
+    fn truncate(&mut self, truncate: bool) -> &mut Self {
+        self.primary.truncate(truncate);
+        self.truncate = truncate;
+
+        self
+    }
+
+    fn create(&mut self, create: bool) -> &mut Self {
+        self.primary.create(create);
+        self.create = create;
+
+        self
+    }
+
+    fn create_new(&mut self, create_new: bool) -> &mut Self {
+        self.primary.create_new(create_new);
+        self.create_new = create_new;
+
+        self
+    }
+
+    fn direct(&mut self, direct: bool) -> &mut Self {
+        self.primary.direct(direct);
+        self.fallback.direct(direct);
+
+        self
+    }
+
+    fn open<P: AsRef<Path>>(&self, path: P) -> crate::Result<Self::File> {
+        let path = path.as_ref();
+        match self.primary.open(path) {
+            Ok(file) => Ok(FallbackFile::Primary(file)),
+            Err(err) if !self.mutating() && err.kind() == ErrorKind::NotFound => {
+                self.fallback.open(path).map(FallbackFile::Fallback)
+            }
+            Err(err) => Err(err),
+        }
+    }
+}
@@ -0,0 +1,513 @@
+//! This module provides a wrapper that hides everything below certain path prefixes.
+
+use std::{
+    io::ErrorKind,
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{
+    Result, SpecialFileKind, UniDirBuilder, UniDirEntry, UniFs, UniOpenOptions, WriteReport,
+};
+
+/// Wraps a filesystem to make everything below a set of hidden path prefixes appear
+/// absent.
+///
+/// Hidden paths do not exist as far as reading is concerned: [`UniFs::exists`]
+/// returns `false`, directory listings omit them, and reading them fails with
+/// [`ErrorKind::NotFound`]. Modifying them fails with [`ErrorKind::PermissionDenied`].
+///
+/// Paths are normalized lexically and resolved through the wrapped filesystem before
+/// they are compared with the hidden prefixes, so `..` components and symbolic links
+/// cannot be used to reach hidden paths. Hard links to hidden files cannot be created
+/// through this wrapper, but hard links that already exist outside the hidden prefixes
+/// are out of scope: they are separate paths and stay visible. Symbolic links whose
+/// target is hidden cannot be created either.
+///
+/// # Example
+///
+/// ```
+/// use unifs::{HideFs, MemoryFs, UniFs as _};
+///
+/// # fn main() -> unifs::Result<()> {
+/// let memory_fs = MemoryFs::default();
+/// memory_fs.create_dir_all("/tenants/b")?;
+/// memory_fs.write("/tenants/b/secret.txt", "secret")?;
+///
+/// let fs = HideFs::new(&memory_fs, ["/tenants/b"]);
+/// assert!(!fs.exists("/tenants/b/secret.txt")?);
+/// assert!(fs.read("/tenants/a/../b/secret.txt").is_err());
+/// # Ok(())
+/// # }
+/// ```
+pub struct HideFs<FS: UniFs> {
+    fs: Arc<FS>,
+    hidden: Arc<[PathBuf]>,
+}
+
+/// Represents a directory iterator for the hiding filesystem.
+pub struct HideReadDir<FS: UniFs> {
+    hidden: Arc<[PathBuf]>,
+    inner: FS::ReadDir,
+}
+
+/// Represents options for opening files in the hiding filesystem.
+pub struct HideOpenOptions<FS: UniFs> {
+    fs: Arc<FS>,
+    hidden: Arc<[PathBuf]>,
+    mutating: bool,
+    inner: FS::OpenOptions,
+}
+
+/// Represents a directory builder for the hiding filesystem.
+pub struct HideDirBuilder<FS: UniFs> {
+    fs: Arc<FS>,
+    hidden: Arc<[PathBuf]>,
+    inner: FS::DirBuilder,
+}
+
+/// Resolves `.` and `..` components without accessing the filesystem.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+fn is_hidden_lexically(hidden: &[PathBuf], path: &Path) -> bool {
+    let path = normalize(path);
+    hidden.iter().any(|prefix| path.starts_with(prefix))
+}
+
+/// Returns whether `path` is below one of the `hidden` prefixes, either lexically or
+/// once the nearest existing ancestor is resolved through `fs`.
+fn is_hidden<FS: UniFs>(fs: &FS, hidden: &[PathBuf], path: &Path) -> bool {
+    if is_hidden_lexically(hidden, path) {
+        return true;
+    }
+
+    // Resolve the nearest existing ancestor, which also covers relative paths and links.
+    for ancestor in path.ancestors() {
+        let resolvable = if ancestor.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            ancestor
+        };
+        if let Ok(canonical) = fs.canonicalize(resolvable) {
+            let rest = path.strip_prefix(ancestor).unwrap_or(path);
+            return is_hidden_lexically(hidden, &canonical.join(rest));
+        }
+    }
+
+    false
+}
+
+fn not_found(path: &Path) -> std::io::Error {
+    std::io::Error::new(
+        ErrorKind::NotFound,
+        format!("Path '{}' does not exist", path.display()),
+    )
+}
+
+fn permission_denied(path: &Path) -> std::io::Error {
+    std::io::Error::new(
+        ErrorKind::PermissionDenied,
+        format!("Path '{}' is not accessible", path.display()),
+    )
+}
+
+impl<FS: UniFs> HideFs<FS> {
+    /// Creates a new `HideFs` wrapping the specified filesystem, hiding every path
+    /// below one of the `hidden` prefixes.
+    pub fn new<I, P>(fs: FS, hidden: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        Self {
+            fs: Arc::new(fs),
+            hidden: hidden
+                .into_iter()
+                .map(|prefix| normalize(prefix.as_ref()))
+                .collect(),
+        }
+    }
+
+    /// Returns the wrapped filesystem.
+    ///
+    /// Open options and directory builders created by this filesystem share the wrapped
+    /// filesystem, so `None` is returned while any of them is still alive.
+    pub fn into_inner(self) -> Option<FS> {
+        Arc::into_inner(self.fs)
+    }
+
+    fn is_hidden(&self, path: &Path) -> bool {
+        is_hidden(&*self.fs, &self.hidden, path)
+    }
+
+    fn check_read(&self, path: &Path) -> Result<()> {
+        if self.is_hidden(path) {
+            Err(not_found(path))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn check_write(&self, path: &Path) -> Result<()> {
+        if self.is_hidden(path) {
+            Err(permission_denied(path))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl<FS: UniFs> UniFs for HideFs<FS> {
+    type Metadata = FS::Metadata;
+    type ReadDir = HideReadDir<FS>;
+    type DirEntry = FS::DirEntry;
+    type Permissions = FS::Permissions;
+    type File = FS::File;
+    type OpenOptions = HideOpenOptions<FS>;
+    type DirBuilder = HideDirBuilder<FS>;
+
+    fn canonicalize<P: AsRef<Path>>(&self, path: P) -> Result<PathBuf> {
+        let path = path.as_ref();
+        self.check_read(path)?;
+
+        self.fs.canonicalize(path)
+    }
+
+    fn copy<P: AsRef<Path>, Q: AsRef<Path>>(&self, from: P, to: Q) -> Result<u64> {
+        let from = from.as_ref();
+        let to = to.as_ref();
+        self.check_read(from)?;
+        self.check_write(to)?;
+
+        self.fs.copy(from, to)
+    }
+
+    fn create_dir<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        self.check_write(path)?;
+
+        self.fs.create_dir(path)
+    }
+
+    fn create_dir_all<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        self.check_write(path)?;
+
+        self.fs.create_dir_all(path)
+    }
+
+    fn exists<P: AsRef<Path>>(&self, path: P) -> Result<bool> {
+        let path = path.as_ref();
+        if self.is_hidden(path) {
+            return Ok(false);
+        }
+
+        self.fs.exists(path)
+    }
+
+    fn hard_link<P: AsRef<Path>, Q: AsRef<Path>>(&self, original: P, link: Q) -> Result<()> {
+        let original = original.as_ref();
+        let link = link.as_ref();
+        self.check_read(original)?;
+        self.check_write(link)?;
+
+        self.fs.hard_link(original, link)
+    }
+
+    fn metadata<P: AsRef<Path>>(&self, path: P) -> Result<Self::Metadata> {
+        let path = path.as_ref();
+        self.check_read(path)?;
+
+        self.fs.metadata(path)
+    }
+
+    fn read<P: AsRef<Path>>(&self, path: P) -> Result<Vec<u8>> {
+        let path = path.as_ref();
+        self.check_read(path)?;
+
+        self.fs.read(path)
+    }
+
+    fn read_dir<P: AsRef<Path>>(&self, path: P) -> Result<Self::ReadDir> {
+        let path = path.as_ref();
+        self.check_read(path)?;
+
+        self.fs.read_dir(path).map(|inner| HideReadDir {
+            hidden: self.hidden.clone(),
+            inner,
+        })
+    }
+
+    fn read_link<P: AsRef<Path>>(&self, path: P) -> Result<PathBuf> {
+        let path = path.as_ref();
+        self.check_read(path)?;
+
+        self.fs.read_link(path)
+    }
+
+    fn read_to_string<P: AsRef<Path>>(&self, path: P) -> Result<String> {
+        let path = path.as_ref();
+        self.check_read(path)?;
+
+        self.fs.read_to_string(path)
+    }
+
+    fn remove_dir<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        self.check_write(path)?;
+
+        self.fs.remove_dir(path)
+    }
+
+    fn remove_dir_all<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        self.check_write(path)?;
+        // Removing an ancestor would remove the hidden paths below it as well.
+        let normalized = normalize(path);
+        if self
+            .hidden
+            .iter()
+            .any(|prefix| prefix.starts_with(&normalized))
+        {
+            return Err(permission_denied(path));
+        }
+
+        self.fs.remove_dir_all(path)
+    }
+
+    fn remove_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        self.check_write(path)?;
+
+        self.fs.remove_file(path)
+    }
+
+    fn rename<P: AsRef<Path>, Q: AsRef<Path>>(&self, from: P, to: Q) -> Result<()> {
+        let from = from.as_ref();
+        let to = to.as_ref();
+        self.check_write(from)?;
+        self.check_write(to)?;
+        // Renaming an ancestor would move the hidden paths below it as well.
+        let normalized = normalize(from);
+        if self
+            .hidden
+            .iter()
+            .any(|prefix| prefix.starts_with(&normalized))
+        {
+            return Err(permission_denied(from));
+        }
+
+        self.fs.rename(from, to)
+    }
+
+    fn set_permissions<P: AsRef<Path>>(&self, path: P, perm: Self::Permissions) -> Result<()> {
+        let path = path.as_ref();
+        self.check_write(path)?;
+
+        self.fs.set_permissions(path, perm)
+    }
+
+    fn symlink_metadata<P: AsRef<Path>>(&self, path: P) -> Result<Self::Metadata> {
+        let path = path.as_ref();
+        self.check_read(path)?;
+
+        self.fs.symlink_metadata(path)
+    }
+
+    fn write<P: AsRef<Path>, C: AsRef<[u8]>>(&self, path: P, contents: C) -> Result<()> {
+        let path = path.as_ref();
+        self.check_write(path)?;
+
+        self.fs.write(path, contents)
+    }
+
+    fn write_reporting<P: AsRef<Path>, C: AsRef<[u8]>>(
+        &self,
+        path: P,
+        contents: C,
+    ) -> Result<WriteReport> {
+        let path = path.as_ref();
+        self.check_write(path)?;
+
+        self.fs.write_reporting(path, contents)
+    }
+
+    fn create_special<P: AsRef<Path>>(&self, path: P, kind: SpecialFileKind) -> Result<()> {
+        let path = path.as_ref();
+        self.check_write(path)?;
+
+        self.fs.create_special(path, kind)
+    }
+
+    fn set_owner<P: AsRef<Path>>(&self, path: P, uid: Option<u32>, gid: Option<u32>) -> Result<()> {
+        let path = path.as_ref();
+        self.check_write(path)?;
+
+        self.fs.set_owner(path, uid, gid)
+    }
+
+    fn symlink<P: AsRef<Path>, Q: AsRef<Path>>(&self, original: P, link: Q) -> Result<()> {
+        let original = original.as_ref();
+        let link = link.as_ref();
+        self.check_write(link)?;
+
+        // Relative targets are resolved against the directory containing the link.
+        let target = match link.parent() {
+            Some(parent) => parent.join(original),
+            None => original.to_path_buf(),
+        };
+        if self.is_hidden(&target) {
+            return Err(permission_denied(link));
+        }
+
+        self.fs.symlink(original, link)
+    }
+
+    fn physical_path<P: AsRef<Path>>(&self, path: P) -> Option<PathBuf> {
+        let path = path.as_ref();
+        if self.is_hidden(path) {
+            return None;
+        }
+
+        self.fs.physical_path(path)
+    }
+
+    fn open_file<P: AsRef<Path>>(&self, path: P) -> Result<Self::File> {
+        let path = path.as_ref();
+        self.check_read(path)?;
+
+        self.fs.open_file(path)
+    }
+
+    fn create_file<P: AsRef<Path>>(&self, path: P) -> Result<Self::File> {
+        let path = path.as_ref();
+        self.check_write(path)?;
+
+        self.fs.create_file(path)
+    }
+
+    fn create_new_file<P: AsRef<Path>>(&self, path: P) -> Result<Self::File> {
+        let path = path.as_ref();
+        self.check_write(path)?;
+
+        self.fs.create_new_file(path)
+    }
+
+    fn new_openoptions(&self) -> Self::OpenOptions {
+        HideOpenOptions {
+            fs: Arc::clone(&self.fs),
+            hidden: self.hidden.clone(),
+            mutating: false,
+            inner: self.fs.new_openoptions(),
+        }
+    }
+
+    fn new_dirbuilder(&self) -> Self::DirBuilder {
+        HideDirBuilder {
+            fs: Arc::clone(&self.fs),
+            hidden: self.hidden.clone(),
+            inner: self.fs.new_dirbuilder(),
+        }
+    }
+}
+
+impl<FS> Iterator for HideReadDir<FS>
+where
+    FS: UniFs,
+{
+    type Item = Result<FS::DirEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.inner.next()? {
+                Ok(entry) if is_hidden_lexically(&self.hidden, &entry.path()) => continue,
+                res => return Some(res),
+            }
+        }
+    }
+}
+
+impl<FS: UniFs> UniOpenOptions for HideOpenOptions<FS> {
+    type File = FS::File;
+
+    fn read(&mut self, read: bool) -> &mut Self {
+        self.inner.read(read);
+        self
+    }
+
+    fn write(&mut self, write: bool) -> &mut Self {
+        self.inner.write(write);
+        self.mutating |= write;
+        self
+    }
+
+    fn append(&mut self, append: bool) -> &mut Self {
+        self.inner.append(append);
+        self.mutating |= append;
+        self
+    }
+
+    fn truncate(&mut self, truncate: bool) -> &mut Self {
+        self.inner.truncate(truncate);
+        self.mutating |= truncate;
+        self
+    }
+
+    fn create(&mut self, create: bool) -> &mut Self {
+        self.inner.create(create);
+        self.mutating |= create;
+        self
+    }
+
+    fn create_new(&mut self, create_new: bool) -> &mut Self {
+        self.inner.create_new(create_new);
+        self.mutating |= create_new;
+        self
+    }
+
+    fn direct(&mut self, direct: bool) -> &mut Self {
+        self.inner.direct(direct);
+        self
+    }
+
+    fn open<P: AsRef<Path>>(&self, path: P) -> Result<Self::File> {
+        let path = path.as_ref();
+        if is_hidden(&*self.fs, &self.hidden, path) {
+            return Err(if self.mutating {
+                permission_denied(path)
+            } else {
+                not_found(path)
+            });
+        }
+
+        self.inner.open(path)
+    }
+}
+
+impl<FS: UniFs> UniDirBuilder for HideDirBuilder<FS> {
+    fn recursive(&mut self, recursive: bool) -> &mut Self {
+        self.inner.recursive(recursive);
+        self
+    }
+
+    fn create<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        if is_hidden(&*self.fs, &self.hidden, path) {
+            return Err(permission_denied(path));
+        }
+
+        self.inner.create(path)
+    }
+}
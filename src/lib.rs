@@ -7,6 +7,10 @@
 mod traits;
 
 mod rw_lock;
+mod uri;
+
+#[cfg(any(feature = "tar", feature = "zip"))]
+mod extract;
 
 #[cfg(feature = "fs_access")]
 mod physical_fs;
@@ -15,16 +19,44 @@ mod physical_fs;
 pub mod memory_fs;
 
 pub mod altroot_fs;
+pub mod audit_fs;
+pub mod batch_write_fs;
+pub mod canon_cache_fs;
+pub mod context_fs;
+pub mod dry_run_fs;
+pub mod fallback_fs;
+pub mod hide_fs;
+pub mod map_path_fs;
 pub mod readonly_fs;
 pub mod stacked_fs;
+pub mod timeout_fs;
+#[cfg(feature = "zip")]
+pub mod zip_fs;
+
+use std::{
+    collections::BTreeMap,
+    fmt::Debug,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+pub use uri::from_uri;
 
-use std::{fmt::Debug, time::SystemTime};
+#[cfg(feature = "tar")]
+pub use extract::extract_tar;
+#[cfg(feature = "zip")]
+pub use extract::extract_zip;
+
+/// Re-export of the `encoding_rs` crate, whose encodings are used by
+/// [`UniFsExt::write_text_encoded`] and [`UniFsExt::read_text_decoded`].
+#[cfg(feature = "encoding_rs")]
+pub use encoding_rs;
 
 #[doc(inline)]
 pub use traits::{
-    dir_builder::UniDirBuilder, file::UniFile, file_system::UniFs, file_system_ext::UniFsExt,
-    open_options::UniOpenOptions, UniDirEntry, UniFileTimes, UniFileType, UniMetadata,
-    UniPermissions,
+    dir_builder::UniDirBuilder, dyn_file_system::DynUniFs, file::UniFile, file_system::UniFs,
+    file_system_ext::UniFsExt, open_options::UniOpenOptions, UniDirEntry, UniFileTimes,
+    UniFileType, UniMetadata, UniPermissions,
 };
 
 #[doc(inline)]
@@ -36,17 +68,39 @@ pub use physical_fs::PhysicalFs;
 pub use memory_fs::MemoryFs;
 
 #[doc(inline)]
-pub use altroot_fs::AltrootFs;
+pub use altroot_fs::{AltrootFs, DirHandle};
+#[doc(inline)]
+pub use audit_fs::AuditFs;
+#[doc(inline)]
+pub use batch_write_fs::BatchWriteFs;
+#[doc(inline)]
+pub use canon_cache_fs::CanonCacheFs;
+#[doc(inline)]
+pub use context_fs::ContextFs;
+#[doc(inline)]
+pub use dry_run_fs::DryRunFs;
+#[doc(inline)]
+pub use fallback_fs::FallbackFs;
+#[doc(inline)]
+pub use hide_fs::HideFs;
+#[doc(inline)]
+pub use map_path_fs::MapPathFs;
 #[doc(inline)]
 pub use readonly_fs::ReadonlyFs;
 #[doc(inline)]
 pub use stacked_fs::StackedFs;
+#[doc(inline)]
+pub use timeout_fs::TimeoutFs;
+#[doc(inline)]
+#[cfg(feature = "zip")]
+pub use zip_fs::ZipFs;
 
 /// A type alias for the result type used throughout the filesystem operations.
 pub type Result<T> = std::result::Result<T, std::io::Error>;
 
 /// A unified file type that can represent different file types in a filesystem.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum FileType {
     /// Represents a directory.
     Directory,
@@ -70,8 +124,21 @@ impl UniFileType for FileType {
     }
 }
 
+/// The kind of special file to create with [`UniFs::create_special`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SpecialFileKind {
+    /// A named pipe (FIFO).
+    Fifo,
+    /// A Unix domain socket.
+    Socket,
+    /// A block device.
+    BlockDevice,
+    /// A character device.
+    CharDevice,
+}
+
 /// A unified permissions type that can represent file permissions in a filesystem.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct Permissions {
     readonly: bool,
 }
@@ -86,6 +153,163 @@ impl UniPermissions for Permissions {
     }
 }
 
+/// A report of a write operation, as returned by [`UniFs::write_reporting`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WriteReport {
+    /// The number of bytes written.
+    pub written: u64,
+    /// The length of the file before it was overwritten, or `None` if the file was newly created.
+    pub previous_len: Option<u64>,
+}
+
+/// Options for [`UniFsExt::copy_dir_with`], selecting which attributes of the copied
+/// entries are preserved in addition to their contents.
+///
+/// The default preserves nothing, which matches [`UniFsExt::copy_dir_all`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CopyOptions {
+    /// Apply the permissions of each entry to its copy.
+    pub preserve_permissions: bool,
+    /// Apply the modification and access times of each file to its copy.
+    pub preserve_times: bool,
+    /// Apply the owner and group of each entry to its copy.
+    pub preserve_owner: bool,
+}
+
+impl CopyOptions {
+    /// Returns options preserving all attributes, like an archive.
+    pub fn archive() -> Self {
+        CopyOptions {
+            preserve_permissions: true,
+            preserve_times: true,
+            preserve_owner: true,
+        }
+    }
+}
+
+/// A summary of a file, as contained in a manifest built by [`UniFsExt::manifest`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EntrySummary {
+    /// The size of the file, in bytes.
+    pub len: u64,
+    /// The last modification time of the file, if available.
+    pub modified: Option<SystemTime>,
+    /// A 64-bit FNV-1a hash of the contents of the file.
+    pub hash: u64,
+}
+
+impl EntrySummary {
+    /// Returns `true` if the file summarized by `other` has different contents.
+    ///
+    /// Only the lengths and hashes are compared, so files that were touched without
+    /// changing their contents do not differ.
+    pub fn differs_from(&self, other: &EntrySummary) -> bool {
+        self.len != other.len || self.hash != other.hash
+    }
+}
+
+/// An entry of a directory tree, as returned by [`UniFsExt::to_records`] and read by
+/// [`MemoryFs::from_records`].
+///
+/// With the `serde` feature, records can be serialized, for example to JSON, to inspect
+/// or process a tree with external tools.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FileRecord {
+    /// The path of the entry, relative to the root of the tree.
+    pub path: PathBuf,
+    /// The type of the entry.
+    pub kind: FileType,
+    /// The size of the entry, in bytes.
+    pub len: u64,
+    /// The last modification time of the entry, if available.
+    pub modified: Option<SystemTime>,
+    /// The contents of the entry if it is a file.
+    pub contents: Option<Vec<u8>>,
+    /// The target of the entry if it is a symbolic link.
+    pub target: Option<PathBuf>,
+}
+
+/// The differences between two manifests, as returned by [`manifest_diff`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ManifestDiff {
+    /// The paths only contained in the second manifest.
+    pub added: Vec<PathBuf>,
+    /// The paths only contained in the first manifest.
+    pub removed: Vec<PathBuf>,
+    /// The paths contained in both manifests whose summaries differ.
+    pub changed: Vec<PathBuf>,
+}
+
+impl ManifestDiff {
+    /// Returns `true` if the manifests contain the same files.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Compares two manifests built by [`UniFsExt::manifest`].
+///
+/// All paths in the returned diff are sorted. See [`EntrySummary::differs_from`] for
+/// how changed files are detected.
+pub fn manifest_diff(
+    a: &BTreeMap<PathBuf, EntrySummary>,
+    b: &BTreeMap<PathBuf, EntrySummary>,
+) -> ManifestDiff {
+    let mut diff = ManifestDiff::default();
+
+    for (path, summary) in a {
+        match b.get(path) {
+            Some(other) if summary.differs_from(other) => diff.changed.push(path.clone()),
+            Some(_) => {}
+            None => diff.removed.push(path.clone()),
+        }
+    }
+    diff.added = b
+        .keys()
+        .filter(|path| !a.contains_key(*path))
+        .cloned()
+        .collect();
+
+    diff
+}
+
+/// A lock file acquired with [`UniFsExt::acquire_lockfile`].
+///
+/// The lock file is removed when the guard is dropped. Errors while removing it are
+/// ignored, use [`LockFileGuard::release`] to handle them.
+#[derive(Debug)]
+pub struct LockFileGuard<'a, F: UniFs> {
+    fs: &'a F,
+    path: PathBuf,
+    /// Whether the lock file still has to be removed on drop.
+    held: bool,
+}
+
+impl<'a, F: UniFs> LockFileGuard<'a, F> {
+    /// Returns the path of the lock file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Releases the lock by removing the lock file.
+    ///
+    /// # Errors
+    /// - if the lock file cannot be removed.
+    pub fn release(mut self) -> Result<()> {
+        self.held = false;
+        self.fs.remove_file(&self.path)
+    }
+}
+
+impl<F: UniFs> Drop for LockFileGuard<'_, F> {
+    fn drop(&mut self) {
+        if self.held {
+            let _ = self.fs.remove_file(&self.path);
+        }
+    }
+}
+
 /// A unified file times structure that can represent file timestamps in a filesystem.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct FileTimes {
@@ -0,0 +1,371 @@
+//! This module provides a wrapper that rewrites paths before they reach a filesystem.
+
+use std::{
+    borrow::Cow,
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{
+    Result, SpecialFileKind, UniDirBuilder, UniDirEntry, UniFs, UniOpenOptions, WriteReport,
+};
+
+/// Wraps a filesystem to rewrite every path with a mapping function before it is
+/// passed to the wrapped filesystem.
+///
+/// This is a generalization of [`crate::AltrootFs`], which only prepends a root.
+///
+/// The paths of directory entries returned by [`UniFs::read_dir`] are mapped back:
+/// if an entry path starts with the mapped directory path, that prefix is replaced
+/// by the directory path that was passed to `read_dir`. Paths returned by
+/// [`UniFs::canonicalize`] are mapped back the same way, using the longest ancestor of
+/// the requested path whose mapped form the canonical path starts with.
+///
+/// # Example
+///
+/// ```
+/// use std::{borrow::Cow, path::Path};
+///
+/// use unifs::{MapPathFs, MemoryFs, UniFs as _};
+///
+/// # fn main() -> unifs::Result<()> {
+/// let memory_fs = MemoryFs::default();
+/// memory_fs.create_dir("/v2")?;
+/// memory_fs.write("/v2/data.txt", "Hello, World!")?;
+///
+/// let fs = MapPathFs::new(&memory_fs, |path: &Path| match path.strip_prefix("/v1") {
+///     Ok(rest) => Cow::Owned(Path::new("/v2").join(rest)),
+///     Err(_) => Cow::Borrowed(path),
+/// });
+/// assert_eq!(fs.read_to_string("/v1/data.txt")?, "Hello, World!");
+/// # Ok(())
+/// # }
+/// ```
+pub struct MapPathFs<FS, F>
+where
+    FS: UniFs,
+    F: for<'p> Fn(&'p Path) -> Cow<'p, Path>,
+{
+    fs: FS,
+    map: Arc<F>,
+}
+
+/// Represents a directory entry in the path mapping filesystem.
+pub struct MapPathDirEntry<T: UniDirEntry> {
+    mapped_dir: PathBuf,
+    requested_dir: PathBuf,
+    entry: T,
+}
+
+/// Represents a directory iterator for the path mapping filesystem.
+pub struct MapPathReadDir<FS: UniFs> {
+    mapped_dir: PathBuf,
+    requested_dir: PathBuf,
+    inner: FS::ReadDir,
+}
+
+/// Represents options for opening files in the path mapping filesystem.
+pub struct MapPathOpenOptions<O, F>
+where
+    O: UniOpenOptions,
+    F: for<'p> Fn(&'p Path) -> Cow<'p, Path>,
+{
+    map: Arc<F>,
+    inner: O,
+}
+
+/// Represents a directory builder for the path mapping filesystem.
+pub struct MapPathDirBuilder<T, F>
+where
+    T: UniDirBuilder,
+    F: for<'p> Fn(&'p Path) -> Cow<'p, Path>,
+{
+    map: Arc<F>,
+    inner: T,
+}
+
+impl<FS, F> MapPathFs<FS, F>
+where
+    FS: UniFs,
+    F: for<'p> Fn(&'p Path) -> Cow<'p, Path>,
+{
+    /// Creates a new `MapPathFs` that applies `map` to every path before delegating to `fs`.
+    pub fn new(fs: FS, map: F) -> Self {
+        Self {
+            fs,
+            map: Arc::new(map),
+        }
+    }
+
+    fn map_path<'p>(&self, path: &'p Path) -> Cow<'p, Path> {
+        (self.map)(path)
+    }
+}
+
+impl<FS, F> UniFs for MapPathFs<FS, F>
+where
+    FS: UniFs,
+    F: for<'p> Fn(&'p Path) -> Cow<'p, Path>,
+{
+    type Metadata = FS::Metadata;
+    type ReadDir = MapPathReadDir<FS>;
+    type DirEntry = MapPathDirEntry<FS::DirEntry>;
+    type Permissions = FS::Permissions;
+    type File = FS::File;
+    type OpenOptions = MapPathOpenOptions<FS::OpenOptions, F>;
+    type DirBuilder = MapPathDirBuilder<FS::DirBuilder, F>;
+
+    fn canonicalize<P: AsRef<Path>>(&self, path: P) -> Result<PathBuf> {
+        let path = path.as_ref();
+        let canonical = self.fs.canonicalize(self.map_path(path))?;
+
+        // Ancestors with `.` or `..` components cannot be a prefix of a canonical path.
+        let unmapped = path
+            .ancestors()
+            .filter(|ancestor| {
+                !ancestor.as_os_str().is_empty()
+                    && ancestor
+                        .components()
+                        .all(|c| !matches!(c, Component::CurDir | Component::ParentDir))
+            })
+            .find_map(|ancestor| {
+                let rest = canonical.strip_prefix(self.map_path(ancestor)).ok()?;
+                Some(ancestor.join(rest))
+            });
+
+        Ok(unmapped.unwrap_or(canonical))
+    }
+
+    fn copy<P: AsRef<Path>, Q: AsRef<Path>>(&self, from: P, to: Q) -> Result<u64> {
+        let from = self.map_path(from.as_ref());
+        let to = self.map_path(to.as_ref());
+
+        self.fs.copy(from, to)
+    }
+
+    fn create_dir<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        self.fs.create_dir(self.map_path(path.as_ref()))
+    }
+
+    fn create_dir_all<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        self.fs.create_dir_all(self.map_path(path.as_ref()))
+    }
+
+    fn exists<P: AsRef<Path>>(&self, path: P) -> Result<bool> {
+        self.fs.exists(self.map_path(path.as_ref()))
+    }
+
+    fn hard_link<P: AsRef<Path>, Q: AsRef<Path>>(&self, original: P, link: Q) -> Result<()> {
+        let original = self.map_path(original.as_ref());
+        let link = self.map_path(link.as_ref());
+
+        self.fs.hard_link(original, link)
+    }
+
+    fn metadata<P: AsRef<Path>>(&self, path: P) -> Result<Self::Metadata> {
+        self.fs.metadata(self.map_path(path.as_ref()))
+    }
+
+    fn read<P: AsRef<Path>>(&self, path: P) -> Result<Vec<u8>> {
+        self.fs.read(self.map_path(path.as_ref()))
+    }
+
+    fn read_dir<P: AsRef<Path>>(&self, path: P) -> Result<Self::ReadDir> {
+        let requested_dir = path.as_ref();
+        let mapped_dir = self.map_path(requested_dir);
+
+        self.fs.read_dir(&mapped_dir).map(|inner| MapPathReadDir {
+            mapped_dir: mapped_dir.into_owned(),
+            requested_dir: requested_dir.to_path_buf(),
+            inner,
+        })
+    }
+
+    fn read_link<P: AsRef<Path>>(&self, path: P) -> Result<PathBuf> {
+        self.fs.read_link(self.map_path(path.as_ref()))
+    }
+
+    fn read_to_string<P: AsRef<Path>>(&self, path: P) -> Result<String> {
+        self.fs.read_to_string(self.map_path(path.as_ref()))
+    }
+
+    fn remove_dir<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        self.fs.remove_dir(self.map_path(path.as_ref()))
+    }
+
+    fn remove_dir_all<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        self.fs.remove_dir_all(self.map_path(path.as_ref()))
+    }
+
+    fn remove_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        self.fs.remove_file(self.map_path(path.as_ref()))
+    }
+
+    fn rename<P: AsRef<Path>, Q: AsRef<Path>>(&self, from: P, to: Q) -> Result<()> {
+        let from = self.map_path(from.as_ref());
+        let to = self.map_path(to.as_ref());
+
+        self.fs.rename(from, to)
+    }
+
+    fn set_permissions<P: AsRef<Path>>(&self, path: P, perm: Self::Permissions) -> Result<()> {
+        self.fs.set_permissions(self.map_path(path.as_ref()), perm)
+    }
+
+    fn symlink_metadata<P: AsRef<Path>>(&self, path: P) -> Result<Self::Metadata> {
+        self.fs.symlink_metadata(self.map_path(path.as_ref()))
+    }
+
+    fn write<P: AsRef<Path>, C: AsRef<[u8]>>(&self, path: P, contents: C) -> Result<()> {
+        self.fs.write(self.map_path(path.as_ref()), contents)
+    }
+
+    fn write_reporting<P: AsRef<Path>, C: AsRef<[u8]>>(
+        &self,
+        path: P,
+        contents: C,
+    ) -> Result<WriteReport> {
+        self.fs
+            .write_reporting(self.map_path(path.as_ref()), contents)
+    }
+
+    fn create_special<P: AsRef<Path>>(&self, path: P, kind: SpecialFileKind) -> Result<()> {
+        self.fs.create_special(self.map_path(path.as_ref()), kind)
+    }
+
+    fn set_owner<P: AsRef<Path>>(&self, path: P, uid: Option<u32>, gid: Option<u32>) -> Result<()> {
+        self.fs.set_owner(self.map_path(path.as_ref()), uid, gid)
+    }
+
+    fn symlink<P: AsRef<Path>, Q: AsRef<Path>>(&self, original: P, link: Q) -> Result<()> {
+        // The target is stored verbatim, so it is not mapped.
+        self.fs.symlink(original, self.map_path(link.as_ref()))
+    }
+
+    fn physical_path<P: AsRef<Path>>(&self, path: P) -> Option<PathBuf> {
+        self.fs.physical_path(self.map_path(path.as_ref()))
+    }
+
+    fn open_file<P: AsRef<Path>>(&self, path: P) -> Result<Self::File> {
+        self.fs.open_file(self.map_path(path.as_ref()))
+    }
+
+    fn new_openoptions(&self) -> Self::OpenOptions {
+        MapPathOpenOptions {
+            map: self.map.clone(),
+            inner: self.fs.new_openoptions(),
+        }
+    }
+
+    fn new_dirbuilder(&self) -> Self::DirBuilder {
+        MapPathDirBuilder {
+            map: self.map.clone(),
+            inner: self.fs.new_dirbuilder(),
+        }
+    }
+}
+
+impl<T: UniDirEntry> UniDirEntry for MapPathDirEntry<T> {
+    type Metadata = T::Metadata;
+    type FileType = T::FileType;
+
+    fn path(&self) -> PathBuf {
+        let path = self.entry.path();
+        if let Ok(stripped) = path.strip_prefix(&self.mapped_dir) {
+            self.requested_dir.join(stripped)
+        } else {
+            path
+        }
+    }
+
+    fn metadata(&self) -> Result<Self::Metadata> {
+        self.entry.metadata()
+    }
+
+    fn file_type(&self) -> Result<Self::FileType> {
+        self.entry.file_type()
+    }
+
+    fn file_name(&self) -> std::ffi::OsString {
+        self.entry.file_name()
+    }
+}
+
+impl<FS> Iterator for MapPathReadDir<FS>
+where
+    FS: UniFs,
+{
+    type Item = Result<MapPathDirEntry<FS::DirEntry>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|entry| {
+            entry.map(|entry| MapPathDirEntry {
+                mapped_dir: self.mapped_dir.clone(),
+                requested_dir: self.requested_dir.clone(),
+                entry,
+            })
+        })
+    }
+}
+
+impl<O, F> UniOpenOptions for MapPathOpenOptions<O, F>
+where
+    O: UniOpenOptions,
+    F: for<'p> Fn(&'p Path) -> Cow<'p, Path>,
+{
+    type File = O::File;
+
+    fn read(&mut self, read: bool) -> &mut Self {
+        self.inner.read(read);
+        self
+    }
+
+    fn write(&mut self, write: bool) -> &mut Self {
+        self.inner.write(write);
+        self
+    }
+
+    fn append(&mut self, append: bool) -> &mut Self {
+        self.inner.append(append);
+        self
+    }
+
+    fn truncate(&mut self, truncate: bool) -> &mut Self {
+        self.inner.truncate(truncate);
+        self
+    }
+
+    fn create(&mut self, create: bool) -> &mut Self {
+        self.inner.create(create);
+        self
+    }
+
+    fn create_new(&mut self, create_new: bool) -> &mut Self {
+        self.inner.create_new(create_new);
+        self
+    }
+
+    fn direct(&mut self, direct: bool) -> &mut Self {
+        self.inner.direct(direct);
+        self
+    }
+
+    fn open<P: AsRef<Path>>(&self, path: P) -> Result<Self::File> {
+        self.inner.open((self.map)(path.as_ref()))
+    }
+}
+
+impl<T, F> UniDirBuilder for MapPathDirBuilder<T, F>
+where
+    T: UniDirBuilder,
+    F: for<'p> Fn(&'p Path) -> Cow<'p, Path>,
+{
+    fn recursive(&mut self, recursive: bool) -> &mut Self {
+        self.inner.recursive(recursive);
+        self
+    }
+
+    fn create<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        self.inner.create((self.map)(path.as_ref()))
+    }
+}
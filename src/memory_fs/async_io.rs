@@ -0,0 +1,50 @@
+use std::{
+    io::{Read as _, Seek as _, SeekFrom, Write as _},
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_io::{AsyncRead, AsyncSeek, AsyncWrite};
+
+use crate::memory_fs::file::MemoryFile;
+
+// All operations on a `MemoryFile` complete immediately, so the asynchronous
+// adapters simply forward to the blocking implementations.
+
+impl AsyncRead for MemoryFile {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<std::io::Result<usize>> {
+        Poll::Ready(self.get_mut().read(buf))
+    }
+}
+
+impl AsyncWrite for MemoryFile {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        Poll::Ready(self.get_mut().write(buf))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Poll::Ready(self.get_mut().flush())
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Poll::Ready(self.get_mut().flush())
+    }
+}
+
+impl AsyncSeek for MemoryFile {
+    fn poll_seek(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        pos: SeekFrom,
+    ) -> Poll<std::io::Result<u64>> {
+        Poll::Ready(self.get_mut().seek(pos))
+    }
+}
@@ -0,0 +1,110 @@
+use std::{
+    io::{Error, ErrorKind},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use crate::memory_fs::MemoryFs;
+
+/// Accounts for the bytes stored in the files of a [`MemoryFs`].
+///
+/// The data of a file counts once, no matter how many hard links it has. It stops
+/// counting when its last link is removed, even if it is still open.
+#[derive(Debug)]
+pub(super) struct Capacity {
+    /// The maximum number of bytes, where `u64::MAX` stands for no limit.
+    limit: AtomicU64,
+    used: AtomicU64,
+}
+
+impl Capacity {
+    pub(super) fn new(limit: Option<u64>) -> Self {
+        Capacity {
+            limit: AtomicU64::new(limit.unwrap_or(u64::MAX)),
+            used: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns the maximum number of bytes, or `None` if there is no limit.
+    pub(super) fn limit(&self) -> Option<u64> {
+        let limit = self.limit.load(Ordering::Acquire);
+        (limit != u64::MAX).then_some(limit)
+    }
+
+    /// Accounts for `bytes` additional bytes.
+    ///
+    /// # Errors
+    /// - [`ErrorKind::QuotaExceeded`] if the limit would be exceeded.
+    pub(super) fn reserve(&self, bytes: u64) -> crate::Result<()> {
+        let limit = self.limit.load(Ordering::Acquire);
+        self.used
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |used| {
+                used.checked_add(bytes).filter(|&total| total <= limit)
+            })
+            .map(drop)
+            .map_err(|used| {
+                Error::new(
+                    ErrorKind::QuotaExceeded,
+                    format!(
+                        "Cannot store {bytes} more bytes, only {} of {limit} bytes are left",
+                        limit.saturating_sub(used)
+                    ),
+                )
+            })
+    }
+
+    /// Accounts for `bytes` additional bytes, even if they exceed the limit.
+    pub(super) fn charge(&self, bytes: u64) {
+        self.used.fetch_add(bytes, Ordering::AcqRel);
+    }
+
+    /// Accounts for `bytes` bytes being freed.
+    pub(super) fn release(&self, bytes: u64) {
+        self.used.fetch_sub(bytes, Ordering::AcqRel);
+    }
+
+    /// Accounts for a file changing its length from `old_len` to `new_len`.
+    pub(super) fn resize(&self, old_len: u64, new_len: u64) -> crate::Result<()> {
+        if new_len > old_len {
+            self.reserve(new_len - old_len)
+        } else {
+            self.release(old_len - new_len);
+            Ok(())
+        }
+    }
+}
+
+impl MemoryFs {
+    /// Creates a new, empty `MemoryFs` whose files may hold at most `bytes` bytes in
+    /// total.
+    ///
+    /// See [`MemoryFs::set_capacity`] for how the capacity is enforced.
+    pub fn with_capacity(bytes: u64) -> Self {
+        let fs = MemoryFs::new();
+        fs.set_capacity(Some(bytes));
+        fs
+    }
+
+    /// Limits the total size of the files in this filesystem to `bytes`, or removes
+    /// the limit if `None`.
+    ///
+    /// The contents of a file count once, regardless of the number of hard links to
+    /// it, and with their full length, even if parts of a sparse file are not allocated.
+    /// Operations that would grow the files beyond the limit fail with
+    /// [`ErrorKind::QuotaExceeded`] without writing anything. Lowering the limit below
+    /// the current usage does not remove any data, but prevents further growth.
+    pub fn set_capacity(&self, bytes: Option<u64>) {
+        self.inner
+            .read()
+            .capacity
+            .limit
+            .store(bytes.unwrap_or(u64::MAX), Ordering::Release);
+    }
+
+    /// Returns the number of bytes that can still be stored, or `None` if the
+    /// capacity is not limited.
+    pub fn remaining_capacity(&self) -> Option<u64> {
+        let inner = self.inner.read();
+        let limit = inner.capacity.limit()?;
+        Some(limit.saturating_sub(inner.capacity.used.load(Ordering::Acquire)))
+    }
+}
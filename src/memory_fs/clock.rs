@@ -0,0 +1,82 @@
+use std::{
+    fmt::Debug,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use crate::rw_lock::RwLock;
+
+/// A source of the current time for the timestamps of a [`MemoryFs`](super::MemoryFs).
+///
+/// See [`MemoryFs::with_clock`](super::MemoryFs::with_clock).
+pub trait Clock: Debug + Send + Sync {
+    /// Returns the current time.
+    fn now(&self) -> SystemTime;
+}
+
+/// The clock used by default, returning the time of the system.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// A clock that only changes when it is set or advanced explicitly.
+///
+/// Clones share the same time, so a clone can be passed to
+/// [`MemoryFs::with_clock`](super::MemoryFs::with_clock) while the original is kept to
+/// control it.
+///
+/// # Example
+///
+/// ```
+/// use std::time::{Duration, UNIX_EPOCH};
+///
+/// use unifs::{memory_fs::ManualClock, MemoryFs, UniFs as _, UniMetadata as _};
+///
+/// # fn main() -> unifs::Result<()> {
+/// let clock = ManualClock::new(UNIX_EPOCH);
+/// let fs = MemoryFs::with_clock(clock.clone());
+///
+/// fs.write("/file.txt", "Hello")?;
+/// clock.advance(Duration::from_secs(60));
+/// fs.write("/file.txt", "World")?;
+/// assert_eq!(
+///     fs.metadata("/file.txt")?.modified()?,
+///     UNIX_EPOCH + Duration::from_secs(60)
+/// );
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+    time: Arc<RwLock<SystemTime>>,
+}
+
+impl ManualClock {
+    /// Creates a new `ManualClock` set to `time`.
+    pub fn new(time: SystemTime) -> Self {
+        Self {
+            time: Arc::new(RwLock::new(time)),
+        }
+    }
+
+    /// Sets the clock to `time`, which may be earlier than the current time.
+    pub fn set(&self, time: SystemTime) {
+        *self.time.write() = time;
+    }
+
+    /// Moves the clock forward by `duration`.
+    pub fn advance(&self, duration: Duration) {
+        *self.time.write() += duration;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> SystemTime {
+        *self.time.read()
+    }
+}
@@ -0,0 +1,159 @@
+use std::{
+    collections::BTreeMap,
+    io::{Error, ErrorKind},
+};
+
+/// The number of bytes in a page of [`FileContents`].
+const PAGE_SIZE: u64 = 4096;
+
+/// The contents of a file in a [`MemoryFs`](super::MemoryFs), stored in pages.
+///
+/// Only pages that were written to are allocated, each up to its last written byte.
+/// Everything else up to the length of the file reads as zeros, so extending a file,
+/// for example with `set_len`, does not allocate memory for the hole.
+#[derive(Debug, Clone, Default)]
+pub(super) struct FileContents {
+    len: u64,
+    /// The written pages by their index, each at most [`PAGE_SIZE`] bytes long.
+    pages: BTreeMap<u64, Vec<u8>>,
+}
+
+impl FileContents {
+    /// Creates contents consisting of `data`.
+    pub(super) fn new(data: &[u8]) -> Self {
+        FileContents {
+            len: data.len() as u64,
+            pages: (0..)
+                .zip(data.chunks(PAGE_SIZE as usize).map(<[u8]>::to_vec))
+                .collect(),
+        }
+    }
+
+    /// Returns the length of the file, including holes.
+    pub(super) fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Reads into `buf` from `offset` and returns the number of bytes read, which is
+    /// only less than the length of `buf` at the end of the file.
+    pub(super) fn read_at(&self, offset: u64, buf: &mut [u8]) -> usize {
+        let available = self.len.saturating_sub(offset);
+        let len = buf
+            .len()
+            .min(usize::try_from(available).unwrap_or(usize::MAX));
+        if len == 0 {
+            return 0;
+        }
+
+        let buf = &mut buf[..len];
+        buf.fill(0);
+        let end = offset + len as u64;
+        for (index, page) in self.pages.range(offset / PAGE_SIZE..=(end - 1) / PAGE_SIZE) {
+            let page_start = index * PAGE_SIZE;
+            let from = offset.max(page_start);
+            let to = end.min(page_start + page.len() as u64);
+            if from < to {
+                buf[(from - offset) as usize..(to - offset) as usize].copy_from_slice(
+                    &page[(from - page_start) as usize..(to - page_start) as usize],
+                );
+            }
+        }
+
+        len
+    }
+
+    /// Writes `buf` at `offset`, extending the file if it ends after the end of the
+    /// file. The bytes between the previous end and `offset` become a hole.
+    ///
+    /// # Errors
+    /// - [`ErrorKind::FileTooLarge`] if the file would end after [`u64::MAX`].
+    pub(super) fn write_at(&mut self, offset: u64, buf: &[u8]) -> crate::Result<()> {
+        let end = offset.checked_add(buf.len() as u64).ok_or_else(|| {
+            Error::new(
+                ErrorKind::FileTooLarge,
+                "Write would exceed the maximum file size",
+            )
+        })?;
+        let mut position = offset;
+        while position < end {
+            let index = position / PAGE_SIZE;
+            let page_start = index * PAGE_SIZE;
+            // The end of the last page does not fit into a `u64`.
+            let page_end = end.min(page_start.saturating_add(PAGE_SIZE));
+
+            let page = self.pages.entry(index).or_default();
+            let (from, to) = (
+                (position - page_start) as usize,
+                (page_end - page_start) as usize,
+            );
+            if page.len() < to {
+                page.resize(to, 0);
+            }
+            page[from..to]
+                .copy_from_slice(&buf[(position - offset) as usize..(page_end - offset) as usize]);
+
+            position = page_end;
+        }
+
+        if !buf.is_empty() {
+            self.len = self.len.max(end);
+        }
+        Ok(())
+    }
+
+    /// Truncates or extends the file to `len` bytes. Extending it adds a hole.
+    pub(super) fn set_len(&mut self, len: u64) {
+        if len < self.len {
+            let index = len / PAGE_SIZE;
+            let keep = (len % PAGE_SIZE) as usize;
+            self.pages.split_off(&(index + 1));
+            if keep == 0 {
+                self.pages.remove(&index);
+            } else if let Some(page) = self.pages.get_mut(&index) {
+                page.truncate(keep);
+            }
+        }
+        self.len = len;
+    }
+
+    /// Returns the contents as a contiguous vector, with the holes filled with zeros.
+    pub(super) fn to_vec(&self) -> Vec<u8> {
+        let mut data = vec![0; self.len as usize];
+        self.read_at(0, &mut data);
+        data
+    }
+
+    /// Frees the memory of zeros at the end of pages and of pages only containing
+    /// zeros, turning them into holes.
+    pub(super) fn shrink_to_fit(&mut self) {
+        self.pages.retain(|_, page| {
+            let used = page
+                .iter()
+                .rposition(|&byte| byte != 0)
+                .map_or(0, |i| i + 1);
+            page.truncate(used);
+            page.shrink_to_fit();
+            used > 0
+        });
+    }
+
+    fn page(&self, index: u64) -> &[u8] {
+        self.pages.get(&index).map_or(&[], Vec::as_slice)
+    }
+}
+
+/// Contents are equal if they read the same, regardless of where the holes are.
+impl PartialEq for FileContents {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len
+            && self.pages.keys().chain(other.pages.keys()).all(|&index| {
+                let (page, other_page) = (self.page(index), other.page(index));
+                let common = page.len().min(other_page.len());
+                page[..common] == other_page[..common]
+                    && page[common..].iter().all(|&byte| byte == 0)
+                    && other_page[common..].iter().all(|&byte| byte == 0)
+            })
+    }
+}
+
+impl Eq for FileContents {}
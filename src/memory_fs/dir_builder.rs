@@ -26,13 +26,14 @@ impl UniDirBuilder for MemoryDirBuilder {
         let mut inner = self.fs.inner.write();
         let path = super::canonicalize_inner(&inner, path, true)?;
 
+        // Existing directories, including the root, are accepted in recursive mode.
         if super::exists(&inner, &path)? {
-            if self.recursive {
+            if self.recursive && super::is_dir(&inner, &path)? {
                 Ok(())
             } else {
                 Err(Error::new(
                     std::io::ErrorKind::AlreadyExists,
-                    format!("Directory already exists: {}", path.display()),
+                    format!("Path already exists: {}", path.display()),
                 ))
             }
         } else {
@@ -52,11 +53,11 @@ impl UniDirBuilder for MemoryDirBuilder {
                         break;
                     }
                 }
-
-                if parts.is_empty() {
+                // Like `std::fs::create_dir_all`, an existing ancestor must be a directory.
+                if !super::is_dir(&inner, current)? {
                     return Err(Error::new(
-                        std::io::ErrorKind::InvalidInput,
-                        "Cannot create directory at root",
+                        std::io::ErrorKind::NotADirectory,
+                        format!("Path '{}' is not a directory", current.display()),
                     ));
                 }
 
@@ -1,6 +1,14 @@
-use std::path::Path;
+use std::{
+    collections::{HashMap, HashSet},
+    io::{Error, ErrorKind},
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+};
 
-use crate::{MemoryFs, UniDirEntry as _, UniFileType as _, UniFs, UniFsExt as _, UniMetadata as _};
+use crate::{
+    memory_fs::MemoryEntryType, FileRecord, FileType, MemoryFs, UniDirEntry as _, UniFile as _,
+    UniFileType as _, UniFs, UniFsExt as _, UniMetadata as _,
+};
 
 impl MemoryFs {
     /// Load the contents of a directory from any filesystem implementing `UniFs`
@@ -33,4 +41,299 @@ impl MemoryFs {
 
         Ok(memory_fs)
     }
+
+    /// Builds a new `MemoryFs` from records as returned by [`UniFsExt::to_records`](crate::UniFsExt::to_records).
+    ///
+    /// The paths of the records are taken relative to the root directory, and missing
+    /// parent directories are created. The modification times of files are restored,
+    /// those of directories and symbolic links are not. The lengths of the records are
+    /// ignored; a file record without contents creates an empty file.
+    ///
+    /// # Errors
+    /// - [`ErrorKind::InvalidInput`] if a path is absolute or contains `..`.
+    /// - [`ErrorKind::InvalidData`] if a symbolic link record has no target.
+    /// - if a record conflicts with an earlier one, for example a file inside a file.
+    pub fn from_records<I>(records: I) -> crate::Result<Self>
+    where
+        I: IntoIterator<Item = FileRecord>,
+    {
+        let memory_fs = MemoryFs::new();
+
+        for record in records {
+            if !record
+                .path
+                .components()
+                .all(|component| matches!(component, Component::Normal(_)))
+            {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Invalid path in record: {}", record.path.display()),
+                ));
+            }
+            let path = Path::new("/").join(&record.path);
+            if let Some(parent) = path.parent() {
+                memory_fs.create_dir_all(parent)?;
+            }
+
+            match record.kind {
+                FileType::Directory => memory_fs.create_dir_all(&path)?,
+                FileType::File => {
+                    memory_fs.write(&path, record.contents.unwrap_or_default())?;
+                    if let Some(modified) = record.modified {
+                        memory_fs.open_file(&path)?.set_modified(modified)?;
+                    }
+                }
+                FileType::Symlink => {
+                    let target = record.target.ok_or_else(|| {
+                        Error::new(
+                            ErrorKind::InvalidData,
+                            format!("Missing link target in record: {}", record.path.display()),
+                        )
+                    })?;
+                    memory_fs.symlink(target, &path)?;
+                }
+            }
+        }
+
+        Ok(memory_fs)
+    }
+
+    /// Reads the entire contents of a file into a string.
+    ///
+    /// This is a shorthand for [`UniFs::read_to_string`] intended for assertions in tests.
+    ///
+    /// # Panics
+    /// - if the file cannot be read or is not valid UTF-8.
+    pub fn text<P: AsRef<Path>>(&self, path: P) -> String {
+        let path = path.as_ref();
+        self.read_to_string(path)
+            .unwrap_or_else(|err| panic!("failed to read '{}': {err}", path.display()))
+    }
+
+    /// Reads the entire contents of a file into a bytes vector.
+    ///
+    /// This is a shorthand for [`UniFs::read`] intended for assertions in tests.
+    ///
+    /// # Panics
+    /// - if the file cannot be read.
+    pub fn bytes<P: AsRef<Path>>(&self, path: P) -> Vec<u8> {
+        let path = path.as_ref();
+        self.read(path)
+            .unwrap_or_else(|err| panic!("failed to read '{}': {err}", path.display()))
+    }
+
+    /// Returns the total length of all files in bytes.
+    ///
+    /// The contents of a file are counted once, regardless of the number of hard
+    /// links to it. Files that were removed but are still open are not counted.
+    pub fn total_size(&self) -> u64 {
+        let inner = self.inner.read();
+        let mut seen = HashSet::new();
+
+        inner
+            .files
+            .values()
+            .filter_map(|entry| match &entry.file_type {
+                MemoryEntryType::File(file) if seen.insert(Arc::as_ptr(&file.data)) => {
+                    Some(file.data.read().len())
+                }
+                _ => None,
+            })
+            .sum()
+    }
+
+    /// Returns the number of entries in the filesystem, including the root
+    /// directory.
+    ///
+    /// Every file, directory and symbolic link is counted, and a file with several
+    /// hard links is counted once per name.
+    pub fn entry_count(&self) -> usize {
+        self.inner.read().files.len()
+    }
+
+    /// Returns the number of entries the filesystem can hold without reallocating its
+    /// table of entries.
+    ///
+    /// The table keeps its size when entries are removed, until
+    /// [`MemoryFs::compact`] is called.
+    pub fn entry_capacity(&self) -> usize {
+        self.inner.read().files.capacity()
+    }
+
+    /// Changes the owner and group of a file or directory, following symbolic links.
+    ///
+    /// Like [`std::os::unix::fs::chown`], an ID of `None` is left unchanged. New entries
+    /// are owned by the user and group of the current process on Unix, and by `0`
+    /// elsewhere.
+    ///
+    /// # Errors
+    /// - [`ErrorKind::NotFound`] if the path does not exist.
+    pub fn set_owner<P: AsRef<Path>>(
+        &self,
+        path: P,
+        uid: Option<u32>,
+        gid: Option<u32>,
+    ) -> crate::Result<()> {
+        let inner = self.inner.read();
+        let path = super::canonicalize_inner(&inner, path, true)?;
+
+        match inner.files.get(&path) {
+            Some(entry) => {
+                let mut attributes = entry.attributes.write();
+                if let Some(uid) = uid {
+                    attributes.owner.uid = uid;
+                }
+                if let Some(gid) = gid {
+                    attributes.owner.gid = gid;
+                }
+                Ok(())
+            }
+            None => Err(super::missing_path_error(&inner, &path)),
+        }
+    }
+
+    /// Removes all files and directories, leaving only an empty root directory.
+    ///
+    /// The change is visible through all handles to this filesystem. The current
+    /// directory is reset to `/`, while settings such as the capacity are kept. Open
+    /// files stay readable and writable, but no longer count towards the capacity.
+    pub fn clear(&self) {
+        let mut inner = self.inner.write();
+        let root = PathBuf::from("/");
+        let root_entry = super::MemoryEntry::root(inner.clock.now());
+        let files = std::mem::replace(
+            &mut inner.files,
+            HashMap::from([(root.clone(), root_entry)]),
+        );
+        inner.current_dir = root.clone();
+
+        for (path, entry) in files {
+            entry.file_type.unlink(&inner.capacity);
+            // Like `remove_dir_all`, a removed directory is reported as a single event.
+            if path.parent() == Some(&root) {
+                notify!(inner, super::FsEvent::Removed(path));
+            }
+        }
+    }
+
+    /// Removes several files or directories while acquiring the lock of the filesystem
+    /// only once.
+    ///
+    /// Directories are removed with all of their contents. The paths are removed in
+    /// order, and the result of each removal is returned at the same position. A
+    /// failed removal does not stop the removal of the remaining paths.
+    pub fn remove_batch<I>(&self, paths: I) -> Vec<crate::Result<()>>
+    where
+        I: IntoIterator<Item = PathBuf>,
+    {
+        let mut inner = self.inner.write();
+
+        paths
+            .into_iter()
+            .map(|path| {
+                let canonical = super::canonicalize_inner(&inner, &path, false)?;
+                if super::is_dir(&inner, &canonical)? {
+                    super::remove_dir_all(&mut inner, &path)
+                } else {
+                    super::remove_file(&mut inner, &path)
+                }
+            })
+            .collect()
+    }
+
+    /// Checks the internal consistency of the filesystem.
+    ///
+    /// Every entry except the root must be listed by its parent, which must be a
+    /// directory, and every entry listed by a directory must exist. Symbolic links are
+    /// not required to resolve.
+    ///
+    /// # Errors
+    /// - [`ErrorKind::InvalidData`] describing the first inconsistency found.
+    pub fn verify(&self) -> crate::Result<()> {
+        let inner = self.inner.read();
+        let invalid = |msg: String| Err(Error::new(ErrorKind::InvalidData, msg));
+
+        for (path, entry) in &inner.files {
+            if let Some(parent) = path.parent() {
+                let listed = match inner.files.get(parent).map(|parent| &parent.file_type) {
+                    Some(MemoryEntryType::Directory(children)) => {
+                        path.file_name().is_some_and(|name| children.contains(name))
+                    }
+                    Some(_) => {
+                        return invalid(format!(
+                            "Parent '{}' of '{}' is not a directory",
+                            parent.display(),
+                            path.display()
+                        ))
+                    }
+                    None => {
+                        return invalid(format!(
+                            "Parent '{}' of '{}' does not exist",
+                            parent.display(),
+                            path.display()
+                        ))
+                    }
+                };
+                if !listed {
+                    return invalid(format!(
+                        "Entry '{}' is not listed by its parent",
+                        path.display()
+                    ));
+                }
+            }
+
+            if let MemoryEntryType::Directory(children) = &entry.file_type {
+                if let Some(child) = children
+                    .iter()
+                    .map(|name| path.join(name))
+                    .find(|child| !inner.files.contains_key(child))
+                {
+                    return invalid(format!("Listed entry '{}' does not exist", child.display()));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Releases the memory kept after many entries were created and removed.
+    ///
+    /// The table of entries is rebuilt with the minimal capacity and the buffers of all
+    /// files are shrunk to their length. In the same pass, the lists of children of all
+    /// directories are rebuilt from the existing entries, dropping entries whose parent
+    /// is missing or not a directory, so [`MemoryFs::verify`] passes afterwards.
+    pub fn compact(&self) {
+        let mut inner = self.inner.write();
+        let mut entries = std::mem::take(&mut inner.files)
+            .into_iter()
+            .collect::<Vec<_>>();
+        // Parents are visited before their children.
+        entries.sort_by_key(|(path, _)| path.components().count());
+
+        let mut files: HashMap<PathBuf, super::MemoryEntry> = HashMap::with_capacity(entries.len());
+        for (path, mut entry) in entries {
+            match &mut entry.file_type {
+                MemoryEntryType::Directory(children) => children.clear(),
+                MemoryEntryType::File(file) => file.data.write().shrink_to_fit(),
+                MemoryEntryType::Symlink(_) => {}
+            }
+
+            let listed = match (path.parent(), path.file_name()) {
+                (Some(parent), Some(name)) => files
+                    .get_mut(parent)
+                    .and_then(|parent| parent.file_type.as_directory_mut())
+                    .map(|children| children.insert(name.to_os_string()))
+                    .is_some(),
+                _ => true,
+            };
+            if listed {
+                files.insert(path, entry);
+            } else {
+                entry.file_type.unlink(&inner.capacity);
+            }
+        }
+
+        files.shrink_to_fit();
+        inner.files = files;
+    }
 }
@@ -1,49 +1,214 @@
 use std::{
     fmt::Debug,
-    io::{Read, Seek, Write},
+    io::{Error, ErrorKind, Read, Seek, Write},
     path::PathBuf,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
 };
 
 use crate::{
-    memory_fs::metadata::MemoryMetadata, rw_lock::RwLock, FileTimes, Permissions, UniFile,
+    memory_fs::{
+        capacity::Capacity, contents::FileContents, metadata::MemoryMetadata, Attributes, FileData,
+        MemoryFs, RangeLock,
+    },
+    rw_lock::RwLock,
+    FileTimes, Permissions, UniFile,
 };
 
+/// The time to wait between attempts of a blocking lock on a [`MemoryFile`].
+const LOCK_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// A handle to an open file in a [`MemoryFs`].
+///
+/// The handle refers to the data of the file, not to its path, so it keeps working
+/// after the file was renamed or removed. The path of the file is recorded when it
+/// is opened and is not updated by renames. The times and permissions are shared
+/// with the file itself, so changes made through the handle apply to all of its
+/// hard links.
 pub struct MemoryFile {
+    fs: MemoryFs,
     path: PathBuf,
     inner: Arc<RwLock<MemoryFileInner>>,
+    attributes: Arc<RwLock<Attributes>>,
+    /// The number of links to the file, which only counts towards the capacity of
+    /// the filesystem while it is linked.
+    links: Arc<AtomicU64>,
+    capacity: Arc<Capacity>,
     write: bool,
     append: bool,
 }
 
 impl MemoryFile {
     pub(super) fn new(
+        fs: MemoryFs,
         path: PathBuf,
-        data: Arc<RwLock<Vec<u8>>>,
-        metadata: MemoryMetadata,
+        file: &FileData,
+        capacity: Arc<Capacity>,
+        attributes: Arc<RwLock<Attributes>>,
         write: bool,
         append: bool,
     ) -> Self {
         Self {
+            fs,
             path,
             inner: Arc::new(RwLock::new(MemoryFileInner {
-                data,
+                data: file.data.clone(),
                 position: 0,
-                metadata,
+                lock: None,
             })),
+            attributes,
+            links: file.links.clone(),
+            capacity,
             write,
             append,
         }
     }
+
+    /// Locks `len` bytes of this file, starting at byte offset `offset`.
+    ///
+    /// Exclusive locks conflict with any other lock on an overlapping range, while
+    /// shared locks only conflict with exclusive ones. Locks are tracked per file,
+    /// so they also conflict with locks taken through other handles to the same file,
+    /// even if it has been renamed. A `len` of zero locks everything from `offset`
+    /// to the end of the file, including bytes appended later.
+    ///
+    /// The lock is held until the returned guard is dropped.
+    ///
+    /// # Errors
+    /// - [`ErrorKind::WouldBlock`] if the range conflicts with an existing lock.
+    pub fn lock_range(
+        &self,
+        offset: u64,
+        len: u64,
+        exclusive: bool,
+    ) -> crate::Result<RangeLockGuard> {
+        let data = self.inner.read().data.clone();
+        let end = if len == 0 {
+            u64::MAX
+        } else {
+            offset.saturating_add(len)
+        };
+
+        self.acquire_lock(data, offset, end, exclusive, None)
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::WouldBlock,
+                    format!(
+                        "Range {offset}..{end} of file '{}' is already locked",
+                        self.path.display()
+                    ),
+                )
+            })
+    }
+
+    /// Locks the range `start..end` of the file `data`, or returns `None` if it
+    /// conflicts with an existing lock other than the lock `replaced`.
+    fn acquire_lock(
+        &self,
+        data: Arc<RwLock<FileContents>>,
+        start: u64,
+        end: u64,
+        exclusive: bool,
+        replaced: Option<u64>,
+    ) -> Option<RangeLockGuard> {
+        let mut fs = self.fs.inner.write();
+        let conflict = fs.range_locks.iter().any(|lock| {
+            Arc::ptr_eq(&lock.data, &data)
+                && Some(lock.id) != replaced
+                && (exclusive || lock.exclusive)
+                && lock.start < end
+                && start < lock.end
+        });
+        if conflict {
+            return None;
+        }
+
+        let id = fs.next_range_lock_id;
+        fs.next_range_lock_id += 1;
+        fs.range_locks.push(RangeLock {
+            id,
+            data,
+            start,
+            end,
+            exclusive,
+        });
+
+        Some(RangeLockGuard {
+            fs: self.fs.clone(),
+            id,
+        })
+    }
+
+    /// Attempts to lock the whole file for this handle, replacing its previous lock.
+    ///
+    /// The previous lock is kept if the new one conflicts with a lock of another handle.
+    fn try_lock_file(&self, exclusive: bool) -> crate::Result<()> {
+        let mut inner = self.inner.write();
+        let replaced = inner.lock.as_ref().map(|lock| lock.id);
+        let lock = self
+            .acquire_lock(inner.data.clone(), 0, u64::MAX, exclusive, replaced)
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::WouldBlock,
+                    format!("File '{}' is already locked", self.path.display()),
+                )
+            })?;
+        inner.lock = Some(lock);
+        Ok(())
+    }
+
+    /// Locks the whole file for this handle, polling until no other handle holds a
+    /// conflicting lock.
+    fn lock_file(&self, exclusive: bool) -> crate::Result<()> {
+        loop {
+            match self.try_lock_file(exclusive) {
+                Err(err) if err.kind() == ErrorKind::WouldBlock => {
+                    std::thread::sleep(LOCK_POLL_INTERVAL);
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Accounts for resizing the data of this file, which must be locked for writing.
+    fn account_resize(&self, old_len: u64, new_len: u64) -> crate::Result<()> {
+        if self.links.load(Ordering::Acquire) > 0 {
+            self.capacity.resize(old_len, new_len)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// A lock on a byte range of a file in a [`MemoryFs`], returned by `MemoryFile::lock_range`.
+///
+/// The lock is released when the guard is dropped.
+#[derive(Debug)]
+pub struct RangeLockGuard {
+    fs: MemoryFs,
+    id: u64,
+}
+
+impl Drop for RangeLockGuard {
+    fn drop(&mut self) {
+        self.fs
+            .inner
+            .write()
+            .range_locks
+            .retain(|lock| lock.id != self.id);
+    }
 }
 
 struct MemoryFileInner {
     // The underlying data of the file, stored in memory.
-    data: Arc<RwLock<Vec<u8>>>,
+    data: Arc<RwLock<FileContents>>,
     // The current position in the file.
-    position: usize,
-    /// The file's metadata, such as creation time, modified time, etc.
-    metadata: MemoryMetadata,
+    position: u64,
+    /// The lock on the whole file held by this handle and its clones.
+    lock: Option<RangeLockGuard>,
 }
 
 impl Debug for MemoryFile {
@@ -57,17 +222,8 @@ impl Debug for MemoryFile {
 impl Read for MemoryFile {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
         let mut inner = self.inner.write();
-        let bytes_to_read = {
-            let data = inner.data.read();
-            if inner.position >= data.len() {
-                return Ok(0); // EOF
-            }
-            let bytes_to_read = std::cmp::min(buf.len(), data.len() - inner.position);
-            buf[..bytes_to_read]
-                .copy_from_slice(&data[inner.position..inner.position + bytes_to_read]);
-            bytes_to_read
-        };
-        inner.position += bytes_to_read;
+        let bytes_to_read = inner.data.read().read_at(inner.position, buf);
+        inner.position += bytes_to_read as u64;
         Ok(bytes_to_read)
     }
 }
@@ -88,15 +244,28 @@ impl Write for MemoryFile {
                 inner.position = length;
             }
             let mut data = inner.data.write();
-            let position = inner.position;
-            if position + buf.len() > data.len() {
-                data.resize(position + buf.len(), 0);
+            let end = inner
+                .position
+                .checked_add(buf.len() as u64)
+                .ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::FileTooLarge,
+                        format!(
+                            "Write to file '{}' would exceed the maximum file size",
+                            self.path.display()
+                        ),
+                    )
+                })?;
+            if end > data.len() {
+                self.account_resize(data.len(), end)?;
             }
-            data[position..position + buf.len()].copy_from_slice(buf);
+            data.write_at(inner.position, buf)?;
 
             buf.len()
         };
-        inner.position += bytes_written;
+        inner.position += bytes_written as u64;
+        let now = self.fs.inner.read().clock.now();
+        self.attributes.write().modified = Some(now);
         Ok(bytes_written)
     }
 
@@ -110,31 +279,20 @@ impl Seek for MemoryFile {
         self.append = false;
         let mut inner = self.inner.write();
         let position = {
-            let data = inner.data.read();
-            match pos {
-                std::io::SeekFrom::Start(offset) => offset as usize,
-                std::io::SeekFrom::End(offset) => {
-                    if (-offset as usize) > data.len() {
-                        return Err(std::io::Error::new(
-                            std::io::ErrorKind::InvalidInput,
-                            "Seek position out of bounds",
-                        ));
-                    }
-                    (data.len() as isize + offset as isize) as usize
-                }
-                std::io::SeekFrom::Current(offset) => {
-                    if (inner.position as i64 + offset) < 0 {
-                        return Err(std::io::Error::new(
-                            std::io::ErrorKind::InvalidInput,
-                            "Seek position out of bounds",
-                        ));
-                    }
-                    (inner.position as i64 + offset) as usize
-                }
-            }
+            let (base, offset) = match pos {
+                std::io::SeekFrom::Start(offset) => (offset, 0),
+                std::io::SeekFrom::End(offset) => (inner.data.read().len(), offset),
+                std::io::SeekFrom::Current(offset) => (inner.position, offset),
+            };
+            base.checked_add_signed(offset).ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "Seek position out of bounds",
+                )
+            })?
         };
         inner.position = position;
-        Ok(position as u64)
+        Ok(position)
     }
 }
 
@@ -152,38 +310,89 @@ impl UniFile for MemoryFile {
     }
 
     fn set_len(&self, size: u64) -> crate::Result<()> {
-        let mut inner = self.inner.write();
         {
+            let inner = self.inner.read();
             let mut data = inner.data.write();
-            data.resize(size as usize, 0);
+            self.account_resize(data.len(), size)?;
+            data.set_len(size);
         }
-        inner.metadata.file_times.modified = Some(std::time::SystemTime::now());
+        let now = self.fs.inner.read().clock.now();
+        self.attributes.write().modified = Some(now);
         Ok(())
     }
 
+    /// Queries the metadata of the file.
+    ///
+    /// The length and the number of links are read from the file itself, so they
+    /// reflect changes made through other handles and paths.
     fn metadata(&self) -> crate::Result<Self::Metadata> {
-        let inner = self.inner.read();
-        Ok(inner.metadata.clone())
+        let len = self.inner.read().data.read().len();
+        let nlink = self.links.load(Ordering::Acquire);
+        Ok(self
+            .attributes
+            .read()
+            .metadata(crate::FileType::File, len, nlink))
     }
 
     fn try_clone(&self) -> crate::Result<Self> {
         Ok(Self {
+            fs: self.fs.clone(),
             path: self.path.clone(),
             inner: self.inner.clone(),
+            attributes: self.attributes.clone(),
+            links: self.links.clone(),
+            capacity: self.capacity.clone(),
             write: self.write,
             append: self.append,
         })
     }
 
     fn set_permissions(&self, perm: Self::Permissions) -> crate::Result<()> {
-        let mut inner = self.inner.write();
-        inner.metadata.permissions = perm;
+        self.attributes.write().permissions = perm;
         Ok(())
     }
 
     fn set_times(&self, times: Self::FileTimes) -> crate::Result<()> {
-        let mut inner = self.inner.write();
-        inner.metadata.file_times = times;
+        let mut attributes = self.attributes.write();
+        if let Some(modified) = times.modified {
+            attributes.modified = Some(modified);
+        }
+        if let Some(accessed) = times.accessed {
+            attributes.accessed = Some(accessed);
+        }
+        Ok(())
+    }
+
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> crate::Result<usize> {
+        // Only read locks are taken, so concurrent reads do not block each other.
+        let inner = self.inner.read();
+        let bytes_read = inner.data.read().read_at(offset, buf);
+        Ok(bytes_read)
+    }
+
+    /// Locks the whole file exclusively, waiting for other handles to release their
+    /// locks.
+    ///
+    /// The lock also conflicts with the locks taken through [`MemoryFile::lock_range`].
+    fn lock_exclusive(&self) -> crate::Result<()> {
+        self.lock_file(true)
+    }
+
+    fn lock_shared(&self) -> crate::Result<()> {
+        self.lock_file(false)
+    }
+
+    fn try_lock_exclusive(&self) -> crate::Result<()> {
+        self.try_lock_file(true)
+    }
+
+    fn try_lock_shared(&self) -> crate::Result<()> {
+        self.try_lock_file(false)
+    }
+
+    fn unlock(&self) -> crate::Result<()> {
+        let lock = self.inner.write().lock.take();
+        drop(lock);
         Ok(())
     }
 }
@@ -1,5 +1,6 @@
 use std::time::SystemTime;
 
+use super::Owner;
 use crate::{FileTimes, FileType, Permissions, Result, UniFileType, UniMetadata};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -8,6 +9,8 @@ pub struct MemoryMetadata {
     pub(super) len: u64,
     pub(super) permissions: Permissions,
     pub(super) file_times: FileTimes,
+    pub(super) nlink: u64,
+    pub(super) owner: Owner,
 }
 
 impl UniMetadata for MemoryMetadata {
@@ -30,6 +33,11 @@ impl UniMetadata for MemoryMetadata {
         self.file_type.is_symlink()
     }
 
+    /// Returns the length of a file in bytes, the length of the target path of a
+    /// symlink, or the number of entries in a directory.
+    ///
+    /// The length of a directory is an approximation of its size, which is zero
+    /// exactly if the directory is empty.
     fn len(&self) -> u64 {
         self.len
     }
@@ -53,4 +61,16 @@ impl UniMetadata for MemoryMetadata {
     fn created(&self) -> Result<SystemTime> {
         Ok(self.file_times.created)
     }
+
+    fn nlink(&self) -> Option<u64> {
+        Some(self.nlink)
+    }
+
+    fn uid(&self) -> Option<u32> {
+        Some(self.owner.uid)
+    }
+
+    fn gid(&self) -> Option<u32> {
+        Some(self.owner.gid)
+    }
 }
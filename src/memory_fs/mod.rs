@@ -1,44 +1,246 @@
 //! This module provides an in-memory filesystem implementation.
 
 use std::{
-    collections::{HashMap, HashSet, VecDeque},
-    ffi::OsString,
+    collections::{BTreeSet, HashMap, VecDeque},
+    ffi::{OsStr, OsString},
     io::{Error, ErrorKind},
     path::{Path, PathBuf},
-    sync::Arc,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
     time::SystemTime,
 };
 
 use crate::{
     memory_fs::{
-        dir_builder::MemoryDirBuilder, file::MemoryFile, metadata::MemoryMetadata,
-        open_options::MemoryOpenOptions,
+        capacity::Capacity, contents::FileContents, dir_builder::MemoryDirBuilder,
+        file::MemoryFile, metadata::MemoryMetadata, open_options::MemoryOpenOptions,
     },
     rw_lock::RwLock,
-    Permissions, UniDirEntry, UniFs,
+    Permissions, UniDirEntry, UniFs, UniOpenOptions as _, WriteReport,
 };
 
+/// Sends an event to the subscribers of the filesystem if the `notify` feature is enabled.
+macro_rules! notify {
+    ($inner:expr, $event:expr) => {
+        #[cfg(feature = "notify")]
+        $inner.notify($event);
+    };
+}
+
+mod capacity;
+mod clock;
+mod contents;
 mod dir_builder;
 mod extra;
 mod file;
 mod metadata;
 mod open_options;
-
+mod snapshot;
+
+#[cfg(feature = "futures")]
+mod async_io;
+#[cfg(feature = "notify")]
+mod notify;
+#[cfg(feature = "serde")]
+mod serialize;
+#[cfg(feature = "tar")]
+mod tar;
 #[cfg(feature = "zip")]
 mod zip;
 
+pub use clock::{Clock, ManualClock, SystemClock};
+pub use file::RangeLockGuard;
+#[cfg(feature = "notify")]
+pub use notify::FsEvent;
+pub use snapshot::Snapshot;
+
 /// The `MemoryFs` struct provides a filesystem interface that operates entirely in memory.
+///
+/// Directory entries are listed in the byte order of their names. With the
+/// `unicode-normalization` feature, [`MemoryFs::set_normalize_names`] lists them by
+/// their NFC form instead.
+///
+/// Files are sparse: extending a file, with `set_len` or by writing past its end, does
+/// not allocate memory for the bytes in between, which read as zeros.
+///
+/// Clones are handles to the same filesystem: changes and settings, such as the
+/// current directory or the path limits, are shared by all handles.
+///
+/// Two instances compare equal if they contain the same paths with the same entry
+/// types, file contents and permissions. Timestamps are not compared.
+#[derive(Debug)]
 pub struct MemoryFs {
     inner: Arc<RwLock<MemoryFsInner>>,
 }
 
 impl MemoryFs {
+    /// The maximum length of a path in bytes used by [`MemoryFs::new`].
+    pub const DEFAULT_MAX_PATH_LEN: usize = 4096;
+
+    /// The maximum length of a file name in bytes used by [`MemoryFs::new`].
+    pub const DEFAULT_MAX_NAME_LEN: usize = 255;
+
     /// Create a new instance of a `MemoryFs` without any files or directories.
     pub fn new() -> Self {
         MemoryFs {
             inner: Arc::new(RwLock::new(MemoryFsInner::new())),
         }
     }
+
+    /// Create a new instance of a `MemoryFs` without any files or directories, which
+    /// takes the timestamps of its entries from `clock`.
+    ///
+    /// Every timestamp set by the filesystem, including the creation time of the root
+    /// directory, is the [`Clock::now`] of the clock at the time of the operation.
+    /// Timestamps set explicitly, for example with
+    /// [`UniFile::set_times`](crate::UniFile::set_times), are kept as they are.
+    pub fn with_clock<C: Clock + 'static>(clock: C) -> Self {
+        MemoryFs {
+            inner: Arc::new(RwLock::new(MemoryFsInner::with_clock(Arc::new(clock)))),
+        }
+    }
+
+    /// Create a new instance of a `MemoryFs` without any files or directories, which
+    /// compares names case-insensitively like the default filesystems of Windows and
+    /// macOS.
+    ///
+    /// Paths resolve to existing entries regardless of the case of their names, so
+    /// creating `/Foo` and then opening `/foo` opens the same file, and creating a new
+    /// file at `/FOO` fails with [`ErrorKind::AlreadyExists`]. The case a name was
+    /// created with is preserved in directory listings and canonical paths, and can be
+    /// changed by renaming the entry. Names are compared by their lowercase form, names
+    /// that are not valid Unicode are compared exactly.
+    pub fn new_case_insensitive() -> Self {
+        let mut inner = MemoryFsInner::new();
+        inner.case_insensitive = true;
+
+        MemoryFs {
+            inner: Arc::new(RwLock::new(inner)),
+        }
+    }
+
+    /// Replaces the clock that the timestamps of entries are taken from, see
+    /// [`MemoryFs::with_clock`].
+    ///
+    /// Timestamps that were set before are kept.
+    pub fn set_clock<C: Clock + 'static>(&self, clock: C) {
+        self.inner.write().clock = Arc::new(clock);
+    }
+
+    /// Sets whether names are compared case-insensitively, see
+    /// [`MemoryFs::new_case_insensitive`].
+    ///
+    /// This can only be changed while the filesystem is empty, as existing names could
+    /// collide otherwise. It fails with [`ErrorKind::DirectoryNotEmpty`] if the root
+    /// directory contains any entries.
+    pub fn set_case_insensitive(&self, case_insensitive: bool) -> crate::Result<()> {
+        let mut inner = self.inner.write();
+        if inner.files.len() > 1 {
+            return Err(Error::new(
+                ErrorKind::DirectoryNotEmpty,
+                "Cannot change case sensitivity of a non-empty filesystem",
+            ));
+        }
+
+        inner.case_insensitive = case_insensitive;
+        Ok(())
+    }
+
+    /// Returns the current directory, against which relative paths are resolved.
+    ///
+    /// The current directory is `/` unless changed with [`MemoryFs::set_current_dir`].
+    pub fn current_dir(&self) -> PathBuf {
+        self.inner.read().current_dir.clone()
+    }
+
+    /// Changes the current directory, against which relative paths are resolved.
+    ///
+    /// The path is resolved against the previous current directory and must be an
+    /// existing directory. Removing the current directory afterwards does not reset it.
+    pub fn set_current_dir<P: AsRef<Path>>(&self, path: P) -> crate::Result<()> {
+        let mut inner = self.inner.write();
+        let path = canonicalize_inner(&inner, path, true)?;
+
+        match inner.files.get(&path) {
+            Some(entry) if matches!(entry.file_type, MemoryEntryType::Directory(_)) => {
+                inner.current_dir = path;
+                Ok(())
+            }
+            Some(_) => Err(Error::new(
+                ErrorKind::NotADirectory,
+                format!("Path '{}' is not a directory", path.display()),
+            )),
+            None => Err(missing_path_error(&inner, &path)),
+        }
+    }
+
+    /// Sets whether reading a file or directory updates its access time.
+    ///
+    /// Access times are tracked by default. Turning this off lets concurrent reads
+    /// proceed without taking the write lock of the filesystem.
+    pub fn set_track_atime(&self, track: bool) {
+        self.inner.write().track_atime = track;
+    }
+
+    /// Sets whether directory entries are listed by the NFC form of their names
+    /// instead of their bytes.
+    ///
+    /// Entries are listed in byte order by default. Listing them by their NFC form
+    /// makes the order independent of how the names were normalized when written.
+    /// The names themselves are not changed, and names with the same NFC form keep
+    /// their byte order.
+    #[cfg(feature = "unicode-normalization")]
+    pub fn set_normalize_names(&self, normalize: bool) {
+        self.inner.write().normalize_names = normalize;
+    }
+
+    /// Sets characters that are rejected in paths, in addition to the NUL byte.
+    ///
+    /// This allows to mimic the restrictions of other filesystems, for example of
+    /// Windows with `['<', '>', ':', '"', '|', '?', '*']`. Paths containing one of the
+    /// characters fail with [`ErrorKind::InvalidInput`].
+    pub fn set_illegal_chars<I: IntoIterator<Item = char>>(&self, chars: I) {
+        self.inner.write().illegal_chars = chars.into_iter().collect();
+    }
+
+    /// Limits the length of absolute paths to `len` bytes, or removes the limit if
+    /// `None`.
+    ///
+    /// Like on real filesystems, longer paths fail with
+    /// [`ErrorKind::InvalidFilename`]. The limit defaults to
+    /// [`MemoryFs::DEFAULT_MAX_PATH_LEN`].
+    pub fn set_max_path_len(&self, len: Option<usize>) {
+        self.inner.write().max_path_len = len;
+    }
+
+    /// Limits the length of each file name in a path to `len` bytes, or removes the
+    /// limit if `None`.
+    ///
+    /// Longer names fail with [`ErrorKind::InvalidFilename`]. The limit defaults to
+    /// [`MemoryFs::DEFAULT_MAX_NAME_LEN`].
+    pub fn set_max_name_len(&self, len: Option<usize>) {
+        self.inner.write().max_name_len = len;
+    }
+
+    /// Runs `read_fn` on `path` and updates the access time of the entry it resolves
+    /// to, if access times are tracked.
+    fn read_tracked<T>(
+        &self,
+        path: &Path,
+        read_fn: impl FnOnce(&MemoryFsInner, &Path) -> crate::Result<T>,
+    ) -> crate::Result<T> {
+        let inner = self.inner.read();
+        let result = read_fn(&inner, path)?;
+        if inner.track_atime {
+            let path = canonicalize_inner(&inner, path, true)?;
+            if let Some(entry) = inner.files.get(&path) {
+                entry.attributes.write().accessed = Some(inner.clock.now());
+            }
+        }
+        Ok(result)
+    }
 }
 
 impl Default for MemoryFs {
@@ -47,66 +249,285 @@ impl Default for MemoryFs {
     }
 }
 
+/// Creates another handle to the same filesystem.
+///
+/// This is not a deep copy: both handles share the same entries and settings, so
+/// changes made through one are visible through the other. Use
+/// [`MemoryFs::deep_clone`] to get an independent copy.
+impl Clone for MemoryFs {
+    fn clone(&self) -> Self {
+        MemoryFs {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl PartialEq for MemoryFs {
+    fn eq(&self, other: &Self) -> bool {
+        if Arc::ptr_eq(&self.inner, &other.inner) {
+            return true;
+        }
+
+        let inner = self.inner.read();
+        let other_inner = other.inner.read();
+
+        inner.files.len() == other_inner.files.len()
+            && inner.files.iter().all(|(path, entry)| {
+                other_inner
+                    .files
+                    .get(path)
+                    .is_some_and(|other_entry| entry.content_eq(other_entry))
+            })
+    }
+}
+
+impl Eq for MemoryFs {}
+
 #[derive(Debug)]
 struct MemoryFsInner {
     files: HashMap<PathBuf, MemoryEntry>,
+    current_dir: PathBuf,
+    range_locks: Vec<RangeLock>,
+    next_range_lock_id: u64,
+    /// Whether reads update the access time, see [`MemoryFs::set_track_atime`].
+    track_atime: bool,
+    /// Characters rejected in paths, see [`MemoryFs::set_illegal_chars`].
+    illegal_chars: Vec<char>,
+    /// See [`MemoryFs::set_max_path_len`].
+    max_path_len: Option<usize>,
+    /// See [`MemoryFs::set_max_name_len`].
+    max_name_len: Option<usize>,
+    /// See [`MemoryFs::new_case_insensitive`].
+    case_insensitive: bool,
+    /// See [`MemoryFs::set_normalize_names`].
+    #[cfg(feature = "unicode-normalization")]
+    normalize_names: bool,
+    /// The source of timestamps, see [`MemoryFs::with_clock`].
+    clock: Arc<dyn Clock>,
+    /// The bytes used by files, see [`MemoryFs::set_capacity`].
+    capacity: Arc<Capacity>,
+    #[cfg(feature = "notify")]
+    subscribers: Vec<std::sync::mpsc::Sender<FsEvent>>,
+}
+
+/// A byte range of a file locked through [`MemoryFile::lock_range`].
+#[derive(Debug)]
+struct RangeLock {
+    id: u64,
+    /// Identifies the locked file, independently of its path.
+    data: Arc<RwLock<FileContents>>,
+    start: u64,
+    end: u64,
+    exclusive: bool,
 }
 
 impl MemoryFsInner {
     pub fn new() -> Self {
+        Self::with_clock(Arc::new(SystemClock))
+    }
+
+    fn with_clock(clock: Arc<dyn Clock>) -> Self {
         let mut files = HashMap::new();
 
         // Create the root directory entry
         let root_path = PathBuf::from("/");
-        let root_entry = MemoryEntry {
-            file_type: MemoryEntryType::Directory(HashSet::new()),
-            created: SystemTime::now(),
-            modified: None,
-            accessed: None,
-            permissions: Permissions { readonly: false },
-        };
-        files.insert(root_path, root_entry);
-
-        MemoryFsInner { files }
+        files.insert(root_path.clone(), MemoryEntry::root(clock.now()));
+
+        MemoryFsInner {
+            files,
+            current_dir: root_path,
+            range_locks: Vec::new(),
+            next_range_lock_id: 0,
+            track_atime: true,
+            illegal_chars: Vec::new(),
+            max_path_len: Some(MemoryFs::DEFAULT_MAX_PATH_LEN),
+            max_name_len: Some(MemoryFs::DEFAULT_MAX_NAME_LEN),
+            case_insensitive: false,
+            #[cfg(feature = "unicode-normalization")]
+            normalize_names: false,
+            clock,
+            capacity: Arc::new(Capacity::new(None)),
+            #[cfg(feature = "notify")]
+            subscribers: Vec::new(),
+        }
     }
 }
 
 #[derive(Debug, Clone)]
 struct MemoryEntry {
     file_type: MemoryEntryType,
+    /// Shared between the hard links to a file and the handles opened on it.
+    attributes: Arc<RwLock<Attributes>>,
+}
+
+/// The times, permissions and owner of an entry.
+///
+/// Like the attributes of an inode, they belong to the file rather than to one of its
+/// names. They have their own lock, so that reads can update the access time while only
+/// holding a read lock on the filesystem.
+#[derive(Debug, Clone)]
+struct Attributes {
     created: SystemTime,
     modified: Option<SystemTime>,
     accessed: Option<SystemTime>,
     permissions: Permissions,
+    owner: Owner,
 }
 
-impl MemoryEntry {
-    fn metadata(&self) -> MemoryMetadata {
+impl Attributes {
+    /// Creates the attributes of a writable entry owned by the current process.
+    fn new(created: SystemTime, modified: Option<SystemTime>) -> Self {
+        Attributes {
+            created,
+            modified,
+            accessed: None,
+            permissions: Permissions { readonly: false },
+            owner: Owner::current(),
+        }
+    }
+
+    /// Builds the metadata of an entry with these attributes.
+    fn metadata(&self, file_type: crate::FileType, len: u64, nlink: u64) -> MemoryMetadata {
         MemoryMetadata {
-            file_type: self.file_type.clone().into(),
-            len: match &self.file_type {
-                MemoryEntryType::File(data) => data.read().len() as u64,
-                _ => 0,
-            },
+            file_type,
+            len,
+            nlink,
             permissions: self.permissions.clone(),
             file_times: crate::FileTimes {
                 created: self.created,
                 modified: self.modified,
                 accessed: self.accessed,
             },
+            owner: self.owner,
+        }
+    }
+}
+
+/// The owner of an entry, see [`MemoryFs::set_owner`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Owner {
+    uid: u32,
+    gid: u32,
+}
+
+impl Owner {
+    /// The owner of new entries: the user and group of the current process on Unix,
+    /// and `0` elsewhere.
+    fn current() -> Self {
+        #[cfg(unix)]
+        // SAFETY: `getuid` and `getgid` are always successful and have no preconditions.
+        let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+        #[cfg(not(unix))]
+        let (uid, gid) = (0, 0);
+
+        Owner { uid, gid }
+    }
+}
+
+impl MemoryEntry {
+    /// Creates an entry with its own attributes.
+    fn new(file_type: MemoryEntryType, attributes: Attributes) -> Self {
+        MemoryEntry {
+            file_type,
+            attributes: Arc::new(RwLock::new(attributes)),
+        }
+    }
+
+    /// Creates the entry of an empty root directory, created at `created`.
+    fn root(created: SystemTime) -> Self {
+        MemoryEntry::new(
+            MemoryEntryType::Directory(BTreeSet::new()),
+            Attributes::new(created, None),
+        )
+    }
+
+    fn metadata(&self) -> MemoryMetadata {
+        let len = match &self.file_type {
+            MemoryEntryType::File(file) => file.data.read().len(),
+            // Like POSIX, the length of a link is the length of its target path.
+            MemoryEntryType::Symlink(target) => target.as_os_str().len() as u64,
+            // Directories report the number of their entries, so that empty ones
+            // can be told apart.
+            MemoryEntryType::Directory(children) => children.len() as u64,
+        };
+        let nlink = match &self.file_type {
+            MemoryEntryType::File(file) => file.links.load(Ordering::Acquire),
+            MemoryEntryType::Directory(_) | MemoryEntryType::Symlink(_) => 1,
+        };
+        self.attributes
+            .read()
+            .metadata(self.file_type.clone().into(), len, nlink)
+    }
+
+    /// Compares the type, contents and permissions of two entries, ignoring timestamps.
+    fn content_eq(&self, other: &Self) -> bool {
+        // Hard links share their attributes, so only one lock is held at a time.
+        let permissions = self.attributes.read().permissions.clone();
+        if permissions != other.attributes.read().permissions {
+            return false;
+        }
+
+        match (&self.file_type, &other.file_type) {
+            (MemoryEntryType::File(file), MemoryEntryType::File(other_file)) => {
+                file.same_file(other_file) || *file.data.read() == *other_file.data.read()
+            }
+            (MemoryEntryType::Directory(children), MemoryEntryType::Directory(other_children)) => {
+                children == other_children
+            }
+            (MemoryEntryType::Symlink(target), MemoryEntryType::Symlink(other_target)) => {
+                target == other_target
+            }
+            _ => false,
         }
     }
 }
 
 #[derive(Debug, Clone)]
 enum MemoryEntryType {
-    File(Arc<RwLock<Vec<u8>>>),
-    Directory(HashSet<OsString>),
-    HardLink(PathBuf),
+    File(FileData),
+    Directory(BTreeSet<OsString>),
+    /// The target exactly as given, resolved relative to the parent of the link.
+    Symlink(PathBuf),
+}
+
+/// The contents of a file, shared by all hard links to it.
+#[derive(Debug, Clone)]
+struct FileData {
+    data: Arc<RwLock<FileContents>>,
+    /// The number of entries referring to `data`.
+    links: Arc<AtomicU64>,
+}
+
+impl FileData {
+    fn new(data: FileContents) -> Self {
+        FileData {
+            data: Arc::new(RwLock::new(data)),
+            links: Arc::new(AtomicU64::new(1)),
+        }
+    }
+
+    /// Returns whether both are links to the same file.
+    fn same_file(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.data, &other.data)
+    }
 }
 
 impl MemoryEntryType {
-    fn as_directory_mut(&mut self) -> Option<&mut HashSet<OsString>> {
+    /// Accounts for the removal of an entry of this type from the filesystem.
+    ///
+    /// The data of a file is freed with its last link, unless it is still open.
+    fn unlink(&self, capacity: &Capacity) {
+        if let MemoryEntryType::File(file) = self {
+            // Writes through open handles check the link count while holding the
+            // data lock, so they either count towards the capacity or not at all.
+            let data = file.data.write();
+            if file.links.fetch_sub(1, Ordering::AcqRel) == 1 {
+                capacity.release(data.len());
+            }
+        }
+    }
+
+    fn as_directory_mut(&mut self) -> Option<&mut BTreeSet<OsString>> {
         if let MemoryEntryType::Directory(ref mut set) = self {
             Some(set)
         } else {
@@ -120,27 +541,45 @@ impl From<MemoryEntryType> for crate::FileType {
         match entry_type {
             MemoryEntryType::File(_) => crate::FileType::File,
             MemoryEntryType::Directory(_) => crate::FileType::Directory,
-            MemoryEntryType::HardLink(_) => crate::FileType::Symlink,
+            MemoryEntryType::Symlink(_) => crate::FileType::Symlink,
         }
     }
 }
 
+/// The maximum number of symbolic links followed while resolving a single path.
+const MAX_SYMLINK_FOLLOWS: usize = 40;
+
+/// Resolves `path` to the absolute path of the entry it refers to.
+///
+/// Links in the ancestors of the path are always followed. A link at the path itself
+/// is only followed if `follow_last` is set.
 fn canonicalize_inner<P: AsRef<Path>>(
     inner: &MemoryFsInner,
     path: P,
-    resolve_hardlinks: bool,
+    follow_last: bool,
 ) -> crate::Result<PathBuf> {
     use std::path::Component;
 
-    let mut buf = PathBuf::new();
+    let path = path.as_ref();
+    let mut buf = if path.is_relative() {
+        inner.current_dir.clone()
+    } else {
+        PathBuf::new()
+    };
 
-    for comp in path.as_ref().components() {
+    for comp in path.components() {
         match comp {
             Component::CurDir => {}
             Component::Normal(name) => {
-                buf.push(name);
+                validate_name(inner, name)?;
+                buf.push(stored_name(inner, &buf, name));
             }
             Component::ParentDir => {
+                // Like POSIX, `..` refers to the parent of the directory a symbolic
+                // link resolves to, not to the directory containing the link.
+                if buf.has_root() {
+                    buf = resolve_links(inner, &buf, true)?;
+                }
                 if !buf.pop() {
                     return Err(Error::new(ErrorKind::NotFound, "No parent directory"));
                 }
@@ -156,37 +595,210 @@ fn canonicalize_inner<P: AsRef<Path>>(
         buf = Path::new("/").join(buf);
     }
 
-    if resolve_hardlinks {
-        let resolve = match inner.files.get(&buf) {
-            Some(entry) if matches!(entry.file_type, MemoryEntryType::HardLink(_)) => true,
-            None => true,
-            _ => false,
+    if let Some(max) = inner.max_path_len {
+        let len = buf.as_os_str().len();
+        if len > max {
+            return Err(Error::new(
+                ErrorKind::InvalidFilename,
+                format!(
+                    "Path '{}' is {len} bytes long, the limit is {max}",
+                    buf.display()
+                ),
+            ));
+        }
+    }
+
+    let resolve = match inner.files.get(&buf) {
+        Some(entry) => follow_last && matches!(entry.file_type, MemoryEntryType::Symlink(_)),
+        None => true,
+    };
+    if resolve {
+        buf = resolve_links(inner, &buf, follow_last)?;
+    }
+
+    Ok(buf)
+}
+
+/// Rejects names containing a NUL byte, like `std::fs` does, or one of the
+/// configured illegal characters, and names exceeding the configured length.
+fn validate_name(inner: &MemoryFsInner, name: &OsStr) -> crate::Result<()> {
+    if let Some(max) = inner.max_name_len {
+        if name.len() > max {
+            return Err(Error::new(
+                ErrorKind::InvalidFilename,
+                format!(
+                    "File name '{}' is {} bytes long, the limit is {max}",
+                    name.to_string_lossy().escape_debug(),
+                    name.len()
+                ),
+            ));
+        }
+    }
+
+    let illegal = name.as_encoded_bytes().contains(&0)
+        || (!inner.illegal_chars.is_empty()
+            && name
+                .to_string_lossy()
+                .chars()
+                .any(|c| inner.illegal_chars.contains(&c)));
+
+    if illegal {
+        Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "File name '{}' contains an illegal character",
+                name.to_string_lossy().escape_debug()
+            ),
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Returns the name of the child of the directory `parent` that `name` refers to.
+///
+/// On a case-insensitive filesystem, this is the name of an existing child matching
+/// `name` regardless of case. Otherwise, or if there is no such child, it is `name`.
+fn stored_name(inner: &MemoryFsInner, parent: &Path, name: &OsStr) -> OsString {
+    if !inner.case_insensitive {
+        return name.to_os_string();
+    }
+    let Some(MemoryEntryType::Directory(children)) =
+        inner.files.get(parent).map(|entry| &entry.file_type)
+    else {
+        return name.to_os_string();
+    };
+    if children.contains(name) {
+        return name.to_os_string();
+    }
+
+    let Some(lowercase) = name.to_str().map(str::to_lowercase) else {
+        return name.to_os_string();
+    };
+    children
+        .iter()
+        .find(|child| child.to_str().map(str::to_lowercase).as_ref() == Some(&lowercase))
+        .cloned()
+        .unwrap_or_else(|| name.to_os_string())
+}
+
+/// Follows the links in the absolute, normalized path `path`.
+///
+/// The targets of symbolic links are resolved component by component, so `..` in a
+/// target refers to the parent of the directory the link resolved to.
+fn resolve_links(inner: &MemoryFsInner, path: &Path, follow_last: bool) -> crate::Result<PathBuf> {
+    use std::path::Component;
+
+    // `None` stands for a `..` component that is still to be resolved.
+    let mut pending = path
+        .components()
+        .filter_map(|comp| match comp {
+            Component::Normal(name) => Some(Some(name.to_os_string())),
+            _ => None,
+        })
+        .collect::<VecDeque<_>>();
+    let mut current_path = PathBuf::from("/");
+    let mut follows = 0;
+
+    while let Some(comp) = pending.pop_front() {
+        let Some(name) = comp else {
+            current_path.pop();
+            continue;
         };
-        if resolve {
-            let mut current_path = PathBuf::from("/");
-            for comp in buf.components() {
-                match comp {
-                    Component::Normal(name) => {
-                        current_path.push(name);
-                        if let Some(entry) = inner.files.get(&current_path) {
-                            if let MemoryEntryType::HardLink(target) = &entry.file_type {
-                                current_path = target.clone();
-                            }
-                        }
-                    }
-                    Component::ParentDir => {
-                        if !current_path.pop() {
-                            return Err(Error::new(ErrorKind::NotFound, "No parent directory"));
-                        }
+        current_path.push(stored_name(inner, &current_path, &name));
+
+        let follow = follow_last || !pending.is_empty();
+        match inner.files.get(&current_path).map(|entry| &entry.file_type) {
+            Some(MemoryEntryType::Symlink(target)) if follow => {
+                follows += 1;
+                if follows > MAX_SYMLINK_FOLLOWS {
+                    return Err(filesystem_loop_error(path));
+                }
+
+                current_path.pop();
+                if target.has_root() {
+                    current_path = PathBuf::from("/");
+                }
+                for comp in target.components().rev() {
+                    match comp {
+                        Component::Normal(name) => pending.push_front(Some(name.to_os_string())),
+                        Component::ParentDir => pending.push_front(None),
+                        _ => {}
                     }
-                    _ => {}
                 }
             }
-            buf = current_path;
+            _ => {}
         }
     }
 
-    Ok(buf)
+    Ok(current_path)
+}
+
+/// Creates the error for a path whose resolution follows too many symbolic links.
+///
+/// [`ErrorKind::FilesystemLoop`] cannot be named on stable Rust yet, so the kind is
+/// taken from the error the operating system reports for symbolic link loops.
+fn filesystem_loop_error(path: &Path) -> Error {
+    #[cfg(unix)]
+    let kind = Error::from_raw_os_error(libc::ELOOP).kind();
+    // `ERROR_CANT_RESOLVE_FILENAME`
+    #[cfg(windows)]
+    let kind = Error::from_raw_os_error(1921).kind();
+    #[cfg(not(any(unix, windows)))]
+    let kind = ErrorKind::Other;
+
+    Error::new(
+        kind,
+        format!(
+            "Too many levels of symbolic links while resolving '{}'",
+            path.display()
+        ),
+    )
+}
+
+/// Creates the error for a path that does not exist.
+///
+/// Like `std::fs`, this is [`ErrorKind::NotADirectory`] if an ancestor of the path
+/// is not a directory and [`ErrorKind::NotFound`] otherwise.
+fn missing_path_error(inner: &MemoryFsInner, path: &Path) -> Error {
+    for ancestor in path.ancestors().skip(1) {
+        if let Some(entry) = inner.files.get(ancestor) {
+            if !matches!(entry.file_type, MemoryEntryType::Directory(_)) {
+                return Error::new(
+                    ErrorKind::NotADirectory,
+                    format!("Path '{}' is not a directory", ancestor.display()),
+                );
+            }
+            break;
+        }
+    }
+
+    Error::new(
+        ErrorKind::NotFound,
+        format!("Path '{}' does not exist", path.display()),
+    )
+}
+
+/// Fails with [`ErrorKind::PermissionDenied`] if the entry at `path` is read-only.
+///
+/// For a directory, this guards adding and removing its children, like a directory
+/// without write permission on Unix. Missing entries are not checked.
+fn check_writable(inner: &MemoryFsInner, path: &Path) -> crate::Result<()> {
+    match inner.files.get(path) {
+        Some(entry) if entry.attributes.read().permissions.readonly => Err(Error::new(
+            ErrorKind::PermissionDenied,
+            format!("Path '{}' is read-only", path.display()),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Checks that the parent directory of `path` allows adding or removing `path`.
+fn check_writable_parent(inner: &MemoryFsInner, path: &Path) -> crate::Result<()> {
+    match path.parent() {
+        Some(parent) => check_writable(inner, parent),
+        None => Ok(()),
+    }
 }
 
 fn is_dir(inner: &MemoryFsInner, path: &Path) -> crate::Result<bool> {
@@ -202,28 +814,29 @@ fn is_dir(inner: &MemoryFsInner, path: &Path) -> crate::Result<bool> {
     }
 }
 
+/// Removes the entry at `path` and everything below it.
+///
+/// Every removed entry is detached from the child set of its parent, so no directory
+/// is left listing a removed entry.
 fn remove_recursive(path: &Path, inner: &mut MemoryFsInner) -> crate::Result<()> {
     if let Some(entry) = inner.files.get(path) {
-        match &entry.file_type {
-            MemoryEntryType::Directory(files) => {
-                let files = files.clone();
-                for file_name in files.iter() {
-                    let file_path = path.join(file_name);
-                    remove_recursive(&file_path, inner)?;
-                }
+        if let MemoryEntryType::Directory(files) = &entry.file_type {
+            let files = files.clone();
+            for file_name in files.iter() {
+                let file_path = path.join(file_name);
+                remove_recursive(&file_path, inner)?;
             }
-            MemoryEntryType::File(_) => {
-                if let Some(parent) = path.parent() {
-                    if let Some(parent_entry) = inner.files.get_mut(parent) {
-                        if let Some(files) = parent_entry.file_type.as_directory_mut() {
-                            files.remove(path.file_name().unwrap());
-                        }
-                    }
+        }
+        if let (Some(parent), Some(file_name)) = (path.parent(), path.file_name()) {
+            if let Some(parent_entry) = inner.files.get_mut(parent) {
+                if let Some(files) = parent_entry.file_type.as_directory_mut() {
+                    files.remove(file_name);
                 }
             }
-            MemoryEntryType::HardLink(_) => {}
         }
-        inner.files.remove(path);
+        if let Some(entry) = inner.files.remove(path) {
+            entry.file_type.unlink(&inner.capacity);
+        }
         Ok(())
     } else {
         Err(Error::new(
@@ -239,10 +852,11 @@ fn change_path_recursive(
     to: &Path,
     subpath: &Path,
 ) -> crate::Result<()> {
+    let now = inner.clock.now();
     let from_path = from.join(subpath);
     let to_path = to.join(subpath);
 
-    if let Some(mut entry) = inner.files.remove(&from_path) {
+    if let Some(entry) = inner.files.remove(&from_path) {
         match &entry.file_type {
             MemoryEntryType::Directory(files) => {
                 let files = files.clone();
@@ -251,9 +865,10 @@ fn change_path_recursive(
                     change_path_recursive(inner, from, to, &new_subpath)?;
                 }
             }
-            MemoryEntryType::File(_) | MemoryEntryType::HardLink(_) => {
-                entry.accessed = Some(SystemTime::now());
-                entry.modified = Some(SystemTime::now());
+            MemoryEntryType::File(_) | MemoryEntryType::Symlink(_) => {
+                let mut attributes = entry.attributes.write();
+                attributes.accessed = Some(now);
+                attributes.modified = Some(now);
             }
         }
         inner.files.insert(to_path, entry);
@@ -275,6 +890,7 @@ fn copy<P: AsRef<Path>, Q: AsRef<Path>>(
     from: P,
     to: Q,
 ) -> crate::Result<u64> {
+    let now = inner.clock.now();
     let from = canonicalize_inner(inner, from, true)?;
     let to = canonicalize_inner(inner, to, true)?;
 
@@ -286,16 +902,54 @@ fn copy<P: AsRef<Path>, Q: AsRef<Path>>(
     })?;
 
     let from_filetype = from_entry.file_type.to_owned();
+    let from_permissions = from_entry.attributes.read().permissions.clone();
 
-    if let MemoryEntryType::File(data) = from_filetype {
-        let data = data.read();
-        let new_entry = MemoryEntry {
-            file_type: MemoryEntryType::File(Arc::new(RwLock::new(data.clone()))),
-            created: SystemTime::now(),
-            modified: Some(SystemTime::now()),
-            accessed: None,
-            permissions: from_entry.permissions.clone(),
-        };
+    if let MemoryEntryType::File(from_file) = from_filetype {
+        let data = from_file.data.read();
+
+        // Copying a file onto itself leaves it untouched.
+        if from == to {
+            return Ok(data.len());
+        }
+
+        if let Some(MemoryEntryType::Directory(_)) =
+            inner.files.get(&to).map(|entry| &entry.file_type)
+        {
+            return Err(Error::new(
+                ErrorKind::IsADirectory,
+                format!("Destination '{}' is a directory", to.display()),
+            ));
+        }
+
+        // An existing destination file is overwritten in place, like `std::fs::copy`
+        // does. This reuses its buffer instead of holding the old and the new contents
+        // at the same time, and open handles to the destination see the new contents.
+        if inner.files.contains_key(&to) {
+            check_writable(inner, &to)?;
+        } else {
+            check_writable_parent(inner, &to)?;
+        }
+        if let Some(to_entry) = inner.files.get(&to) {
+            if let MemoryEntryType::File(to_file) = &to_entry.file_type {
+                if !from_file.same_file(to_file) {
+                    let mut to_data = to_file.data.write();
+                    inner.capacity.resize(to_data.len(), data.len())?;
+                    *to_data = data.clone();
+                }
+                let mut attributes = to_entry.attributes.write();
+                attributes.modified = Some(now);
+                attributes.permissions = from_permissions;
+                return Ok(data.len());
+            }
+        }
+
+        let new_entry = MemoryEntry::new(
+            MemoryEntryType::File(FileData::new(data.clone())),
+            Attributes {
+                permissions: from_permissions,
+                ..Attributes::new(now, Some(now))
+            },
+        );
 
         if let (Some(from_parent), Some(to_parent)) = (from.parent(), to.parent()) {
             if !inner.files.contains_key(from_parent) {
@@ -310,10 +964,11 @@ fn copy<P: AsRef<Path>, Q: AsRef<Path>>(
 
             if let Some(to_parent_entry) = inner.files.get_mut(to_parent) {
                 if let MemoryEntryType::Directory(files) = &mut to_parent_entry.file_type {
+                    inner.capacity.reserve(data.len())?;
                     files.insert(to.file_name().unwrap().to_os_string());
                 } else {
                     return Err(Error::new(
-                        ErrorKind::InvalidInput,
+                        ErrorKind::NotADirectory,
                         format!("Parent '{}' is not a directory", to_parent.display()),
                     ));
                 }
@@ -328,8 +983,10 @@ fn copy<P: AsRef<Path>, Q: AsRef<Path>>(
             }
         }
 
-        inner.files.insert(to, new_entry);
-        Ok(data.len() as u64)
+        if inner.files.insert(to.clone(), new_entry).is_none() {
+            notify!(inner, FsEvent::Created(to));
+        }
+        Ok(data.len())
     } else {
         Err(Error::new(
             ErrorKind::InvalidInput,
@@ -339,6 +996,7 @@ fn copy<P: AsRef<Path>, Q: AsRef<Path>>(
 }
 
 fn create_dir<P: AsRef<Path>>(inner: &mut MemoryFsInner, path: P) -> crate::Result<()> {
+    let now = inner.clock.now();
     let path = canonicalize_inner(inner, path, false)?;
 
     if inner.files.contains_key(&path) {
@@ -355,27 +1013,26 @@ fn create_dir<P: AsRef<Path>>(inner: &mut MemoryFsInner, path: P) -> crate::Resu
                 format!("Parent directory '{}' does not exist", parent.display()),
             ));
         }
+        check_writable(inner, parent)?;
 
         if let Some(parent_entry) = inner.files.get_mut(parent) {
             if let MemoryEntryType::Directory(files) = &mut parent_entry.file_type {
                 files.insert(path.file_name().unwrap().to_os_string());
             } else {
                 return Err(Error::new(
-                    ErrorKind::InvalidInput,
+                    ErrorKind::NotADirectory,
                     format!("Parent '{}' is not a directory", parent.display()),
                 ));
             }
         }
     }
 
-    let new_entry = MemoryEntry {
-        file_type: MemoryEntryType::Directory(HashSet::new()),
-        created: SystemTime::now(),
-        modified: Some(SystemTime::now()),
-        accessed: None,
-        permissions: Permissions { readonly: false },
-    };
-    inner.files.insert(path, new_entry);
+    let new_entry = MemoryEntry::new(
+        MemoryEntryType::Directory(BTreeSet::new()),
+        Attributes::new(now, Some(now)),
+    );
+    inner.files.insert(path.clone(), new_entry);
+    notify!(inner, FsEvent::Created(path));
     Ok(())
 }
 
@@ -392,12 +1049,22 @@ fn hard_link<P: AsRef<Path>, Q: AsRef<Path>>(
     let original = canonicalize_inner(inner, original, true)?;
     let link = canonicalize_inner(inner, link, false)?;
 
-    if !inner.files.contains_key(&original) {
-        return Err(Error::new(
-            ErrorKind::NotFound,
-            format!("Original path '{}' does not exist", original.display()),
-        ));
-    }
+    let original_entry = match inner.files.get(&original) {
+        Some(entry) if matches!(entry.file_type, MemoryEntryType::File(_)) => entry.clone(),
+        // Like on most platforms, directories cannot be hard linked.
+        Some(_) => {
+            return Err(Error::new(
+                ErrorKind::PermissionDenied,
+                format!("Original path '{}' is a directory", original.display()),
+            ))
+        }
+        None => {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                format!("Original path '{}' does not exist", original.display()),
+            ))
+        }
+    };
 
     if inner.files.contains_key(&link) {
         return Err(Error::new(
@@ -415,18 +1082,17 @@ fn hard_link<P: AsRef<Path>, Q: AsRef<Path>>(
 
     if !is_dir(inner, link_parent)? {
         return Err(Error::new(
-            ErrorKind::NotFound,
+            ErrorKind::NotADirectory,
             format!("Parent directory for '{}' does not exist", link.display()),
         ));
     }
+    check_writable(inner, link_parent)?;
 
-    let new_entry = MemoryEntry {
-        file_type: MemoryEntryType::HardLink(original.clone()),
-        created: SystemTime::now(),
-        modified: Some(SystemTime::now()),
-        accessed: None,
-        permissions: Permissions { readonly: false },
-    };
+    // The link shares the data and the attributes of the original.
+    if let MemoryEntryType::File(file) = &original_entry.file_type {
+        file.links.fetch_add(1, Ordering::AcqRel);
+    }
+    let new_entry = original_entry;
 
     inner
         .files
@@ -437,7 +1103,8 @@ fn hard_link<P: AsRef<Path>, Q: AsRef<Path>>(
         .expect("Parent should be a directory")
         .insert(link.file_name().unwrap().to_os_string());
 
-    inner.files.insert(link, new_entry);
+    inner.files.insert(link.clone(), new_entry);
+    notify!(inner, FsEvent::Created(link));
 
     Ok(())
 }
@@ -448,10 +1115,7 @@ fn metadata<P: AsRef<Path>>(inner: &MemoryFsInner, path: P) -> crate::Result<Mem
     if let Some(entry) = inner.files.get(&path) {
         Ok(entry.metadata())
     } else {
-        Err(Error::new(
-            ErrorKind::NotFound,
-            format!("Path '{}' does not exist", path.display()),
-        ))
+        Err(missing_path_error(inner, &path))
     }
 }
 
@@ -459,19 +1123,16 @@ fn read<P: AsRef<Path>>(inner: &MemoryFsInner, path: P) -> crate::Result<Vec<u8>
     let path = canonicalize_inner(inner, path, true)?;
 
     if let Some(entry) = inner.files.get(&path) {
-        if let MemoryEntryType::File(data) = &entry.file_type {
-            Ok(data.read().clone())
+        if let MemoryEntryType::File(file) = &entry.file_type {
+            Ok(file.data.read().to_vec())
         } else {
             Err(Error::new(
-                ErrorKind::InvalidInput,
-                format!("Path '{}' is not a file", path.display()),
+                ErrorKind::IsADirectory,
+                format!("Path '{}' is a directory", path.display()),
             ))
         }
     } else {
-        Err(Error::new(
-            ErrorKind::NotFound,
-            format!("Path '{}' does not exist", path.display()),
-        ))
+        Err(missing_path_error(inner, &path))
     }
 }
 
@@ -480,10 +1141,27 @@ fn read_dir<P: AsRef<Path>>(inner: &MemoryFsInner, path: P) -> crate::Result<Mem
 
     if let Some(entry) = inner.files.get(&path) {
         if let MemoryEntryType::Directory(files) = &entry.file_type {
-            let mut entries = files.iter().cloned().collect::<Vec<_>>();
-            entries.sort();
-            let entries = entries
+            // The children are kept sorted, so entries are listed alphabetically.
+            let file_names = files.iter().collect::<Vec<_>>();
+
+            // Compare the NFC forms of the names instead if requested. The sort is
+            // stable, so names with the same normalized form keep their byte order.
+            #[cfg(feature = "unicode-normalization")]
+            let file_names = if inner.normalize_names {
+                use unicode_normalization::UnicodeNormalization as _;
+
+                let mut file_names = file_names;
+                file_names.sort_by_cached_key(|file_name| {
+                    file_name.to_string_lossy().nfc().collect::<String>()
+                });
+                file_names
+            } else {
+                file_names
+            };
+
+            let entries = file_names
                 .into_iter()
+                .cloned()
                 .map(|file_name| {
                     let path = path.join(&file_name);
                     let file_entry = inner.files.get(&path).ok_or_else(|| {
@@ -504,7 +1182,7 @@ fn read_dir<P: AsRef<Path>>(inner: &MemoryFsInner, path: P) -> crate::Result<Mem
             Ok(MemoryReadDir { entries })
         } else {
             Err(Error::new(
-                ErrorKind::InvalidInput,
+                ErrorKind::NotADirectory,
                 format!("Path '{}' is not a directory", path.display()),
             ))
         }
@@ -516,11 +1194,17 @@ fn read_dir<P: AsRef<Path>>(inner: &MemoryFsInner, path: P) -> crate::Result<Mem
     }
 }
 
-fn read_link<P: AsRef<Path>>(_path: P) -> crate::Result<PathBuf> {
-    Err(Error::new(
-        ErrorKind::Unsupported,
-        "MemoryFs does not support symbolic links",
-    ))
+fn read_link<P: AsRef<Path>>(inner: &MemoryFsInner, path: P) -> crate::Result<PathBuf> {
+    let path = canonicalize_inner(inner, path, false)?;
+
+    match inner.files.get(&path).map(|entry| &entry.file_type) {
+        Some(MemoryEntryType::Symlink(target)) => Ok(target.clone()),
+        Some(_) => Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("Path '{}' is not a symbolic link", path.display()),
+        )),
+        None => Err(missing_path_error(inner, &path)),
+    }
 }
 
 fn read_to_string<P: AsRef<Path>>(inner: &MemoryFsInner, path: P) -> crate::Result<String> {
@@ -534,7 +1218,8 @@ fn read_to_string<P: AsRef<Path>>(inner: &MemoryFsInner, path: P) -> crate::Resu
 }
 
 fn remove_dir<P: AsRef<Path>>(inner: &mut MemoryFsInner, path: P) -> crate::Result<()> {
-    let path = canonicalize_inner(inner, path, true)?;
+    let path = canonicalize_inner(inner, path, false)?;
+    check_writable_parent(inner, &path)?;
 
     if let Some(entry) = inner.files.get(&path) {
         if let MemoryEntryType::Directory(files) = &entry.file_type {
@@ -547,12 +1232,13 @@ fn remove_dir<P: AsRef<Path>>(inner: &mut MemoryFsInner, path: P) -> crate::Resu
                         files.remove(path.file_name().unwrap());
                     } else {
                         return Err(Error::new(
-                            ErrorKind::InvalidInput,
+                            ErrorKind::NotADirectory,
                             format!("Parent '{}' is not a directory", parent.display()),
                         ));
                     }
                 }
                 inner.files.remove(&path);
+                notify!(inner, FsEvent::Removed(path));
                 Ok(())
             } else {
                 Err(Error::new(
@@ -562,7 +1248,7 @@ fn remove_dir<P: AsRef<Path>>(inner: &mut MemoryFsInner, path: P) -> crate::Resu
             }
         } else {
             Err(Error::new(
-                ErrorKind::InvalidInput,
+                ErrorKind::NotADirectory,
                 format!("Path '{}' is not a directory", path.display()),
             ))
         }
@@ -574,8 +1260,13 @@ fn remove_dir<P: AsRef<Path>>(inner: &mut MemoryFsInner, path: P) -> crate::Resu
     }
 }
 
+/// Removes a directory and all of its contents.
+///
+/// The whole removal runs while the caller holds the write lock of the filesystem,
+/// so no entries can be added below the directory while it is being traversed.
 fn remove_dir_all<P: AsRef<Path>>(inner: &mut MemoryFsInner, path: P) -> crate::Result<()> {
-    let path = canonicalize_inner(inner, path, true)?;
+    let path = canonicalize_inner(inner, path, false)?;
+    check_writable_parent(inner, &path)?;
 
     if let Some(entry) = inner.files.get(&path) {
         if let MemoryEntryType::Directory(files) = &entry.file_type {
@@ -592,16 +1283,17 @@ fn remove_dir_all<P: AsRef<Path>>(inner: &mut MemoryFsInner, path: P) -> crate::
                     files.remove(path.file_name().unwrap());
                 } else {
                     return Err(Error::new(
-                        ErrorKind::InvalidInput,
+                        ErrorKind::NotADirectory,
                         format!("Parent '{}' is not a directory", parent.display()),
                     ));
                 }
             }
             inner.files.remove(&path);
+            notify!(inner, FsEvent::Removed(path));
             Ok(())
         } else {
             Err(Error::new(
-                ErrorKind::InvalidInput,
+                ErrorKind::NotADirectory,
                 format!("Path '{}' is not a directory", path.display()),
             ))
         }
@@ -614,10 +1306,12 @@ fn remove_dir_all<P: AsRef<Path>>(inner: &mut MemoryFsInner, path: P) -> crate::
 }
 
 fn remove_file<P: AsRef<Path>>(inner: &mut MemoryFsInner, path: P) -> crate::Result<()> {
-    let path = canonicalize_inner(inner, path, true)?;
+    let path = canonicalize_inner(inner, path, false)?;
+    check_writable_parent(inner, &path)?;
 
     if let Some(entry) = inner.files.get(&path) {
-        if let MemoryEntryType::File(_) = entry.file_type {
+        // Links are removed themselves, not the entries they point to.
+        if !matches!(entry.file_type, MemoryEntryType::Directory(_)) {
             if let Some(parent) = path.parent() {
                 if let Some(parent_entry) = inner.files.get_mut(parent) {
                     if let Some(files) = parent_entry.file_type.as_directory_mut() {
@@ -626,12 +1320,15 @@ fn remove_file<P: AsRef<Path>>(inner: &mut MemoryFsInner, path: P) -> crate::Res
                 }
             }
 
-            inner.files.remove(&path);
+            if let Some(entry) = inner.files.remove(&path) {
+                entry.file_type.unlink(&inner.capacity);
+            }
+            notify!(inner, FsEvent::Removed(path));
             Ok(())
         } else {
             Err(Error::new(
-                ErrorKind::InvalidInput,
-                format!("Path '{}' is not a file", path.display()),
+                ErrorKind::IsADirectory,
+                format!("Path '{}' is a directory", path.display()),
             ))
         }
     } else {
@@ -647,8 +1344,10 @@ fn rename<P: AsRef<Path>, Q: AsRef<Path>>(
     from: P,
     to: Q,
 ) -> crate::Result<()> {
-    let from = canonicalize_inner(inner, from, true)?;
-    let to = canonicalize_inner(inner, to, false)?;
+    let now = inner.clock.now();
+    let requested_name = to.as_ref().file_name().map(OsStr::to_os_string);
+    let from = canonicalize_inner(inner, from, false)?;
+    let mut to = canonicalize_inner(inner, to, false)?;
 
     if !inner.files.contains_key(&from) {
         return Err(Error::new(
@@ -656,63 +1355,137 @@ fn rename<P: AsRef<Path>, Q: AsRef<Path>>(
             format!("Source path '{}' does not exist", from.display()),
         ));
     }
+    // Like POSIX, renaming an entry onto itself does nothing, unless only the case of
+    // its name changes on a case-insensitive filesystem.
+    if from == to {
+        match requested_name.filter(|name| inner.case_insensitive && to.file_name() != Some(name)) {
+            Some(name) => to.set_file_name(name),
+            None => return Ok(()),
+        }
+    }
 
     if let Some(entry) = inner.files.get(&to) {
-        if let MemoryEntryType::Directory(_) = entry.file_type {
-            return Err(Error::new(
-                ErrorKind::AlreadyExists,
-                format!("Destination path '{}' is a directory", to.display()),
-            ));
+        if let MemoryEntryType::Directory(files) = &entry.file_type {
+            let from_is_dir = is_dir(inner, &from)?;
+            return Err(if from_is_dir && !files.is_empty() {
+                Error::new(
+                    ErrorKind::DirectoryNotEmpty,
+                    format!("Destination directory '{}' is not empty", to.display()),
+                )
+            } else if from_is_dir {
+                Error::new(
+                    ErrorKind::AlreadyExists,
+                    format!("Destination directory '{}' already exists", to.display()),
+                )
+            } else {
+                Error::new(
+                    ErrorKind::IsADirectory,
+                    format!("Destination path '{}' is a directory", to.display()),
+                )
+            });
         }
     }
 
-    let from_parent = from.parent();
-    let to_parent = to.parent();
+    check_writable_parent(inner, &from)?;
+    check_writable_parent(inner, &to)?;
 
-    if let Some(mut entry) = inner.files.remove(&from) {
+    if let Some(entry) = inner.files.get(&to) {
+        let from_entry = &inner.files[&from];
+        match (&from_entry.file_type, &entry.file_type) {
+            // Renaming onto another link to the same file does nothing as well.
+            (MemoryEntryType::File(file), MemoryEntryType::File(other))
+                if file.same_file(other) =>
+            {
+                return Ok(());
+            }
+            (MemoryEntryType::Directory(_), _) => {
+                return Err(Error::new(
+                    ErrorKind::NotADirectory,
+                    format!("Destination path '{}' is not a directory", to.display()),
+                ));
+            }
+            _ => {}
+        }
+    }
+    if to.starts_with(&from) {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "Cannot move '{}' into itself at '{}'",
+                from.display(),
+                to.display()
+            ),
+        ));
+    }
+
+    // The replaced entry is dropped first, so its name and data do not linger.
+    if let Some(replaced) = inner.files.remove(&to) {
+        replaced.file_type.unlink(&inner.capacity);
+        remove_child(inner, &to);
+    }
+
+    if let Some(entry) = inner.files.remove(&from) {
         match &entry.file_type {
             MemoryEntryType::Directory(files) => {
                 for file_name in files.iter() {
                     change_path_recursive(inner, &from, &to, Path::new(file_name))?;
                 }
             }
-            MemoryEntryType::File(_) | MemoryEntryType::HardLink(_) => {}
+            MemoryEntryType::File(_) | MemoryEntryType::Symlink(_) => {}
         }
 
-        if let (Some(from_parent), Some(to_parent)) = (from_parent, to_parent) {
-            if from_parent != to_parent {
-                if let Some(from_entry) = inner.files.get_mut(from_parent) {
-                    if let Some(files) = from_entry.file_type.as_directory_mut() {
-                        files.remove(from.file_name().unwrap());
-                    }
-                }
-                if let Some(to_entry) = inner.files.get_mut(to_parent) {
-                    if let Some(files) = to_entry.file_type.as_directory_mut() {
-                        files.insert(to.file_name().unwrap().to_owned());
-                    }
-                }
+        // The parents are updated even if they are the same directory, since the
+        // name of the entry changes.
+        remove_child(inner, &from);
+        if let (Some(to_parent), Some(name)) = (to.parent(), to.file_name()) {
+            if let Some(files) = inner
+                .files
+                .get_mut(to_parent)
+                .and_then(|parent| parent.file_type.as_directory_mut())
+            {
+                files.insert(name.to_owned());
             }
         }
 
-        entry.accessed = Some(SystemTime::now());
-        entry.modified = Some(SystemTime::now());
+        {
+            let mut attributes = entry.attributes.write();
+            attributes.accessed = Some(now);
+            attributes.modified = Some(now);
+        }
 
-        inner.files.insert(to, entry);
+        inner.files.insert(to.clone(), entry);
+        // A single event for the whole move, so watchers can track it.
+        notify!(inner, FsEvent::Renamed { from, to });
     }
 
     Ok(())
 }
 
+/// Removes the name of `path` from the children of its parent directory.
+fn remove_child(inner: &mut MemoryFsInner, path: &Path) {
+    if let (Some(parent), Some(name)) = (path.parent(), path.file_name()) {
+        if let Some(files) = inner
+            .files
+            .get_mut(parent)
+            .and_then(|parent| parent.file_type.as_directory_mut())
+        {
+            files.remove(name);
+        }
+    }
+}
+
 fn set_permissions<P: AsRef<Path>>(
     inner: &mut MemoryFsInner,
     path: P,
     perm: Permissions,
 ) -> crate::Result<()> {
+    let now = inner.clock.now();
     let path = canonicalize_inner(inner, path, true)?;
 
-    if let Some(entry) = inner.files.get_mut(&path) {
-        entry.permissions = perm;
-        entry.modified = Some(SystemTime::now());
+    if let Some(entry) = inner.files.get(&path) {
+        let mut attributes = entry.attributes.write();
+        attributes.permissions = perm;
+        attributes.modified = Some(now);
         Ok(())
     } else {
         Err(Error::new(
@@ -722,11 +1495,72 @@ fn set_permissions<P: AsRef<Path>>(
     }
 }
 
-fn symlink_metadata<P: AsRef<Path>>(_path: P) -> crate::Result<MemoryMetadata> {
-    Err(Error::new(
-        ErrorKind::Unsupported,
-        "MemoryFs does not support symbolic links",
-    ))
+fn symlink<P: AsRef<Path>, Q: AsRef<Path>>(
+    inner: &mut MemoryFsInner,
+    original: P,
+    link: Q,
+) -> crate::Result<()> {
+    let now = inner.clock.now();
+    let link = canonicalize_inner(inner, link, false)?;
+    for comp in original.as_ref().components() {
+        if let std::path::Component::Normal(name) = comp {
+            validate_name(inner, name)?;
+        }
+    }
+
+    if inner.files.contains_key(&link) {
+        return Err(Error::new(
+            ErrorKind::AlreadyExists,
+            format!("Link path '{}' already exists", link.display()),
+        ));
+    }
+
+    let link_parent = link.parent().ok_or_else(|| {
+        Error::new(
+            ErrorKind::InvalidInput,
+            "Link path must have a parent directory",
+        )
+    })?;
+
+    check_writable(inner, link_parent)?;
+    match inner
+        .files
+        .get_mut(link_parent)
+        .map(|entry| &mut entry.file_type)
+    {
+        Some(MemoryEntryType::Directory(files)) => {
+            files.insert(link.file_name().unwrap().to_os_string());
+        }
+        Some(_) => {
+            return Err(Error::new(
+                ErrorKind::NotADirectory,
+                format!("Parent '{}' is not a directory", link_parent.display()),
+            ))
+        }
+        None => return Err(missing_path_error(inner, link_parent)),
+    }
+
+    let new_entry = MemoryEntry::new(
+        MemoryEntryType::Symlink(original.as_ref().to_path_buf()),
+        Attributes::new(now, Some(now)),
+    );
+    inner.files.insert(link.clone(), new_entry);
+    notify!(inner, FsEvent::Created(link));
+
+    Ok(())
+}
+
+fn symlink_metadata<P: AsRef<Path>>(
+    inner: &MemoryFsInner,
+    path: P,
+) -> crate::Result<MemoryMetadata> {
+    let path = canonicalize_inner(inner, path, false)?;
+
+    if let Some(entry) = inner.files.get(&path) {
+        Ok(entry.metadata())
+    } else {
+        Err(missing_path_error(inner, &path))
+    }
 }
 
 impl UniFs for MemoryFs {
@@ -743,6 +1577,10 @@ impl UniFs for MemoryFs {
         canonicalize(&inner, path)
     }
 
+    /// Copies the contents of one file to another.
+    ///
+    /// If `from` and `to` refer to the same file, the file is left untouched and
+    /// its length is returned.
     fn copy<P: AsRef<Path>, Q: AsRef<Path>>(&self, from: P, to: Q) -> crate::Result<u64> {
         let mut inner = self.inner.write();
         copy(&mut inner, from, to)
@@ -769,22 +1607,21 @@ impl UniFs for MemoryFs {
     }
 
     fn read<P: AsRef<Path>>(&self, path: P) -> crate::Result<Vec<u8>> {
-        let inner = self.inner.read();
-        read(&inner, path)
+        self.read_tracked(path.as_ref(), |inner, path| read(inner, path))
     }
 
     fn read_dir<P: AsRef<Path>>(&self, path: P) -> crate::Result<Self::ReadDir> {
-        let inner = self.inner.read();
-        read_dir(&inner, path)
+        self.read_tracked(path.as_ref(), |inner, path| read_dir(inner, path))
     }
 
+    /// Returns the target of the symbolic link at `path` exactly as it was created.
     fn read_link<P: AsRef<Path>>(&self, path: P) -> crate::Result<PathBuf> {
-        read_link(path)
+        let inner = self.inner.read();
+        read_link(&inner, path)
     }
 
     fn read_to_string<P: AsRef<Path>>(&self, path: P) -> crate::Result<String> {
-        let inner = self.inner.read();
-        read_to_string(&inner, path)
+        self.read_tracked(path.as_ref(), |inner, path| read_to_string(inner, path))
     }
 
     fn remove_dir<P: AsRef<Path>>(&self, path: P) -> crate::Result<()> {
@@ -816,24 +1653,98 @@ impl UniFs for MemoryFs {
         set_permissions(&mut inner, path, perm)
     }
 
+    /// Queries the metadata of the entry at `path` without following a link at `path`.
+    ///
+    /// For links, the reported length is the length of the target path in bytes.
     fn symlink_metadata<P: AsRef<Path>>(&self, path: P) -> crate::Result<Self::Metadata> {
-        symlink_metadata(path)
+        let inner = self.inner.read();
+        symlink_metadata(&inner, path)
     }
 
-    fn new_openoptions(&self) -> Self::OpenOptions {
-        let fs = MemoryFs {
-            inner: self.inner.clone(),
-        };
+    /// Creates a symbolic link at `link` pointing to `original`.
+    ///
+    /// The target is not required to exist. Following a link whose target is missing
+    /// fails with [`ErrorKind::NotFound`], and [`UniFs::exists`] returns `false` for it.
+    fn symlink<P: AsRef<Path>, Q: AsRef<Path>>(&self, original: P, link: Q) -> crate::Result<()> {
+        let mut inner = self.inner.write();
+        symlink(&mut inner, original, link)
+    }
 
-        MemoryOpenOptions::new(fs)
+    fn set_owner<P: AsRef<Path>>(
+        &self,
+        path: P,
+        uid: Option<u32>,
+        gid: Option<u32>,
+    ) -> crate::Result<()> {
+        MemoryFs::set_owner(self, path, uid, gid)
     }
 
-    fn new_dirbuilder(&self) -> Self::DirBuilder {
-        let fs = MemoryFs {
-            inner: self.inner.clone(),
+    /// Writes a slice as the entire contents of a file.
+    ///
+    /// If the contents do not fit into the capacity of the filesystem, this fails with
+    /// [`ErrorKind::QuotaExceeded`] and the previous contents are kept.
+    fn write<P: AsRef<Path>, C: AsRef<[u8]>>(&self, path: P, contents: C) -> crate::Result<()> {
+        self.write_reporting(path, contents).map(drop)
+    }
+
+    fn write_reporting<P: AsRef<Path>, C: AsRef<[u8]>>(
+        &self,
+        path: P,
+        contents: C,
+    ) -> crate::Result<WriteReport> {
+        let mut inner = self.inner.write();
+        let path = canonicalize_inner(&inner, path, true)?;
+
+        let previous_len = match inner.files.get(&path) {
+            Some(MemoryEntry {
+                file_type: MemoryEntryType::File(file),
+                ..
+            }) => Some(file.data.read().len()),
+            _ => None,
         };
 
-        MemoryDirBuilder::new(fs)
+        // The capacity is reserved before the file is opened, so a write exceeding it
+        // leaves the previous contents untouched.
+        let contents = contents.as_ref();
+        let (old_len, new_len) = (previous_len.unwrap_or(0), contents.len() as u64);
+        inner.capacity.resize(old_len, new_len)?;
+        if let Err(err) = self
+            .new_openoptions()
+            .write(true)
+            .create(true)
+            .open_locked(&mut inner, &path)
+        {
+            if new_len > old_len {
+                inner.capacity.release(new_len - old_len);
+            } else {
+                inner.capacity.charge(old_len - new_len);
+            }
+            return Err(err);
+        }
+
+        // Writing through the opened file would try to lock the filesystem again,
+        // so the contents are written into the entry directly.
+        let inner = &mut *inner;
+        let now = inner.clock.now();
+        if let Some(entry) = inner.files.get_mut(&path) {
+            if let MemoryEntryType::File(file) = &entry.file_type {
+                *file.data.write() = FileContents::new(contents);
+                entry.attributes.write().modified = Some(now);
+            }
+        }
+
+        Ok(WriteReport {
+            written: contents.len() as u64,
+            previous_len,
+        })
+    }
+
+    fn new_openoptions(&self) -> Self::OpenOptions {
+        MemoryOpenOptions::new(self.clone())
+    }
+
+    fn new_dirbuilder(&self) -> Self::DirBuilder {
+        MemoryDirBuilder::new(self.clone())
     }
 }
 
@@ -904,7 +1815,7 @@ mod tests {
         let path = fs.canonicalize("foo/../../bar/./baz");
         assert!(path.is_err(), "Expected error for invalid path");
 
-        fs.hard_link("/", "/link").unwrap();
+        fs.symlink("/", "/link").unwrap();
         let link_path = fs.canonicalize("/link").unwrap();
         assert_eq!(link_path, PathBuf::from("/"));
 
@@ -0,0 +1,45 @@
+use std::{path::PathBuf, sync::mpsc};
+
+use crate::memory_fs::{MemoryFs, MemoryFsInner};
+
+/// A change to the directory structure of a [`MemoryFs`], as reported to the receivers
+/// returned by [`MemoryFs::subscribe`].
+///
+/// Paths are absolute and canonical.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FsEvent {
+    /// A file, directory or link was created.
+    Created(PathBuf),
+    /// A file, directory or link was removed. Removing a directory with its contents
+    /// is reported as a single event for the directory.
+    Removed(PathBuf),
+    /// An entry was moved. Moving a directory is reported as a single event for the
+    /// directory, not for each of its descendants.
+    Renamed {
+        /// The previous path of the entry.
+        from: PathBuf,
+        /// The new path of the entry.
+        to: PathBuf,
+    },
+}
+
+impl MemoryFs {
+    /// Subscribes to changes of the directory structure of this filesystem.
+    ///
+    /// Events are sent after the change has been applied, in the order in which the
+    /// changes were made. Changes to the contents of existing files are not reported.
+    /// The subscription ends when the returned receiver is dropped.
+    pub fn subscribe(&self) -> mpsc::Receiver<FsEvent> {
+        let (sender, receiver) = mpsc::channel();
+        self.inner.write().subscribers.push(sender);
+        receiver
+    }
+}
+
+impl MemoryFsInner {
+    /// Sends `event` to all subscribers, dropping those whose receiver is gone.
+    pub(super) fn notify(&mut self, event: FsEvent) {
+        self.subscribers
+            .retain(|subscriber| subscriber.send(event.clone()).is_ok());
+    }
+}
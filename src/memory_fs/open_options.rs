@@ -1,11 +1,11 @@
-use std::{path::Path, sync::Arc, time::SystemTime};
+use std::path::Path;
 
 use crate::{
     memory_fs::{
-        file::MemoryFile, metadata::MemoryMetadata, MemoryEntry, MemoryEntryType, MemoryFs,
+        contents::FileContents, file::MemoryFile, Attributes, FileData, MemoryEntry,
+        MemoryEntryType, MemoryFs, MemoryFsInner,
     },
-    rw_lock::RwLock,
-    FileType, UniOpenOptions,
+    UniOpenOptions,
 };
 
 pub struct MemoryOpenOptions {
@@ -70,42 +70,63 @@ impl UniOpenOptions for MemoryOpenOptions {
 
     fn open<P: AsRef<Path>>(&self, path: P) -> crate::Result<Self::File> {
         let mut inner = self.fs.inner.write();
-        let path = super::canonicalize_inner(&inner, path, true)?;
+        self.open_locked(&mut inner, path)
+    }
+}
 
-        if self.create_new && super::exists(&inner, &path)? {
+impl MemoryOpenOptions {
+    /// Opens the file at `path` while the filesystem lock is already held by the caller.
+    pub(super) fn open_locked<P: AsRef<Path>>(
+        &self,
+        inner: &mut MemoryFsInner,
+        path: P,
+    ) -> crate::Result<MemoryFile> {
+        let link = super::canonicalize_inner(inner, &path, false)?;
+        let path = super::canonicalize_inner(inner, path, true)?;
+
+        // Like `O_EXCL`, a symbolic link is never followed when creating a new file.
+        if self.create_new && (inner.files.contains_key(&link) || super::exists(inner, &path)?) {
             return Err(std::io::Error::new(
                 std::io::ErrorKind::AlreadyExists,
                 "File already exists",
             ));
         }
 
-        if !self.create && !super::exists(&inner, &path)? {
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::NotFound,
-                "File not found",
-            ));
+        if !self.create && !super::exists(inner, &path)? {
+            return Err(super::missing_path_error(inner, &path));
         }
 
         if let Some(entry) = inner.files.get(&path) {
             match &entry.file_type {
                 MemoryEntryType::Directory(_) => Err(std::io::Error::new(
-                    std::io::ErrorKind::InvalidInput,
+                    std::io::ErrorKind::IsADirectory,
                     "Cannot open a directory as a file",
                 )),
-                MemoryEntryType::HardLink(_) => Err(std::io::Error::new(
+                MemoryEntryType::Symlink(_) => Err(std::io::Error::new(
                     std::io::ErrorKind::InvalidInput,
                     "Cannot open a symlink as a file",
                 )),
-                MemoryEntryType::File(data) => {
+                MemoryEntryType::File(_)
+                    if entry.attributes.read().permissions.readonly
+                        && (self.write || self.append || self.truncate) =>
+                {
+                    Err(std::io::Error::new(
+                        std::io::ErrorKind::PermissionDenied,
+                        format!("File '{}' is read-only", path.display()),
+                    ))
+                }
+                MemoryEntryType::File(file) => {
                     if self.truncate {
-                        let mut data = data.write();
-                        data.clear();
-                        data.shrink_to_fit();
+                        let mut data = file.data.write();
+                        inner.capacity.release(data.len());
+                        *data = FileContents::default();
                     }
                     Ok(MemoryFile::new(
+                        self.fs.clone(),
                         path,
-                        data.clone(),
-                        entry.metadata(),
+                        file,
+                        inner.capacity.clone(),
+                        entry.attributes.clone(),
                         self.write,
                         self.append,
                     ))
@@ -113,28 +134,15 @@ impl UniOpenOptions for MemoryOpenOptions {
             }
         } else {
             if !self.create || !self.write {
-                return Err(std::io::Error::new(
-                    std::io::ErrorKind::NotFound,
-                    "File not found",
-                ));
+                return Err(super::missing_path_error(inner, &path));
             }
+            super::check_writable_parent(inner, &path)?;
+
+            let file = FileData::new(FileContents::default());
+            let file_type = MemoryEntryType::File(file.clone());
 
-            let metadata = MemoryMetadata {
-                file_type: FileType::File,
-                permissions: crate::Permissions { readonly: false },
-                file_times: Default::default(),
-                len: 0,
-            };
-            let data = Arc::new(RwLock::new(Vec::new()));
-            let file_type = MemoryEntryType::File(data.clone());
-
-            let entry = MemoryEntry {
-                accessed: None,
-                created: SystemTime::now(),
-                modified: None,
-                file_type,
-                permissions: metadata.permissions.clone(),
-            };
+            let entry = MemoryEntry::new(file_type, Attributes::new(inner.clock.now(), None));
+            let attributes = entry.attributes.clone();
 
             let parent = path.parent().ok_or_else(|| {
                 std::io::Error::new(std::io::ErrorKind::InvalidInput, "No parent path")
@@ -144,7 +152,7 @@ impl UniOpenOptions for MemoryOpenOptions {
                     files.insert(path.file_name().unwrap().to_os_string());
                 } else {
                     return Err(std::io::Error::new(
-                        std::io::ErrorKind::InvalidInput,
+                        std::io::ErrorKind::NotADirectory,
                         "Parent is not a directory",
                     ));
                 }
@@ -156,11 +164,14 @@ impl UniOpenOptions for MemoryOpenOptions {
             }
 
             inner.files.insert(path.clone(), entry);
+            notify!(inner, super::FsEvent::Created(path.clone()));
 
             Ok(MemoryFile::new(
+                self.fs.clone(),
                 path,
-                data,
-                metadata,
+                &file,
+                inner.capacity.clone(),
+                attributes,
                 self.write,
                 self.append,
             ))
@@ -0,0 +1,288 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    io::{BufReader, BufWriter, ErrorKind},
+    path::{Path, PathBuf},
+    sync::{atomic::Ordering, Arc},
+    time::SystemTime,
+};
+
+use bincode::Options as _;
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{
+    memory_fs::{
+        contents::FileContents, Attributes, FileData, MemoryEntry, MemoryEntryType, MemoryFs,
+        MemoryFsInner, Owner,
+    },
+    rw_lock::RwLock,
+    Permissions,
+};
+
+/// The serialized form of a [`MemoryFs`].
+///
+/// The contents of files are stored separately from the entries, so hard links to the
+/// same file refer to the same contents. Each link repeats the attributes of the file.
+#[derive(Serialize, Deserialize)]
+struct SerializedFs<C> {
+    current_dir: PathBuf,
+    contents: Vec<C>,
+    entries: Vec<SerializedEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SerializedEntry {
+    path: PathBuf,
+    kind: SerializedKind,
+    created: SystemTime,
+    modified: Option<SystemTime>,
+    accessed: Option<SystemTime>,
+    readonly: bool,
+    uid: u32,
+    gid: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+enum SerializedKind {
+    /// A file, with the index of its contents.
+    File(usize),
+    Directory,
+    Symlink(PathBuf),
+}
+
+/// Serializes the contents of a file without copying them.
+struct SharedContents<'a>(&'a RwLock<FileContents>);
+
+impl Serialize for SharedContents<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.read().to_vec().serialize(serializer)
+    }
+}
+
+/// Serializes all entries of the filesystem, including the contents of the files, and
+/// the current directory. Settings such as the capacity are not serialized.
+///
+/// Unlike a zip archive, which other tools can open but which only keeps directories
+/// and file contents, this representation keeps links, times, permissions and owners.
+/// It does not prescribe a file format, so it is up to the caller to pick a serde
+/// format to persist it with, or to use [`MemoryFs::save_to_file`].
+impl Serialize for MemoryFs {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let inner = self.inner.read();
+        let mut contents = Vec::new();
+        let mut content_indices = HashMap::new();
+
+        // Sorted by path, so the output does not depend on the order of the map.
+        let mut files = inner.files.iter().collect::<Vec<_>>();
+        files.sort_unstable_by_key(|(path, _)| *path);
+
+        let entries = files
+            .into_iter()
+            .map(|(path, entry)| {
+                let attributes = entry.attributes.read();
+                SerializedEntry {
+                    path: path.clone(),
+                    kind: match &entry.file_type {
+                        MemoryEntryType::File(file) => {
+                            let index = *content_indices
+                                .entry(Arc::as_ptr(&file.data))
+                                .or_insert_with(|| {
+                                    contents.push(SharedContents(&file.data));
+                                    contents.len() - 1
+                                });
+                            SerializedKind::File(index)
+                        }
+                        MemoryEntryType::Directory(_) => SerializedKind::Directory,
+                        MemoryEntryType::Symlink(target) => SerializedKind::Symlink(target.clone()),
+                    },
+                    created: attributes.created,
+                    modified: attributes.modified,
+                    accessed: attributes.accessed,
+                    readonly: attributes.permissions.readonly,
+                    uid: attributes.owner.uid,
+                    gid: attributes.owner.gid,
+                }
+            })
+            .collect();
+
+        SerializedFs {
+            current_dir: inner.current_dir.clone(),
+            contents,
+            entries,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for MemoryFs {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let serialized = SerializedFs::<Vec<u8>>::deserialize(deserializer)?;
+        let contents = serialized
+            .contents
+            .into_iter()
+            .map(|data| {
+                let file = FileData::new(FileContents::new(&data));
+                file.links.store(0, Ordering::Release);
+                file
+            })
+            .collect::<Vec<_>>();
+
+        // Hard links share the attributes of the first link to their contents.
+        let mut shared_attributes = vec![None; contents.len()];
+
+        let mut files = HashMap::new();
+        for entry in serialized.entries {
+            let attributes = Attributes {
+                created: entry.created,
+                modified: entry.modified,
+                accessed: entry.accessed,
+                permissions: Permissions {
+                    readonly: entry.readonly,
+                },
+                owner: Owner {
+                    uid: entry.uid,
+                    gid: entry.gid,
+                },
+            };
+            let entry_value = match entry.kind {
+                SerializedKind::File(index) => {
+                    let file = contents.get(index).ok_or_else(|| {
+                        D::Error::custom(format!("missing contents of '{}'", entry.path.display()))
+                    })?;
+                    file.links.fetch_add(1, Ordering::AcqRel);
+                    let attributes = shared_attributes[index]
+                        .get_or_insert_with(|| Arc::new(RwLock::new(attributes)))
+                        .clone();
+                    MemoryEntry {
+                        file_type: MemoryEntryType::File(file.clone()),
+                        attributes,
+                    }
+                }
+                SerializedKind::Directory => {
+                    MemoryEntry::new(MemoryEntryType::Directory(BTreeSet::new()), attributes)
+                }
+                SerializedKind::Symlink(target) => {
+                    MemoryEntry::new(MemoryEntryType::Symlink(target), attributes)
+                }
+            };
+            if files.insert(entry.path.clone(), entry_value).is_some() {
+                return Err(D::Error::custom(format!(
+                    "duplicate entry '{}'",
+                    entry.path.display()
+                )));
+            }
+        }
+
+        // The children of the directories are derived from the paths of the entries.
+        let paths = files.keys().cloned().collect::<Vec<_>>();
+        for path in paths {
+            let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+                continue;
+            };
+            match files
+                .get_mut(parent)
+                .and_then(|parent| parent.file_type.as_directory_mut())
+            {
+                Some(children) => {
+                    children.insert(name.to_os_string());
+                }
+                None => {
+                    return Err(D::Error::custom(format!(
+                        "parent of '{}' is not a directory",
+                        path.display()
+                    )))
+                }
+            }
+        }
+        if !matches!(
+            files.get(Path::new("/")).map(|root| &root.file_type),
+            Some(MemoryEntryType::Directory(_))
+        ) {
+            return Err(D::Error::custom("missing root directory"));
+        }
+
+        // Contents that no entry refers to are dropped.
+        let len = contents
+            .iter()
+            .filter(|file| file.links.load(Ordering::Acquire) > 0)
+            .map(|file| file.data.read().len())
+            .sum();
+
+        let mut inner = MemoryFsInner::new();
+        inner.files = files;
+        inner.current_dir = serialized.current_dir;
+        inner.capacity.charge(len);
+
+        Ok(MemoryFs {
+            inner: Arc::new(RwLock::new(inner)),
+        })
+    }
+}
+
+impl MemoryFs {
+    /// Saves all entries of the filesystem and the current directory to a file at
+    /// `path` on the real disk, from which [`MemoryFs::open_or_create_from_file`] can
+    /// load it again.
+    ///
+    /// The file holds the representation of the `Serialize` implementation in a
+    /// compact binary encoding, so nothing but the settings is lost. It is written to
+    /// a temporary file next to `path` first, which then replaces `path`. If saving
+    /// fails, a previous save at `path` is left intact.
+    ///
+    /// # Errors
+    /// - [`ErrorKind::InvalidInput`] if `path` has no file name.
+    /// - [`ErrorKind::InvalidData`] if an entry cannot be represented, for example
+    ///   because its path is not valid UTF-8.
+    pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
+        let path = path.as_ref();
+        let mut temp_name = std::ffi::OsString::from(".");
+        temp_name.push(path.file_name().ok_or_else(|| {
+            std::io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("Path '{}' has no file name", path.display()),
+            )
+        })?);
+        temp_name.push(".tmp");
+        let temp_path = path.with_file_name(temp_name);
+
+        let result = std::fs::File::create(&temp_path).and_then(|file| {
+            let mut writer = BufWriter::new(file);
+            bincode::DefaultOptions::new()
+                .serialize_into(&mut writer, self)
+                .map_err(|err| bincode_error(*err))?;
+            writer.into_inner()?.sync_all()?;
+            std::fs::rename(&temp_path, path)
+        });
+        if result.is_err() {
+            let _ = std::fs::remove_file(&temp_path);
+        }
+        result
+    }
+
+    /// Loads a filesystem saved with [`MemoryFs::save_to_file`] from `path` on the real
+    /// disk, or creates an empty one if the file does not exist.
+    ///
+    /// # Errors
+    /// - [`ErrorKind::InvalidData`] if the file does not hold a saved filesystem.
+    pub fn open_or_create_from_file<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+        match std::fs::File::open(path) {
+            Ok(file) => {
+                // Lengths in a corrupted file must not make it allocate more than its size.
+                let len = file.metadata()?.len();
+                bincode::DefaultOptions::new()
+                    .with_limit(len)
+                    .deserialize_from(BufReader::new(file))
+                    .map_err(|err| bincode_error(*err))
+            }
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(Self::new()),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+/// Converts an error of the binary encoding into an I/O error.
+fn bincode_error(err: bincode::ErrorKind) -> std::io::Error {
+    match err {
+        bincode::ErrorKind::Io(err) => err,
+        err => std::io::Error::new(ErrorKind::InvalidData, err),
+    }
+}
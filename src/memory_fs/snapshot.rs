@@ -0,0 +1,129 @@
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    path::PathBuf,
+    sync::{atomic::Ordering, Arc},
+};
+
+use crate::{
+    memory_fs::{
+        capacity::Capacity, contents::FileContents, FileData, MemoryEntry, MemoryEntryType,
+        MemoryFs, MemoryFsInner,
+    },
+    rw_lock::RwLock,
+};
+
+/// The state of a [`MemoryFs`] at some point in time, taken with
+/// [`MemoryFs::snapshot`].
+///
+/// A snapshot does not change when the filesystem it was taken from changes, and it
+/// can be restored any number of times.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    files: HashMap<PathBuf, MemoryEntry>,
+    current_dir: PathBuf,
+}
+
+impl MemoryFs {
+    /// Takes a snapshot of all entries of this filesystem, including the contents of
+    /// the files and the current directory.
+    pub fn snapshot(&self) -> Snapshot {
+        let inner = self.inner.read();
+        Snapshot {
+            files: deep_clone(&inner.files).0,
+            current_dir: inner.current_dir.clone(),
+        }
+    }
+
+    /// Replaces all entries of this filesystem with those of `snapshot`.
+    ///
+    /// The entries are replaced at once, so other threads see either the previous
+    /// state or the restored one. Files that are open keep their contents, but are
+    /// detached from the filesystem. The restored files count towards the capacity
+    /// even if they exceed it. The change is not reported to subscribers.
+    pub fn restore(&self, snapshot: Snapshot) {
+        let (files, len) = deep_clone(&snapshot.files);
+
+        let mut inner = self.inner.write();
+        let previous = std::mem::replace(&mut inner.files, files);
+        for entry in previous.into_values() {
+            entry.file_type.unlink(&inner.capacity);
+        }
+        inner.capacity.charge(len);
+        inner.current_dir = snapshot.current_dir;
+    }
+
+    /// Creates an independent copy of this filesystem, including the contents of the
+    /// files, the current directory and the settings.
+    ///
+    /// Unlike [`MemoryFs::clone`], which returns another handle to the same
+    /// filesystem, changes to the copy are not visible in the original and vice
+    /// versa. Hard links keep sharing their data within the copy. Open files, range
+    /// locks and subscribers are not copied.
+    pub fn deep_clone(&self) -> MemoryFs {
+        let inner = self.inner.read();
+        let (files, len) = deep_clone(&inner.files);
+
+        let capacity = Capacity::new(inner.capacity.limit());
+        capacity.charge(len);
+        let mut copy = MemoryFsInner::new();
+        copy.files = files;
+        copy.current_dir = inner.current_dir.clone();
+        copy.track_atime = inner.track_atime;
+        copy.illegal_chars = inner.illegal_chars.clone();
+        copy.max_path_len = inner.max_path_len;
+        copy.max_name_len = inner.max_name_len;
+        copy.case_insensitive = inner.case_insensitive;
+        #[cfg(feature = "unicode-normalization")]
+        {
+            copy.normalize_names = inner.normalize_names;
+        }
+        copy.clock = inner.clock.clone();
+        copy.capacity = Arc::new(capacity);
+
+        MemoryFs {
+            inner: Arc::new(RwLock::new(copy)),
+        }
+    }
+}
+
+/// Copies `files`, including the contents of the files, and returns the copy with the
+/// total length of the copied files.
+///
+/// Hard links keep sharing their data and attributes in the copy.
+fn deep_clone(files: &HashMap<PathBuf, MemoryEntry>) -> (HashMap<PathBuf, MemoryEntry>, u64) {
+    let mut copies = HashMap::<*const RwLock<FileContents>, MemoryEntry>::new();
+    let mut len = 0;
+
+    let files = files
+        .iter()
+        .map(|(path, entry)| {
+            let entry = match &entry.file_type {
+                MemoryEntryType::File(file) => match copies.entry(Arc::as_ptr(&file.data)) {
+                    Entry::Occupied(copy) => {
+                        if let MemoryEntryType::File(file) = &copy.get().file_type {
+                            file.links.fetch_add(1, Ordering::AcqRel);
+                        }
+                        copy.get().clone()
+                    }
+                    Entry::Vacant(slot) => {
+                        let data = file.data.read().clone();
+                        len += data.len();
+                        let attributes = entry.attributes.read().clone();
+                        slot.insert(MemoryEntry::new(
+                            MemoryEntryType::File(FileData::new(data)),
+                            attributes,
+                        ))
+                        .clone()
+                    }
+                },
+                file_type => {
+                    let attributes = entry.attributes.read().clone();
+                    MemoryEntry::new(file_type.clone(), attributes)
+                }
+            };
+            (path.clone(), entry)
+        })
+        .collect();
+
+    (files, len)
+}
@@ -0,0 +1,164 @@
+use std::{
+    io::{ErrorKind, Read, Write},
+    path::{Component, Path, PathBuf},
+    time::{Duration, UNIX_EPOCH},
+};
+
+use tar::{Archive, Builder, EntryType, Header};
+
+use crate::{
+    MemoryFs, UniDirEntry as _, UniFileType as _, UniFs as _, UniFsExt as _, UniMetadata as _,
+    UniPermissions as _,
+};
+
+impl MemoryFs {
+    /// Write the contents of the filesystem into a tar archive.
+    ///
+    /// Every directory, file and symbolic link is written as an entry, so empty
+    /// directories are preserved. The modification time, the owner and whether the
+    /// entry is read-only are recorded in the header of each entry.
+    pub fn tar_into<W>(&self, writer: W) -> std::io::Result<()>
+    where
+        W: Write,
+    {
+        let mut builder = Builder::new(writer);
+
+        for entry in self.walk_dir("/") {
+            let entry = entry?;
+
+            let path = entry.path();
+            let name = path.strip_prefix("/").unwrap_or(&path);
+            let metadata = self.symlink_metadata(&path)?;
+            let file_type = metadata.file_type();
+
+            let mut header = Header::new_gnu();
+            let mode = match (file_type.is_dir(), metadata.permissions().readonly()) {
+                (true, false) => 0o755,
+                (true, true) => 0o555,
+                (false, false) => 0o644,
+                (false, true) => 0o444,
+            };
+            header.set_mode(mode);
+            header.set_uid(metadata.uid().unwrap_or_default().into());
+            header.set_gid(metadata.gid().unwrap_or_default().into());
+            if let Ok(modified) = metadata.modified() {
+                let mtime = modified.duration_since(UNIX_EPOCH).unwrap_or_default();
+                header.set_mtime(mtime.as_secs());
+            }
+
+            if file_type.is_file() {
+                let data = self.read(&path)?;
+                header.set_entry_type(EntryType::Regular);
+                header.set_size(data.len() as u64);
+                builder.append_data(&mut header, name, data.as_slice())?;
+            } else if file_type.is_dir() {
+                header.set_entry_type(EntryType::Directory);
+                header.set_size(0);
+                builder.append_data(&mut header, name, std::io::empty())?;
+            } else if file_type.is_symlink() {
+                let target = self.read_link(&path)?;
+                header.set_entry_type(EntryType::Symlink);
+                header.set_size(0);
+                builder.append_link(&mut header, name, target)?;
+            }
+        }
+
+        builder.into_inner()?.flush()
+    }
+
+    /// Create a tar archive from the filesystem and return it as a byte vector.
+    pub fn tar(&self) -> std::io::Result<Vec<u8>> {
+        let mut buffer = Vec::new();
+        self.tar_into(&mut buffer)?;
+
+        Ok(buffer)
+    }
+
+    /// Read the contents of a tar archive into a new `MemoryFs` instance.
+    ///
+    /// Directories, regular files and symbolic links are loaded together with their
+    /// modification time, owner and read-only flag. Other kinds of entries are
+    /// skipped. Entries with paths escaping the archive root are rejected.
+    pub fn from_tar<R>(tar_data: R) -> std::io::Result<Self>
+    where
+        R: Read,
+    {
+        let mut archive = Archive::new(tar_data);
+        let memory_fs = MemoryFs::new();
+        // Applied once all entries exist, as creating children updates their parents.
+        let mut attributes = Vec::new();
+
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            let path = enclosed_path(&entry.path()?).ok_or_else(|| {
+                std::io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "Invalid path in tar archive: {}",
+                        String::from_utf8_lossy(&entry.path_bytes())
+                    ),
+                )
+            })?;
+
+            let entry_type = entry.header().entry_type();
+            if entry_type.is_dir() {
+                memory_fs.create_dir_all(&path)?;
+            } else {
+                if let Some(parent) = path.parent() {
+                    memory_fs.create_dir_all(parent)?;
+                }
+                if entry_type.is_file() {
+                    let mut data = Vec::new();
+                    entry.read_to_end(&mut data)?;
+                    memory_fs.write(&path, data)?;
+                } else if entry_type.is_symlink() {
+                    let target = entry.link_name()?.ok_or_else(|| {
+                        std::io::Error::new(
+                            ErrorKind::InvalidData,
+                            format!("Missing link target in tar archive: {}", path.display()),
+                        )
+                    })?;
+                    memory_fs.symlink(target, &path)?;
+                } else {
+                    continue;
+                }
+            }
+
+            let header = entry.header();
+            attributes.push((
+                path,
+                header.mtime()?,
+                header.mode()? & 0o222 == 0,
+                header.uid()?,
+                header.gid()?,
+            ));
+        }
+
+        let inner = memory_fs.inner.read();
+        for (path, mtime, readonly, uid, gid) in attributes {
+            if let Some(entry) = inner.files.get(&path) {
+                let mut attributes = entry.attributes.write();
+                attributes.modified = Some(UNIX_EPOCH + Duration::from_secs(mtime));
+                attributes.permissions.readonly = readonly;
+                attributes.owner.uid = uid.try_into().unwrap_or_default();
+                attributes.owner.gid = gid.try_into().unwrap_or_default();
+            }
+        }
+        drop(inner);
+
+        Ok(memory_fs)
+    }
+}
+
+/// Returns the absolute path of an archive entry, or `None` if it escapes the root.
+fn enclosed_path(path: &Path) -> Option<PathBuf> {
+    let mut enclosed = PathBuf::from("/");
+    for component in path.components() {
+        match component {
+            Component::Normal(name) => enclosed.push(name),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    Some(enclosed)
+}
@@ -1,4 +1,4 @@
-use std::io::{Cursor, Seek, Write};
+use std::io::{Cursor, Read, Seek, Write};
 
 use zip::{write::FileOptions, ZipWriter};
 
@@ -12,7 +12,7 @@ impl MemoryFs {
     {
         let mut zip_writer = ZipWriter::new(zip_data);
 
-        for entry in self.walk_dir(".") {
+        for entry in self.walk_dir("/") {
             let entry = entry?;
 
             let path = entry.path();
@@ -52,4 +52,17 @@ impl MemoryFs {
 
         Ok(buffer.into_inner())
     }
+
+    /// Read the contents of a zip archive into a new `MemoryFs` instance.
+    ///
+    /// Entries with paths escaping the archive root are rejected.
+    pub fn from_zip<R>(zip_data: R) -> std::io::Result<Self>
+    where
+        R: Read + Seek,
+    {
+        let memory_fs = MemoryFs::new();
+        crate::extract_zip(zip_data, &memory_fs, "/")?;
+
+        Ok(memory_fs)
+    }
 }
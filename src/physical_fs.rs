@@ -10,7 +10,7 @@ use crate::{
         dir_builder::UniDirBuilder, open_options::UniOpenOptions, UniDirEntry, UniFileTimes,
         UniFileType, UniMetadata, UniPermissions,
     },
-    Result, UniFile, UniFs,
+    Result, SpecialFileKind, UniFile, UniFs,
 };
 
 /// The `PhysicalFs` struct provides a filesystem interface that operates on the root filesystem of the operating system.
@@ -22,11 +22,12 @@ use crate::{
 /// # Example
 ///
 /// ```no_run
-/// use unifs::PhysicalFs;
+/// use unifs::{PhysicalFs, UniFs as _};
 ///
 /// # fn main() -> unifs::Result<()> {
-/// PhysicalFs::create_dir("example_dir")?;
-/// PhysicalFs::write("example_dir/example_file.txt", "Hello, World!")?;
+/// PhysicalFs.create_dir("example_dir")?;
+/// PhysicalFs.write("example_dir/example_file.txt", "Hello, World!")?;
+/// # Ok(())
 /// # }
 /// ```
 pub struct PhysicalFs;
@@ -130,6 +131,94 @@ impl UniFs for PhysicalFs {
         fs::write(path, contents)
     }
 
+    /// Creates a special file at the specified path.
+    ///
+    /// Only named pipes ([`SpecialFileKind::Fifo`]) on Unix platforms are supported,
+    /// all other kinds return an error of kind [`std::io::ErrorKind::Unsupported`].
+    fn create_special<P: AsRef<Path>>(&self, path: P, kind: SpecialFileKind) -> Result<()> {
+        let path = path.as_ref();
+        match kind {
+            #[cfg(unix)]
+            SpecialFileKind::Fifo => {
+                use std::{ffi::CString, os::unix::ffi::OsStrExt as _};
+
+                let c_path = CString::new(path.as_os_str().as_bytes())?;
+                // SAFETY: `c_path` is a valid NUL-terminated string that outlives the call.
+                if unsafe { libc::mkfifo(c_path.as_ptr(), 0o666) } == 0 {
+                    Ok(())
+                } else {
+                    Err(std::io::Error::last_os_error())
+                }
+            }
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                format!(
+                    "Cannot create special file '{}' of kind {kind:?}: not supported on this platform",
+                    path.display()
+                ),
+            )),
+        }
+    }
+
+    /// Changes the owner and group of a file or directory, following symbolic links.
+    ///
+    /// This is supported on Unix only, other platforms return an error of kind
+    /// [`ErrorKind::Unsupported`](std::io::ErrorKind::Unsupported).
+    fn set_owner<P: AsRef<Path>>(&self, path: P, uid: Option<u32>, gid: Option<u32>) -> Result<()> {
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::chown(path, uid, gid)
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = (uid, gid);
+            Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                format!(
+                    "Cannot change the owner of '{}': not supported on this platform",
+                    path.as_ref().display()
+                ),
+            ))
+        }
+    }
+
+    fn symlink<P: AsRef<Path>, Q: AsRef<Path>>(&self, original: P, link: Q) -> Result<()> {
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(original, link)
+        }
+        #[cfg(windows)]
+        {
+            // Windows distinguishes links to directories from links to files.
+            let link = link.as_ref();
+            let target = link.parent().map_or_else(
+                || original.as_ref().to_path_buf(),
+                |parent| parent.join(&original),
+            );
+            if target.is_dir() {
+                std::os::windows::fs::symlink_dir(original, link)
+            } else {
+                std::os::windows::fs::symlink_file(original, link)
+            }
+        }
+        #[cfg(not(any(unix, windows)))]
+        {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                format!(
+                    "Cannot create symbolic link '{}' to '{}': not supported on this platform",
+                    link.as_ref().display(),
+                    original.as_ref().display()
+                ),
+            ))
+        }
+    }
+
+    #[inline(always)]
+    fn physical_path<P: AsRef<Path>>(&self, path: P) -> Option<PathBuf> {
+        Some(path.as_ref().to_path_buf())
+    }
+
     #[inline(always)]
     fn open_file<P: AsRef<Path>>(&self, path: P) -> Result<Self::File> {
         fs::File::open(path)
@@ -195,6 +284,24 @@ impl UniMetadata for fs::Metadata {
     fn permissions(&self) -> Self::Permissions {
         self.permissions()
     }
+
+    #[cfg(unix)]
+    #[inline(always)]
+    fn nlink(&self) -> Option<u64> {
+        Some(std::os::unix::fs::MetadataExt::nlink(self))
+    }
+
+    #[cfg(unix)]
+    #[inline(always)]
+    fn uid(&self) -> Option<u32> {
+        Some(std::os::unix::fs::MetadataExt::uid(self))
+    }
+
+    #[cfg(unix)]
+    #[inline(always)]
+    fn gid(&self) -> Option<u32> {
+        Some(std::os::unix::fs::MetadataExt::gid(self))
+    }
 }
 
 impl UniPermissions for fs::Permissions {
@@ -291,6 +398,44 @@ impl UniFile for fs::File {
     fn try_clone(&self) -> Result<Self> {
         self.try_clone()
     }
+
+    #[inline(always)]
+    fn lock_exclusive(&self) -> Result<()> {
+        self.lock()
+    }
+
+    #[inline(always)]
+    fn lock_shared(&self) -> Result<()> {
+        self.lock_shared()
+    }
+
+    #[inline(always)]
+    fn try_lock_exclusive(&self) -> Result<()> {
+        self.try_lock().map_err(Into::into)
+    }
+
+    #[inline(always)]
+    fn try_lock_shared(&self) -> Result<()> {
+        self.try_lock_shared().map_err(Into::into)
+    }
+
+    #[inline(always)]
+    fn unlock(&self) -> Result<()> {
+        self.unlock()
+    }
+
+    #[cfg(unix)]
+    #[inline(always)]
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> Result<usize> {
+        std::os::unix::fs::FileExt::read_at(self, buf, offset)
+    }
+
+    /// On Windows, this moves the position of the file to the end of the read bytes.
+    #[cfg(windows)]
+    #[inline(always)]
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> Result<usize> {
+        std::os::windows::fs::FileExt::seek_read(self, buf, offset)
+    }
 }
 
 impl UniOpenOptions for fs::OpenOptions {
@@ -330,6 +475,33 @@ impl UniOpenOptions for fs::OpenOptions {
     fn write(&mut self, write: bool) -> &mut Self {
         self.write(write)
     }
+
+    /// Sets `O_DIRECT` on Linux and Android, and `FILE_FLAG_NO_BUFFERING` on Windows.
+    /// Does nothing on other platforms.
+    ///
+    /// This replaces any custom flags set before.
+    #[cfg_attr(
+        not(any(target_os = "linux", target_os = "android", windows)),
+        expect(unused_variables)
+    )]
+    fn direct(&mut self, direct: bool) -> &mut Self {
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        {
+            use std::os::unix::fs::OpenOptionsExt as _;
+
+            self.custom_flags(if direct { libc::O_DIRECT } else { 0 });
+        }
+
+        #[cfg(windows)]
+        {
+            use std::os::windows::fs::OpenOptionsExt as _;
+
+            const FILE_FLAG_NO_BUFFERING: u32 = 0x2000_0000;
+            self.custom_flags(if direct { FILE_FLAG_NO_BUFFERING } else { 0 });
+        }
+
+        self
+    }
 }
 
 impl UniFileTimes for FileTimes {
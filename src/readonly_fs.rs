@@ -7,7 +7,7 @@ use std::{
 
 use crate::{
     traits::{dir_builder::UniDirBuilder, open_options::UniOpenOptions},
-    Result, UniDirEntry, UniFile, UniFs, UniMetadata, UniPermissions,
+    Result, SpecialFileKind, UniDirEntry, UniFile, UniFs, UniMetadata, UniPermissions, WriteReport,
 };
 
 /// The `ReadonlyFs` struct provides a read-only filesystem interface that wraps around another filesystem implementation.
@@ -17,7 +17,7 @@ pub struct ReadonlyFs<FS: UniFs>(FS);
 pub struct ReadonlyMetadata<T: UniMetadata>(T);
 
 /// A permissions type that indicates the filesystem is read-only.
-#[derive(PartialEq, Eq)]
+#[derive(PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct ReadonlyPermissions;
 
 /// A wrapper for open options that makes them read-only.
@@ -169,6 +169,49 @@ where
         Err(error("Cannot write to files in a read-only filesystem"))
     }
 
+    /// Writes a slice as the entire contents of a file and reports on the write.
+    ///
+    /// This function will return an error indicating that the filesystem is read-only.
+    fn write_reporting<P: AsRef<Path>, C: AsRef<[u8]>>(
+        &self,
+        _path: P,
+        _contents: C,
+    ) -> crate::Result<WriteReport> {
+        Err(error("Cannot write to files in a read-only filesystem"))
+    }
+
+    /// Creates a special file at the specified path.
+    ///
+    /// This function will return an error indicating that the filesystem is read-only.
+    fn create_special<P: AsRef<Path>>(
+        &self,
+        _path: P,
+        _kind: SpecialFileKind,
+    ) -> crate::Result<()> {
+        Err(error(
+            "Cannot create special file in a read-only filesystem",
+        ))
+    }
+
+    fn set_owner<P: AsRef<Path>>(
+        &self,
+        _path: P,
+        _uid: Option<u32>,
+        _gid: Option<u32>,
+    ) -> crate::Result<()> {
+        Err(error("Cannot change the owner in a read-only filesystem"))
+    }
+
+    fn symlink<P: AsRef<Path>, Q: AsRef<Path>>(&self, _original: P, _link: Q) -> crate::Result<()> {
+        Err(error(
+            "Cannot create symbolic links in a read-only filesystem",
+        ))
+    }
+
+    fn physical_path<P: AsRef<Path>>(&self, path: P) -> Option<std::path::PathBuf> {
+        self.0.physical_path(path)
+    }
+
     fn open_file<P: AsRef<Path>>(&self, path: P) -> crate::Result<Self::File> {
         self.0.open_file(path).map(ReadonlyFile)
     }
@@ -198,6 +241,18 @@ impl<T: UniMetadata> UniMetadata for ReadonlyMetadata<T> {
         self.0.created()
     }
 
+    fn nlink(&self) -> Option<u64> {
+        self.0.nlink()
+    }
+
+    fn uid(&self) -> Option<u32> {
+        self.0.uid()
+    }
+
+    fn gid(&self) -> Option<u32> {
+        self.0.gid()
+    }
+
     fn file_type(&self) -> Self::FileType {
         self.0.file_type()
     }
@@ -250,6 +305,11 @@ impl<T: UniOpenOptions> UniOpenOptions for ReadonlyOpenOptions<T> {
         self
     }
 
+    fn direct(&mut self, direct: bool) -> &mut Self {
+        self.0.direct(direct);
+        self
+    }
+
     fn open<P: AsRef<Path>>(&self, path: P) -> crate::Result<Self::File> {
         self.0.open(path).map(ReadonlyFile)
     }
@@ -370,4 +430,28 @@ impl<T: UniFile> UniFile for ReadonlyFile<T> {
     fn set_times(&self, times: Self::FileTimes) -> Result<()> {
         self.0.set_times(times)
     }
+
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> Result<usize> {
+        self.0.read_at(buf, offset)
+    }
+
+    fn lock_exclusive(&self) -> Result<()> {
+        self.0.lock_exclusive()
+    }
+
+    fn lock_shared(&self) -> Result<()> {
+        self.0.lock_shared()
+    }
+
+    fn try_lock_exclusive(&self) -> Result<()> {
+        self.0.try_lock_exclusive()
+    }
+
+    fn try_lock_shared(&self) -> Result<()> {
+        self.0.try_lock_shared()
+    }
+
+    fn unlock(&self) -> Result<()> {
+        self.0.unlock()
+    }
 }
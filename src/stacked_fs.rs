@@ -1,14 +1,17 @@
 //! Stacked file system module
 
 use std::{
+    cmp::Ordering,
     fmt::Debug,
+    hash::{Hash, Hasher},
     io::{Read, Seek, Write},
-    path::{Path, PathBuf},
+    iter::Peekable,
+    path::{Component, Path, PathBuf},
 };
 
 use crate::{
-    UniDirBuilder, UniDirEntry, UniFile, UniFileTimes, UniFileType, UniFs, UniMetadata,
-    UniOpenOptions, UniPermissions,
+    SpecialFileKind, UniDirBuilder, UniDirEntry, UniFile, UniFileTimes, UniFileType, UniFs,
+    UniMetadata, UniOpenOptions, UniPermissions,
 };
 
 /// A file system that allows stacking multiple file systems on top of each other.
@@ -95,6 +98,19 @@ where
         /// The mount point where the overlay file system is mounted.
         mount_point: PathBuf,
     },
+    /// Read directory iterator merging a directory that exists in both file systems.
+    ///
+    /// Both iterators are expected to yield their entries in ascending order of their
+    /// names. They are merged one entry at a time, and base entries shadowed by an
+    /// overlay entry with the same name are skipped.
+    Merged {
+        /// The read directory iterator from the base file system.
+        base: Peekable<B::ReadDir>,
+        /// The read directory iterator from the overlay file system.
+        overlay: Peekable<O::ReadDir>,
+        /// The mount point where the overlay file system is mounted.
+        mount_point: PathBuf,
+    },
 }
 
 /// File for a stacked file system, which can represent files from either the base or overlay file system.
@@ -148,6 +164,34 @@ where
     mount_point: PathBuf,
 }
 
+/// How a [`StackedFs::rename_checked`] was performed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenameKind {
+    /// Both paths are in the same file system, which renamed the entry itself.
+    Atomic,
+    /// The paths are in different file systems, so the file was copied to the
+    /// destination and then removed from the source. This is not atomic: if it fails,
+    /// the destination may already exist while the source still does.
+    CopyDelete,
+}
+
+/// Strips the mount point from the path, yielding the path within the overlay file system.
+///
+/// The mount point itself (with or without a trailing slash) maps to the current
+/// directory of the overlay file system rather than an empty path.
+fn strip_mount_point<'a>(
+    mount_point: &Path,
+    path: &'a Path,
+) -> Result<&'a Path, std::path::StripPrefixError> {
+    path.strip_prefix(mount_point).map(|path| {
+        if path.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            path
+        }
+    })
+}
+
 impl<B, O> StackedFs<B, O>
 where
     B: UniFs,
@@ -161,6 +205,57 @@ where
             mount_point: mount_point.into(),
         }
     }
+
+    /// Renames a file or directory like [`UniFs::rename`], returning whether the rename
+    /// crossed the mount point.
+    ///
+    /// A rename within the base or the overlay file system is passed to it and reported
+    /// as [`RenameKind::Atomic`]. A file moved between them is copied and removed,
+    /// which is reported as [`RenameKind::CopyDelete`], so callers relying on atomic
+    /// renames can detect it.
+    pub fn rename_checked<P: AsRef<Path>, Q: AsRef<Path>>(
+        &self,
+        from: P,
+        to: Q,
+    ) -> crate::Result<RenameKind> {
+        let from = from.as_ref();
+        let to = to.as_ref();
+        match (
+            strip_mount_point(&self.mount_point, from),
+            strip_mount_point(&self.mount_point, to),
+        ) {
+            (Ok(from), Ok(to)) => self.overlay_fs.rename(from, to)?,
+            (Err(_), Err(_)) => self.base_fs.rename(from, to)?,
+            (Ok(from), Err(_)) => {
+                let mut from_file = self.overlay_fs.new_openoptions().read(true).open(from)?;
+                let mut to_file = self
+                    .base_fs
+                    .new_openoptions()
+                    .write(true)
+                    .create(true)
+                    .open(to)?;
+
+                std::io::copy(&mut from_file, &mut to_file)?;
+                self.overlay_fs.remove_file(from)?;
+                return Ok(RenameKind::CopyDelete);
+            }
+            (Err(_), Ok(to)) => {
+                let mut from_file = self.base_fs.new_openoptions().read(true).open(from)?;
+                let mut to_file = self
+                    .overlay_fs
+                    .new_openoptions()
+                    .write(true)
+                    .create(true)
+                    .open(to)?;
+
+                std::io::copy(&mut from_file, &mut to_file)?;
+                self.base_fs.remove_file(from)?;
+                return Ok(RenameKind::CopyDelete);
+            }
+        }
+
+        Ok(RenameKind::Atomic)
+    }
 }
 
 impl<B, O> UniFs for StackedFs<B, O>
@@ -178,8 +273,17 @@ where
 
     fn canonicalize<P: AsRef<Path>>(&self, path: P) -> crate::Result<PathBuf> {
         let path = path.as_ref();
-        if let Ok(path) = path.strip_prefix(&self.mount_point) {
-            Ok(self.mount_point.join(self.overlay_fs.canonicalize(path)?))
+        if let Ok(path) = strip_mount_point(&self.mount_point, path) {
+            let canonical = self.overlay_fs.canonicalize(path)?;
+            let relative = canonical
+                .components()
+                .filter(|comp| !matches!(comp, Component::Prefix(_) | Component::RootDir))
+                .collect::<PathBuf>();
+            if relative.as_os_str().is_empty() {
+                Ok(self.mount_point.clone())
+            } else {
+                Ok(self.mount_point.join(relative))
+            }
         } else {
             self.base_fs.canonicalize(path)
         }
@@ -189,8 +293,8 @@ where
         let from = from.as_ref();
         let to = to.as_ref();
         match (
-            from.strip_prefix(&self.mount_point),
-            to.strip_prefix(&self.mount_point),
+            strip_mount_point(&self.mount_point, from),
+            strip_mount_point(&self.mount_point, to),
         ) {
             (Ok(from), Ok(to)) => self.overlay_fs.copy(from, to),
             (Err(_), Err(_)) => self.base_fs.copy(from, to),
@@ -221,16 +325,48 @@ where
 
     fn create_dir<P: AsRef<Path>>(&self, path: P) -> crate::Result<()> {
         let path = path.as_ref();
-        if let Ok(path) = path.strip_prefix(&self.mount_point) {
+        if let Ok(path) = strip_mount_point(&self.mount_point, path) {
             self.overlay_fs.create_dir(path)
         } else {
             self.base_fs.create_dir(path)
         }
     }
 
+    fn create_special<P: AsRef<Path>>(&self, path: P, kind: SpecialFileKind) -> crate::Result<()> {
+        let path = path.as_ref();
+        if let Ok(path) = strip_mount_point(&self.mount_point, path) {
+            self.overlay_fs.create_special(path, kind)
+        } else {
+            self.base_fs.create_special(path, kind)
+        }
+    }
+
+    fn set_owner<P: AsRef<Path>>(
+        &self,
+        path: P,
+        uid: Option<u32>,
+        gid: Option<u32>,
+    ) -> crate::Result<()> {
+        let path = path.as_ref();
+        if let Ok(path) = strip_mount_point(&self.mount_point, path) {
+            self.overlay_fs.set_owner(path, uid, gid)
+        } else {
+            self.base_fs.set_owner(path, uid, gid)
+        }
+    }
+
+    fn symlink<P: AsRef<Path>, Q: AsRef<Path>>(&self, original: P, link: Q) -> crate::Result<()> {
+        let link = link.as_ref();
+        if let Ok(link) = strip_mount_point(&self.mount_point, link) {
+            self.overlay_fs.symlink(original, link)
+        } else {
+            self.base_fs.symlink(original, link)
+        }
+    }
+
     fn exists<P: AsRef<Path>>(&self, path: P) -> crate::Result<bool> {
         let path = path.as_ref();
-        if let Ok(path) = path.strip_prefix(&self.mount_point) {
+        if let Ok(path) = strip_mount_point(&self.mount_point, path) {
             if self.overlay_fs.exists(path)? {
                 return Ok(true);
             }
@@ -243,12 +379,13 @@ where
         let original = original.as_ref();
         let link = link.as_ref();
         match (
-            original.strip_prefix(&self.mount_point),
-            link.strip_prefix(&self.mount_point),
+            strip_mount_point(&self.mount_point, original),
+            strip_mount_point(&self.mount_point, link),
         ) {
             (Ok(original), Ok(link)) => self.overlay_fs.hard_link(original, link),
             (Err(_), Err(_)) => self.base_fs.hard_link(original, link),
-            _ => Err(std::io::Error::other(
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::CrossesDevices,
                 "Cannot create hard link across filesystems",
             )),
         }
@@ -256,7 +393,7 @@ where
 
     fn metadata<P: AsRef<Path>>(&self, path: P) -> crate::Result<Self::Metadata> {
         let path = path.as_ref();
-        if let Ok(path) = path.strip_prefix(&self.mount_point) {
+        if let Ok(path) = strip_mount_point(&self.mount_point, path) {
             if self.overlay_fs.exists(path)? {
                 let metadata = self.overlay_fs.metadata(path)?;
                 return Ok(StackedMetadata::Overlay {
@@ -272,7 +409,7 @@ where
 
     fn read<P: AsRef<Path>>(&self, path: P) -> crate::Result<Vec<u8>> {
         let path = path.as_ref();
-        if let Ok(path) = path.strip_prefix(&self.mount_point) {
+        if let Ok(path) = strip_mount_point(&self.mount_point, path) {
             if self.overlay_fs.exists(path)? {
                 return self.overlay_fs.read(path);
             }
@@ -281,23 +418,50 @@ where
         self.base_fs.read(path)
     }
 
+    /// Reads the entries of a directory.
+    ///
+    /// Below the mount point, the entries of the directory in the overlay file system
+    /// are merged with those of the same directory in the base file system, hiding base
+    /// entries with the same name as an overlay entry. The merge is done lazily and
+    /// yields the entries in ascending order of their names, provided that both file
+    /// systems list them in that order, as [`crate::MemoryFs`] does.
     fn read_dir<P: AsRef<Path>>(&self, path: P) -> crate::Result<Self::ReadDir> {
         let path = path.as_ref();
-        if let Ok(path) = path.strip_prefix(&self.mount_point) {
-            let overlay_read_dir = self.overlay_fs.read_dir(path)?;
-            return Ok(StackedReadDir::Overlay {
-                data: overlay_read_dir,
-                mount_point: self.mount_point.clone(),
-            });
+        if let Ok(overlay_path) = strip_mount_point(&self.mount_point, path) {
+            let overlay_read_dir = self.overlay_fs.read_dir(overlay_path);
+            return match (overlay_read_dir, self.base_fs.read_dir(path)) {
+                (Ok(overlay), Ok(base)) => Ok(StackedReadDir::Merged {
+                    base: base.peekable(),
+                    overlay: overlay.peekable(),
+                    mount_point: self.mount_point.clone(),
+                }),
+                (Ok(overlay), Err(_)) => Ok(StackedReadDir::Overlay {
+                    data: overlay,
+                    mount_point: self.mount_point.clone(),
+                }),
+                (Err(_), Ok(base)) => Ok(StackedReadDir::Base(base)),
+                (Err(err), Err(_)) => Err(err),
+            };
         }
 
         let base_read_dir = self.base_fs.read_dir(path)?;
         Ok(StackedReadDir::Base(base_read_dir))
     }
 
+    fn physical_path<P: AsRef<Path>>(&self, path: P) -> Option<PathBuf> {
+        let path = path.as_ref();
+        if let Ok(path) = strip_mount_point(&self.mount_point, path) {
+            if self.overlay_fs.exists(path).unwrap_or_default() {
+                return self.overlay_fs.physical_path(path);
+            }
+        }
+
+        self.base_fs.physical_path(path)
+    }
+
     fn read_link<P: AsRef<Path>>(&self, path: P) -> crate::Result<PathBuf> {
         let path = path.as_ref();
-        if let Ok(path) = path.strip_prefix(&self.mount_point) {
+        if let Ok(path) = strip_mount_point(&self.mount_point, path) {
             if self.overlay_fs.exists(path)? {
                 return self.overlay_fs.read_link(path);
             }
@@ -308,7 +472,7 @@ where
 
     fn read_to_string<P: AsRef<Path>>(&self, path: P) -> crate::Result<String> {
         let path = path.as_ref();
-        if let Ok(path) = path.strip_prefix(&self.mount_point) {
+        if let Ok(path) = strip_mount_point(&self.mount_point, path) {
             if self.overlay_fs.exists(path)? {
                 return self.overlay_fs.read_to_string(path);
             }
@@ -319,7 +483,7 @@ where
 
     fn remove_dir<P: AsRef<Path>>(&self, path: P) -> crate::Result<()> {
         let path = path.as_ref();
-        if let Ok(path) = path.strip_prefix(&self.mount_point) {
+        if let Ok(path) = strip_mount_point(&self.mount_point, path) {
             if self.overlay_fs.exists(path)? {
                 return self.overlay_fs.remove_dir(path);
             }
@@ -330,7 +494,7 @@ where
 
     fn remove_dir_all<P: AsRef<Path>>(&self, path: P) -> crate::Result<()> {
         let path = path.as_ref();
-        if let Ok(path) = path.strip_prefix(&self.mount_point) {
+        if let Ok(path) = strip_mount_point(&self.mount_point, path) {
             if self.overlay_fs.exists(path)? {
                 return self.overlay_fs.remove_dir_all(path);
             }
@@ -341,7 +505,7 @@ where
 
     fn remove_file<P: AsRef<Path>>(&self, path: P) -> crate::Result<()> {
         let path = path.as_ref();
-        if let Ok(path) = path.strip_prefix(&self.mount_point) {
+        if let Ok(path) = strip_mount_point(&self.mount_point, path) {
             if self.overlay_fs.exists(path)? {
                 return self.overlay_fs.remove_file(path);
             }
@@ -350,40 +514,10 @@ where
         self.base_fs.remove_file(path)
     }
 
+    /// Renames a file or directory, copying and removing a file moved across the mount
+    /// point. See [`StackedFs::rename_checked`].
     fn rename<P: AsRef<Path>, Q: AsRef<Path>>(&self, from: P, to: Q) -> crate::Result<()> {
-        let from = from.as_ref();
-        let to = to.as_ref();
-        match (
-            from.strip_prefix(&self.mount_point),
-            to.strip_prefix(&self.mount_point),
-        ) {
-            (Ok(from), Ok(to)) => self.overlay_fs.rename(from, to),
-            (Err(_), Err(_)) => self.base_fs.rename(from, to),
-            (Ok(from), Err(_)) => {
-                let mut from_file = self.overlay_fs.new_openoptions().read(true).open(from)?;
-                let mut to_file = self
-                    .base_fs
-                    .new_openoptions()
-                    .write(true)
-                    .create(true)
-                    .open(to)?;
-
-                std::io::copy(&mut from_file, &mut to_file)?;
-                self.overlay_fs.remove_file(from)
-            }
-            (Err(_), Ok(to)) => {
-                let mut from_file = self.base_fs.new_openoptions().read(true).open(from)?;
-                let mut to_file = self
-                    .overlay_fs
-                    .new_openoptions()
-                    .write(true)
-                    .create(true)
-                    .open(to)?;
-
-                std::io::copy(&mut from_file, &mut to_file)?;
-                self.base_fs.remove_file(from)
-            }
-        }
+        self.rename_checked(from, to).map(|_| ())
     }
 
     fn set_permissions<P: AsRef<Path>>(
@@ -392,7 +526,7 @@ where
         perm: Self::Permissions,
     ) -> crate::Result<()> {
         let path = path.as_ref();
-        if let Ok(path) = path.strip_prefix(&self.mount_point) {
+        if let Ok(path) = strip_mount_point(&self.mount_point, path) {
             if self.overlay_fs.exists(path)? {
                 return self.overlay_fs.set_permissions(
                     path,
@@ -423,7 +557,7 @@ where
 
     fn symlink_metadata<P: AsRef<Path>>(&self, path: P) -> crate::Result<Self::Metadata> {
         let path = path.as_ref();
-        if let Ok(path) = path.strip_prefix(&self.mount_point) {
+        if let Ok(path) = strip_mount_point(&self.mount_point, path) {
             if self.overlay_fs.exists(path)? {
                 let metadata = self.overlay_fs.symlink_metadata(path)?;
                 return Ok(StackedMetadata::Overlay {
@@ -526,6 +660,27 @@ where
             StackedMetadata::Overlay { data, .. } => data.created(),
         }
     }
+
+    fn nlink(&self) -> Option<u64> {
+        match self {
+            StackedMetadata::Base(meta) => meta.nlink(),
+            StackedMetadata::Overlay { data, .. } => data.nlink(),
+        }
+    }
+
+    fn uid(&self) -> Option<u32> {
+        match self {
+            StackedMetadata::Base(meta) => meta.uid(),
+            StackedMetadata::Overlay { data, .. } => data.uid(),
+        }
+    }
+
+    fn gid(&self) -> Option<u32> {
+        match self {
+            StackedMetadata::Base(meta) => meta.gid(),
+            StackedMetadata::Overlay { data, .. } => data.gid(),
+        }
+    }
 }
 
 impl<B, O> PartialEq for StackedPermissions<B, O>
@@ -549,6 +704,20 @@ where
 {
 }
 
+impl<B, O> Hash for StackedPermissions<B, O>
+where
+    B: UniPermissions + Hash,
+    O: UniPermissions + Hash,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            StackedPermissions::Base(perm) => perm.hash(state),
+            StackedPermissions::Overlay(perm) => perm.hash(state),
+        }
+    }
+}
+
 impl<B, O> UniPermissions for StackedPermissions<B, O>
 where
     B: UniPermissions,
@@ -658,6 +827,35 @@ where
                     mount_point: mount_point.clone(),
                 })
             }),
+            StackedReadDir::Merged {
+                base,
+                overlay,
+                mount_point,
+            } => {
+                // Errors are passed on as soon as they are encountered.
+                let order = match (base.peek(), overlay.peek()) {
+                    (None, None) => return None,
+                    (Some(Err(_)), _) | (Some(_), None) => Ordering::Less,
+                    (_, Some(Err(_))) | (None, Some(_)) => Ordering::Greater,
+                    (Some(Ok(base)), Some(Ok(overlay))) => {
+                        base.file_name().cmp(&overlay.file_name())
+                    }
+                };
+
+                if order == Ordering::Less {
+                    return base.next().map(|res| res.map(StackedDirEntry::Base));
+                }
+                if order == Ordering::Equal {
+                    // The base entry is shadowed by the overlay entry.
+                    base.next();
+                }
+                overlay.next().map(|res| {
+                    res.map(|entry| StackedDirEntry::Overlay {
+                        data: entry,
+                        mount_point: mount_point.clone(),
+                    })
+                })
+            }
         }
     }
 }
@@ -804,6 +1002,48 @@ where
             )),
         }
     }
+
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> crate::Result<usize> {
+        match self {
+            StackedFile::Base(file) => file.read_at(buf, offset),
+            StackedFile::Overlay { data, .. } => data.read_at(buf, offset),
+        }
+    }
+
+    fn lock_exclusive(&self) -> crate::Result<()> {
+        match self {
+            StackedFile::Base(file) => file.lock_exclusive(),
+            StackedFile::Overlay { data, .. } => data.lock_exclusive(),
+        }
+    }
+
+    fn lock_shared(&self) -> crate::Result<()> {
+        match self {
+            StackedFile::Base(file) => file.lock_shared(),
+            StackedFile::Overlay { data, .. } => data.lock_shared(),
+        }
+    }
+
+    fn try_lock_exclusive(&self) -> crate::Result<()> {
+        match self {
+            StackedFile::Base(file) => file.try_lock_exclusive(),
+            StackedFile::Overlay { data, .. } => data.try_lock_exclusive(),
+        }
+    }
+
+    fn try_lock_shared(&self) -> crate::Result<()> {
+        match self {
+            StackedFile::Base(file) => file.try_lock_shared(),
+            StackedFile::Overlay { data, .. } => data.try_lock_shared(),
+        }
+    }
+
+    fn unlock(&self) -> crate::Result<()> {
+        match self {
+            StackedFile::Base(file) => file.unlock(),
+            StackedFile::Overlay { data, .. } => data.unlock(),
+        }
+    }
 }
 
 impl<B, O> Default for StackedFileTimes<B, O>
@@ -885,9 +1125,16 @@ where
         self
     }
 
+    fn direct(&mut self, direct: bool) -> &mut Self {
+        self.base.direct(direct);
+        self.overlay.direct(direct);
+
+        self
+    }
+
     fn open<P: AsRef<Path>>(&self, path: P) -> crate::Result<Self::File> {
         let path = path.as_ref();
-        if let Ok(path) = path.strip_prefix(&self.mount_point) {
+        if let Ok(path) = strip_mount_point(&self.mount_point, path) {
             self.overlay.open(path).map(|file| StackedFile::Overlay {
                 data: file,
                 mount_point: self.mount_point.clone(),
@@ -905,7 +1152,7 @@ where
 {
     fn create<P: AsRef<Path>>(&self, path: P) -> crate::Result<()> {
         let path = path.as_ref();
-        if let Ok(path) = path.strip_prefix(&self.mount_point) {
+        if let Ok(path) = strip_mount_point(&self.mount_point, path) {
             self.overlay.create(path)
         } else {
             self.base.create(path)
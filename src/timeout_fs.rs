@@ -0,0 +1,367 @@
+//! This module provides a wrapper that limits the duration of filesystem operations.
+
+use std::{
+    io::ErrorKind,
+    panic::{self, AssertUnwindSafe},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError, Sender},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use crate::{Result, SpecialFileKind, UniFs, WriteReport};
+
+/// Wraps a filesystem to limit the duration of each operation.
+///
+/// Every operation is run on a worker thread. If it does not complete within the
+/// configured timeout, an error of kind [`ErrorKind::TimedOut`] is returned. The
+/// operation itself is abandoned but **not cancelled**: it keeps running in the
+/// background until it completes, and its result is discarded. A timed out write may
+/// therefore still be applied later.
+///
+/// The workers are spawned as needed, up to the limit given to
+/// [`TimeoutFs::with_workers`], and reused afterwards. At most that many threads exist
+/// at any time, even if every one of them is stuck in an abandoned operation. While
+/// all workers are busy, further operations wait for one to become free and time out
+/// like any other operation. An operation that times out before a worker picked it up
+/// is not run at all.
+///
+/// Opening files through [`UniFs::new_openoptions`] and creating directories through
+/// [`UniFs::new_dirbuilder`] are forwarded to the wrapped filesystem without a timeout.
+/// Use [`UniFs::open_file`], [`UniFs::create_file`] and [`UniFs::create_dir`] instead
+/// for time-limited operations.
+pub struct TimeoutFs<FS> {
+    fs: Arc<FS>,
+    timeout: Duration,
+    pool: WorkerPool,
+}
+
+impl<FS> TimeoutFs<FS>
+where
+    FS: UniFs + Send + Sync + 'static,
+{
+    /// The maximum number of worker threads used by [`TimeoutFs::new`].
+    pub const DEFAULT_WORKERS: usize = 8;
+
+    /// Creates a new `TimeoutFs` wrapping the specified filesystem, failing every
+    /// operation that takes longer than `timeout`.
+    ///
+    /// Operations run on up to [`TimeoutFs::DEFAULT_WORKERS`] threads.
+    pub fn new(fs: FS, timeout: Duration) -> Self {
+        Self::with_workers(fs, timeout, Self::DEFAULT_WORKERS)
+    }
+
+    /// Creates a new `TimeoutFs` wrapping the specified filesystem, failing every
+    /// operation that takes longer than `timeout` and running operations on up to
+    /// `workers` threads. A limit of zero is treated as one.
+    pub fn with_workers(fs: FS, timeout: Duration, workers: usize) -> Self {
+        Self {
+            fs: Arc::new(fs),
+            timeout,
+            pool: WorkerPool::new(workers.max(1)),
+        }
+    }
+
+    /// Returns the configured timeout.
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Sets the timeout for subsequent operations.
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    fn run<T, F>(&self, name: &str, op: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&FS) -> Result<T> + Send + 'static,
+    {
+        let fs = Arc::clone(&self.fs);
+        let (sender, receiver) = mpsc::channel();
+        let abandoned = Arc::new(AtomicBool::new(false));
+        let job_abandoned = Arc::clone(&abandoned);
+        self.pool.execute(Box::new(move || {
+            if job_abandoned.load(Ordering::Acquire) {
+                return;
+            }
+            // A panic drops the sender without a result, which is reported below.
+            if let Ok(res) = panic::catch_unwind(AssertUnwindSafe(|| op(&fs))) {
+                // The receiver is gone if the operation timed out, so the result is
+                // discarded.
+                let _ = sender.send(res);
+            }
+        }));
+
+        match receiver.recv_timeout(self.timeout) {
+            Ok(res) => res,
+            Err(RecvTimeoutError::Timeout) => {
+                abandoned.store(true, Ordering::Release);
+                Err(std::io::Error::new(
+                    ErrorKind::TimedOut,
+                    format!(
+                        "Operation '{name}' did not complete within {:?}",
+                        self.timeout
+                    ),
+                ))
+            }
+            Err(RecvTimeoutError::Disconnected) => Err(std::io::Error::other(format!(
+                "Operation '{name}' panicked"
+            ))),
+        }
+    }
+}
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// The threads that run the operations of a [`TimeoutFs`].
+///
+/// The workers exit once the pool is dropped and they finished their current job.
+struct WorkerPool {
+    sender: Sender<Job>,
+    receiver: Arc<Mutex<Receiver<Job>>>,
+    /// The number of workers waiting for a job that was not handed to them yet.
+    idle: Arc<AtomicUsize>,
+    /// The number of workers spawned so far.
+    workers: Mutex<usize>,
+    max_workers: usize,
+}
+
+impl WorkerPool {
+    fn new(max_workers: usize) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self {
+            sender,
+            receiver: Arc::new(Mutex::new(receiver)),
+            idle: Arc::new(AtomicUsize::new(0)),
+            workers: Mutex::new(0),
+            max_workers,
+        }
+    }
+
+    /// Queues `job`, spawning a worker for it if none is idle and the limit allows it.
+    fn execute(&self, job: Job) {
+        let claimed_idle = self
+            .idle
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |idle| {
+                idle.checked_sub(1)
+            })
+            .is_ok();
+        if !claimed_idle {
+            let mut workers = self.workers.lock().unwrap_or_else(|err| err.into_inner());
+            if *workers < self.max_workers {
+                *workers += 1;
+                let receiver = Arc::clone(&self.receiver);
+                let idle = Arc::clone(&self.idle);
+                std::thread::spawn(move || loop {
+                    let job = receiver
+                        .lock()
+                        .unwrap_or_else(|err| err.into_inner())
+                        .recv();
+                    match job {
+                        Ok(job) => job(),
+                        Err(_) => return,
+                    }
+                    idle.fetch_add(1, Ordering::AcqRel);
+                });
+            }
+        }
+
+        // The workers only stop once the pool is dropped, so sending cannot fail.
+        let _ = self.sender.send(job);
+    }
+}
+
+impl<FS> UniFs for TimeoutFs<FS>
+where
+    FS: UniFs + Send + Sync + 'static,
+    FS::Metadata: Send + 'static,
+    FS::ReadDir: Send + 'static,
+    FS::Permissions: Send + 'static,
+    FS::File: Send + 'static,
+{
+    type Metadata = FS::Metadata;
+    type ReadDir = FS::ReadDir;
+    type DirEntry = FS::DirEntry;
+    type Permissions = FS::Permissions;
+    type File = FS::File;
+    type OpenOptions = FS::OpenOptions;
+    type DirBuilder = FS::DirBuilder;
+
+    fn canonicalize<P: AsRef<Path>>(&self, path: P) -> Result<PathBuf> {
+        let path = path.as_ref().to_path_buf();
+
+        self.run("canonicalize", move |fs| fs.canonicalize(path))
+    }
+
+    fn copy<P: AsRef<Path>, Q: AsRef<Path>>(&self, from: P, to: Q) -> Result<u64> {
+        let from = from.as_ref().to_path_buf();
+        let to = to.as_ref().to_path_buf();
+
+        self.run("copy", move |fs| fs.copy(from, to))
+    }
+
+    fn create_dir<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref().to_path_buf();
+
+        self.run("create_dir", move |fs| fs.create_dir(path))
+    }
+
+    fn create_dir_all<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref().to_path_buf();
+
+        self.run("create_dir_all", move |fs| fs.create_dir_all(path))
+    }
+
+    fn exists<P: AsRef<Path>>(&self, path: P) -> Result<bool> {
+        let path = path.as_ref().to_path_buf();
+
+        self.run("exists", move |fs| fs.exists(path))
+    }
+
+    fn hard_link<P: AsRef<Path>, Q: AsRef<Path>>(&self, original: P, link: Q) -> Result<()> {
+        let original = original.as_ref().to_path_buf();
+        let link = link.as_ref().to_path_buf();
+
+        self.run("hard_link", move |fs| fs.hard_link(original, link))
+    }
+
+    fn metadata<P: AsRef<Path>>(&self, path: P) -> Result<Self::Metadata> {
+        let path = path.as_ref().to_path_buf();
+
+        self.run("metadata", move |fs| fs.metadata(path))
+    }
+
+    fn read<P: AsRef<Path>>(&self, path: P) -> Result<Vec<u8>> {
+        let path = path.as_ref().to_path_buf();
+
+        self.run("read", move |fs| fs.read(path))
+    }
+
+    fn read_dir<P: AsRef<Path>>(&self, path: P) -> Result<Self::ReadDir> {
+        let path = path.as_ref().to_path_buf();
+
+        self.run("read_dir", move |fs| fs.read_dir(path))
+    }
+
+    fn read_link<P: AsRef<Path>>(&self, path: P) -> Result<PathBuf> {
+        let path = path.as_ref().to_path_buf();
+
+        self.run("read_link", move |fs| fs.read_link(path))
+    }
+
+    fn read_to_string<P: AsRef<Path>>(&self, path: P) -> Result<String> {
+        let path = path.as_ref().to_path_buf();
+
+        self.run("read_to_string", move |fs| fs.read_to_string(path))
+    }
+
+    fn remove_dir<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref().to_path_buf();
+
+        self.run("remove_dir", move |fs| fs.remove_dir(path))
+    }
+
+    fn remove_dir_all<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref().to_path_buf();
+
+        self.run("remove_dir_all", move |fs| fs.remove_dir_all(path))
+    }
+
+    fn remove_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref().to_path_buf();
+
+        self.run("remove_file", move |fs| fs.remove_file(path))
+    }
+
+    fn rename<P: AsRef<Path>, Q: AsRef<Path>>(&self, from: P, to: Q) -> Result<()> {
+        let from = from.as_ref().to_path_buf();
+        let to = to.as_ref().to_path_buf();
+
+        self.run("rename", move |fs| fs.rename(from, to))
+    }
+
+    fn set_permissions<P: AsRef<Path>>(&self, path: P, perm: Self::Permissions) -> Result<()> {
+        let path = path.as_ref().to_path_buf();
+
+        self.run("set_permissions", move |fs| fs.set_permissions(path, perm))
+    }
+
+    fn symlink_metadata<P: AsRef<Path>>(&self, path: P) -> Result<Self::Metadata> {
+        let path = path.as_ref().to_path_buf();
+
+        self.run("symlink_metadata", move |fs| fs.symlink_metadata(path))
+    }
+
+    fn write<P: AsRef<Path>, C: AsRef<[u8]>>(&self, path: P, contents: C) -> Result<()> {
+        let path = path.as_ref().to_path_buf();
+        let contents = contents.as_ref().to_vec();
+
+        self.run("write", move |fs| fs.write(path, contents))
+    }
+
+    fn write_reporting<P: AsRef<Path>, C: AsRef<[u8]>>(
+        &self,
+        path: P,
+        contents: C,
+    ) -> Result<WriteReport> {
+        let path = path.as_ref().to_path_buf();
+        let contents = contents.as_ref().to_vec();
+
+        self.run("write_reporting", move |fs| {
+            fs.write_reporting(path, contents)
+        })
+    }
+
+    fn create_special<P: AsRef<Path>>(&self, path: P, kind: SpecialFileKind) -> Result<()> {
+        let path = path.as_ref().to_path_buf();
+
+        self.run("create_special", move |fs| fs.create_special(path, kind))
+    }
+
+    fn set_owner<P: AsRef<Path>>(&self, path: P, uid: Option<u32>, gid: Option<u32>) -> Result<()> {
+        let path = path.as_ref().to_path_buf();
+
+        self.run("set_owner", move |fs| fs.set_owner(path, uid, gid))
+    }
+
+    fn symlink<P: AsRef<Path>, Q: AsRef<Path>>(&self, original: P, link: Q) -> Result<()> {
+        let original = original.as_ref().to_path_buf();
+        let link = link.as_ref().to_path_buf();
+
+        self.run("symlink", move |fs| fs.symlink(original, link))
+    }
+
+    fn physical_path<P: AsRef<Path>>(&self, path: P) -> Option<PathBuf> {
+        self.fs.physical_path(path)
+    }
+
+    fn open_file<P: AsRef<Path>>(&self, path: P) -> Result<Self::File> {
+        let path = path.as_ref().to_path_buf();
+
+        self.run("open_file", move |fs| fs.open_file(path))
+    }
+
+    fn create_file<P: AsRef<Path>>(&self, path: P) -> Result<Self::File> {
+        let path = path.as_ref().to_path_buf();
+
+        self.run("create_file", move |fs| fs.create_file(path))
+    }
+
+    fn create_new_file<P: AsRef<Path>>(&self, path: P) -> Result<Self::File> {
+        let path = path.as_ref().to_path_buf();
+
+        self.run("create_new_file", move |fs| fs.create_new_file(path))
+    }
+
+    fn new_openoptions(&self) -> Self::OpenOptions {
+        self.fs.new_openoptions()
+    }
+
+    fn new_dirbuilder(&self) -> Self::DirBuilder {
+        self.fs.new_dirbuilder()
+    }
+}
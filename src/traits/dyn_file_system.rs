@@ -0,0 +1,319 @@
+use std::{
+    any::Any,
+    path::{Path, PathBuf},
+};
+
+use crate::{Result, SpecialFileKind, UniFs, WriteReport};
+
+/// An object-safe version of the [`UniFs`] trait, allowing filesystems to be
+/// selected at runtime and stored as `Box<dyn DynUniFs>`.
+///
+/// This trait is implemented for every type implementing [`UniFs`]. Methods of
+/// [`UniFs`] that return filesystem-specific associated types are not available.
+///
+/// The methods of this trait take paths as `&Path` and are prefixed with `dyn_`.
+/// The same generic, ergonomic methods as on [`UniFs`] are available directly on
+/// `dyn DynUniFs`, as well as on `dyn DynUniFs + Send` and `dyn DynUniFs + Send + Sync`:
+///
+/// ```
+/// use unifs::{DynUniFs, MemoryFs};
+///
+/// # fn main() -> unifs::Result<()> {
+/// let fs: Box<dyn DynUniFs> = Box::new(MemoryFs::default());
+/// fs.write("/file.txt", "Hello, World!")?;
+/// assert_eq!(fs.read_to_string("/file.txt")?, "Hello, World!");
+/// # Ok(())
+/// # }
+/// ```
+pub trait DynUniFs {
+    /// See [`UniFs::canonicalize`].
+    fn dyn_canonicalize(&self, path: &Path) -> Result<PathBuf>;
+
+    /// See [`UniFs::copy`].
+    fn dyn_copy(&self, from: &Path, to: &Path) -> Result<u64>;
+
+    /// See [`UniFs::create_dir`].
+    fn dyn_create_dir(&self, path: &Path) -> Result<()>;
+
+    /// See [`UniFs::create_dir_all`].
+    fn dyn_create_dir_all(&self, path: &Path) -> Result<()>;
+
+    /// See [`UniFs::exists`].
+    fn dyn_exists(&self, path: &Path) -> Result<bool>;
+
+    /// See [`UniFs::hard_link`].
+    fn dyn_hard_link(&self, original: &Path, link: &Path) -> Result<()>;
+
+    /// See [`UniFs::read`].
+    fn dyn_read(&self, path: &Path) -> Result<Vec<u8>>;
+
+    /// See [`UniFs::read_link`].
+    fn dyn_read_link(&self, path: &Path) -> Result<PathBuf>;
+
+    /// See [`UniFs::read_to_string`].
+    fn dyn_read_to_string(&self, path: &Path) -> Result<String>;
+
+    /// See [`UniFs::remove_dir`].
+    fn dyn_remove_dir(&self, path: &Path) -> Result<()>;
+
+    /// See [`UniFs::remove_dir_all`].
+    fn dyn_remove_dir_all(&self, path: &Path) -> Result<()>;
+
+    /// See [`UniFs::remove_file`].
+    fn dyn_remove_file(&self, path: &Path) -> Result<()>;
+
+    /// See [`UniFs::rename`].
+    fn dyn_rename(&self, from: &Path, to: &Path) -> Result<()>;
+
+    /// See [`UniFs::write`].
+    fn dyn_write(&self, path: &Path, contents: &[u8]) -> Result<()>;
+
+    /// See [`UniFs::write_reporting`].
+    fn dyn_write_reporting(&self, path: &Path, contents: &[u8]) -> Result<WriteReport>;
+
+    /// See [`UniFs::create_special`].
+    fn dyn_create_special(&self, path: &Path, kind: SpecialFileKind) -> Result<()>;
+
+    /// See [`UniFs::set_owner`].
+    fn dyn_set_owner(&self, path: &Path, uid: Option<u32>, gid: Option<u32>) -> Result<()>;
+
+    /// See [`UniFs::symlink`].
+    fn dyn_symlink(&self, original: &Path, link: &Path) -> Result<()>;
+
+    /// See [`UniFs::physical_path`].
+    fn dyn_physical_path(&self, path: &Path) -> Option<PathBuf>;
+
+    /// Returns the filesystem as [`Any`], so that the concrete filesystem can be
+    /// recovered with `downcast_ref`.
+    ///
+    /// This allows calling methods specific to a filesystem through the erased
+    /// interface:
+    ///
+    /// ```
+    /// use unifs::{DynUniFs, MemoryFs};
+    ///
+    /// let fs: Box<dyn DynUniFs> = Box::new(MemoryFs::default());
+    /// let memory_fs = fs.as_any().downcast_ref::<MemoryFs>();
+    /// assert!(memory_fs.is_some());
+    /// ```
+    fn as_any(&self) -> &dyn Any
+    where
+        Self: 'static;
+}
+
+impl<T: UniFs> DynUniFs for T {
+    fn dyn_canonicalize(&self, path: &Path) -> Result<PathBuf> {
+        self.canonicalize(path)
+    }
+
+    fn dyn_copy(&self, from: &Path, to: &Path) -> Result<u64> {
+        self.copy(from, to)
+    }
+
+    fn dyn_create_dir(&self, path: &Path) -> Result<()> {
+        self.create_dir(path)
+    }
+
+    fn dyn_create_dir_all(&self, path: &Path) -> Result<()> {
+        self.create_dir_all(path)
+    }
+
+    fn dyn_exists(&self, path: &Path) -> Result<bool> {
+        self.exists(path)
+    }
+
+    fn dyn_hard_link(&self, original: &Path, link: &Path) -> Result<()> {
+        self.hard_link(original, link)
+    }
+
+    fn dyn_read(&self, path: &Path) -> Result<Vec<u8>> {
+        self.read(path)
+    }
+
+    fn dyn_read_link(&self, path: &Path) -> Result<PathBuf> {
+        self.read_link(path)
+    }
+
+    fn dyn_read_to_string(&self, path: &Path) -> Result<String> {
+        self.read_to_string(path)
+    }
+
+    fn dyn_remove_dir(&self, path: &Path) -> Result<()> {
+        self.remove_dir(path)
+    }
+
+    fn dyn_remove_dir_all(&self, path: &Path) -> Result<()> {
+        self.remove_dir_all(path)
+    }
+
+    fn dyn_remove_file(&self, path: &Path) -> Result<()> {
+        self.remove_file(path)
+    }
+
+    fn dyn_rename(&self, from: &Path, to: &Path) -> Result<()> {
+        self.rename(from, to)
+    }
+
+    fn dyn_write(&self, path: &Path, contents: &[u8]) -> Result<()> {
+        self.write(path, contents)
+    }
+
+    fn dyn_write_reporting(&self, path: &Path, contents: &[u8]) -> Result<WriteReport> {
+        self.write_reporting(path, contents)
+    }
+
+    fn dyn_create_special(&self, path: &Path, kind: SpecialFileKind) -> Result<()> {
+        self.create_special(path, kind)
+    }
+
+    fn dyn_set_owner(&self, path: &Path, uid: Option<u32>, gid: Option<u32>) -> Result<()> {
+        self.set_owner(path, uid, gid)
+    }
+
+    fn dyn_symlink(&self, original: &Path, link: &Path) -> Result<()> {
+        self.symlink(original, link)
+    }
+
+    fn dyn_physical_path(&self, path: &Path) -> Option<PathBuf> {
+        self.physical_path(path)
+    }
+
+    fn as_any(&self) -> &dyn Any
+    where
+        Self: 'static,
+    {
+        self
+    }
+}
+
+/// Implements the generic methods of [`UniFs`] on the trait object `$ty`, so that they
+/// are also available if the object is `Send` or `Send + Sync`.
+macro_rules! dyn_helpers {
+    ($ty:ty) => {
+        impl $ty {
+            /// See [`UniFs::canonicalize`].
+            pub fn canonicalize<P: AsRef<Path>>(&self, path: P) -> Result<PathBuf> {
+                self.dyn_canonicalize(path.as_ref())
+            }
+
+            /// See [`UniFs::copy`].
+            pub fn copy<P: AsRef<Path>, Q: AsRef<Path>>(&self, from: P, to: Q) -> Result<u64> {
+                self.dyn_copy(from.as_ref(), to.as_ref())
+            }
+
+            /// See [`UniFs::create_dir`].
+            pub fn create_dir<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+                self.dyn_create_dir(path.as_ref())
+            }
+
+            /// See [`UniFs::create_dir_all`].
+            pub fn create_dir_all<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+                self.dyn_create_dir_all(path.as_ref())
+            }
+
+            /// See [`UniFs::exists`].
+            pub fn exists<P: AsRef<Path>>(&self, path: P) -> Result<bool> {
+                self.dyn_exists(path.as_ref())
+            }
+
+            /// See [`UniFs::hard_link`].
+            pub fn hard_link<P: AsRef<Path>, Q: AsRef<Path>>(
+                &self,
+                original: P,
+                link: Q,
+            ) -> Result<()> {
+                self.dyn_hard_link(original.as_ref(), link.as_ref())
+            }
+
+            /// See [`UniFs::read`].
+            pub fn read<P: AsRef<Path>>(&self, path: P) -> Result<Vec<u8>> {
+                self.dyn_read(path.as_ref())
+            }
+
+            /// See [`UniFs::read_link`].
+            pub fn read_link<P: AsRef<Path>>(&self, path: P) -> Result<PathBuf> {
+                self.dyn_read_link(path.as_ref())
+            }
+
+            /// See [`UniFs::read_to_string`].
+            pub fn read_to_string<P: AsRef<Path>>(&self, path: P) -> Result<String> {
+                self.dyn_read_to_string(path.as_ref())
+            }
+
+            /// See [`UniFs::remove_dir`].
+            pub fn remove_dir<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+                self.dyn_remove_dir(path.as_ref())
+            }
+
+            /// See [`UniFs::remove_dir_all`].
+            pub fn remove_dir_all<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+                self.dyn_remove_dir_all(path.as_ref())
+            }
+
+            /// See [`UniFs::remove_file`].
+            pub fn remove_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+                self.dyn_remove_file(path.as_ref())
+            }
+
+            /// See [`UniFs::rename`].
+            pub fn rename<P: AsRef<Path>, Q: AsRef<Path>>(&self, from: P, to: Q) -> Result<()> {
+                self.dyn_rename(from.as_ref(), to.as_ref())
+            }
+
+            /// See [`UniFs::write`].
+            pub fn write<P: AsRef<Path>, C: AsRef<[u8]>>(
+                &self,
+                path: P,
+                contents: C,
+            ) -> Result<()> {
+                self.dyn_write(path.as_ref(), contents.as_ref())
+            }
+
+            /// See [`UniFs::write_reporting`].
+            pub fn write_reporting<P: AsRef<Path>, C: AsRef<[u8]>>(
+                &self,
+                path: P,
+                contents: C,
+            ) -> Result<WriteReport> {
+                self.dyn_write_reporting(path.as_ref(), contents.as_ref())
+            }
+
+            /// See [`UniFs::create_special`].
+            pub fn create_special<P: AsRef<Path>>(
+                &self,
+                path: P,
+                kind: SpecialFileKind,
+            ) -> Result<()> {
+                self.dyn_create_special(path.as_ref(), kind)
+            }
+
+            /// See [`UniFs::set_owner`].
+            pub fn set_owner<P: AsRef<Path>>(
+                &self,
+                path: P,
+                uid: Option<u32>,
+                gid: Option<u32>,
+            ) -> Result<()> {
+                self.dyn_set_owner(path.as_ref(), uid, gid)
+            }
+
+            /// See [`UniFs::symlink`].
+            pub fn symlink<P: AsRef<Path>, Q: AsRef<Path>>(
+                &self,
+                original: P,
+                link: Q,
+            ) -> Result<()> {
+                self.dyn_symlink(original.as_ref(), link.as_ref())
+            }
+
+            /// See [`UniFs::physical_path`].
+            pub fn physical_path<P: AsRef<Path>>(&self, path: P) -> Option<PathBuf> {
+                self.dyn_physical_path(path.as_ref())
+            }
+        }
+    };
+}
+
+dyn_helpers!(dyn DynUniFs + '_);
+dyn_helpers!(dyn DynUniFs + Send + '_);
+dyn_helpers!(dyn DynUniFs + Send + Sync + '_);
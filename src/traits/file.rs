@@ -71,4 +71,82 @@ where
     fn set_modified(&self, time: SystemTime) -> Result<()> {
         self.set_times(Self::FileTimes::default().set_modified(time))
     }
+
+    /// Reads a number of bytes starting from a given offset.
+    ///
+    /// Unlike reading through [`Read`], this only requires a shared reference, so
+    /// several threads can read from the same file at different offsets. Reading at
+    /// or after the end of the file returns `Ok(0)`.
+    ///
+    /// Whether the position of this file changes depends on the platform: on Unix it
+    /// is left unchanged, while physical files on Windows are read with `seek_read`,
+    /// which moves the position past the bytes read. Callers mixing positional and
+    /// cursor-based reads should seek explicitly afterwards.
+    ///
+    /// This function mirrors the [`std::os::unix::fs::FileExt::read_at`] function.
+    /// The default implementation returns an [`std::io::ErrorKind::Unsupported`] error.
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> Result<usize> {
+        let _ = (buf, offset);
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "Positional reads are not supported by this file",
+        ))
+    }
+
+    /// Acquires an exclusive advisory lock on the whole file, blocking until it can be
+    /// acquired.
+    ///
+    /// Advisory locks only conflict with other locks, they do not prevent reading or
+    /// writing the file. A handle holds at most one lock, so locking it again replaces
+    /// its lock. The lock is released with [`UniFile::unlock`] or when the handle and
+    /// all of its clones are dropped.
+    ///
+    /// This function mirrors the [`std::fs::File::lock`] function.
+    /// The default implementation does nothing and returns `Ok(())`.
+    fn lock_exclusive(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Acquires a shared advisory lock on the whole file, blocking until it can be
+    /// acquired.
+    ///
+    /// Any number of handles can hold a shared lock at the same time, as long as none
+    /// holds an exclusive lock. See [`UniFile::lock_exclusive`] for how locks behave.
+    ///
+    /// This function mirrors the [`std::fs::File::lock_shared`] function.
+    /// The default implementation does nothing and returns `Ok(())`.
+    fn lock_shared(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Attempts to acquire an exclusive advisory lock on the whole file without
+    /// blocking.
+    ///
+    /// Fails with [`std::io::ErrorKind::WouldBlock`] if another handle holds a lock on
+    /// the file. See [`UniFile::lock_exclusive`] for how locks behave.
+    ///
+    /// This function mirrors the [`std::fs::File::try_lock`] function.
+    /// The default implementation does nothing and returns `Ok(())`.
+    fn try_lock_exclusive(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Attempts to acquire a shared advisory lock on the whole file without blocking.
+    ///
+    /// Fails with [`std::io::ErrorKind::WouldBlock`] if another handle holds an
+    /// exclusive lock on the file. See [`UniFile::lock_exclusive`] for how locks behave.
+    ///
+    /// This function mirrors the [`std::fs::File::try_lock_shared`] function.
+    /// The default implementation does nothing and returns `Ok(())`.
+    fn try_lock_shared(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Releases the advisory lock held by this handle, if any.
+    ///
+    /// This function mirrors the [`std::fs::File::unlock`] function.
+    /// The default implementation does nothing and returns `Ok(())`.
+    fn unlock(&self) -> Result<()> {
+        Ok(())
+    }
 }
@@ -1,11 +1,11 @@
 use std::{
-    io::Write as _,
+    io::{ErrorKind, Write as _},
     path::{Path, PathBuf},
 };
 
 use crate::{
     traits::{dir_builder::UniDirBuilder, open_options::UniOpenOptions},
-    Result, UniDirEntry, UniFile, UniMetadata, UniPermissions,
+    Result, SpecialFileKind, UniDirEntry, UniFile, UniMetadata, UniPermissions, WriteReport,
 };
 
 /// A trait that represents a filesystem that can be used to perform
@@ -63,6 +63,12 @@ where
 
     /// Creates a new, empty directory at the provided path
     ///
+    /// Unlike [`UniFs::create_dir_all`], missing parent directories are never created.
+    /// Every backend and wrapper of this crate that creates directories fails with
+    /// [`std::io::ErrorKind::NotFound`] if the parent directory does not exist; the
+    /// [`crate::ReadonlyFs`] always fails, and the [`crate::DryRunFs`] only records the
+    /// operation.
+    ///
     /// This function mirrors the [`std::fs::create_dir`] function.
     fn create_dir<P: AsRef<Path>>(&self, path: P) -> Result<()>;
 
@@ -184,6 +190,92 @@ where
             .write_all(contents.as_ref())
     }
 
+    /// Writes a slice as the entire contents of a file, like [`UniFs::write`], and
+    /// reports the number of bytes written and the previous length of the file.
+    ///
+    /// If the file did not exist before, [`WriteReport::previous_len`] is `None`.
+    fn write_reporting<P: AsRef<Path>, C: AsRef<[u8]>>(
+        &self,
+        path: P,
+        contents: C,
+    ) -> Result<WriteReport> {
+        let path = path.as_ref();
+        let contents = contents.as_ref();
+
+        let previous_len = match self.metadata(path) {
+            Ok(metadata) => Some(metadata.len()),
+            Err(err) if err.kind() == ErrorKind::NotFound => None,
+            Err(err) => return Err(err),
+        };
+        self.write(path, contents)?;
+
+        Ok(WriteReport {
+            written: contents.len() as u64,
+            previous_len,
+        })
+    }
+
+    /// Creates a special file, such as a named pipe, at the specified path.
+    ///
+    /// Most filesystems have no concept of special files, so the default implementation
+    /// returns an error of kind [`ErrorKind::Unsupported`].
+    fn create_special<P: AsRef<Path>>(&self, path: P, kind: SpecialFileKind) -> Result<()> {
+        Err(std::io::Error::new(
+            ErrorKind::Unsupported,
+            format!(
+                "Cannot create special file '{}' of kind {kind:?}: not supported by this filesystem",
+                path.as_ref().display()
+            ),
+        ))
+    }
+
+    /// Changes the owner and group of a file or directory, following symbolic links.
+    ///
+    /// An ID of `None` is left unchanged. This function mirrors the
+    /// [`std::os::unix::fs::chown`] function.
+    ///
+    /// Not every filesystem records owners, so the default implementation returns an
+    /// error of kind [`ErrorKind::Unsupported`].
+    fn set_owner<P: AsRef<Path>>(&self, path: P, uid: Option<u32>, gid: Option<u32>) -> Result<()> {
+        let _ = (uid, gid);
+        Err(std::io::Error::new(
+            ErrorKind::Unsupported,
+            format!(
+                "Cannot change the owner of '{}': not supported by this filesystem",
+                path.as_ref().display()
+            ),
+        ))
+    }
+
+    /// Creates a new symbolic link at `link` pointing to `original`.
+    ///
+    /// The target is stored as given and is not required to exist. A relative target
+    /// is resolved against the directory containing the link when the link is followed.
+    ///
+    /// Not every filesystem supports symbolic links, so the default implementation
+    /// returns an error of kind [`ErrorKind::Unsupported`].
+    fn symlink<P: AsRef<Path>, Q: AsRef<Path>>(&self, original: P, link: Q) -> Result<()> {
+        Err(std::io::Error::new(
+            ErrorKind::Unsupported,
+            format!(
+                "Cannot create symbolic link '{}' to '{}': not supported by this filesystem",
+                link.as_ref().display(),
+                original.as_ref().display()
+            ),
+        ))
+    }
+
+    /// Returns the path on the physical filesystem of the operating system that
+    /// backs the specified path, if there is one.
+    ///
+    /// This allows handing files to APIs that require a real file. The default
+    /// implementation returns `None`, indicating that the path is not backed by
+    /// the physical filesystem.
+    fn physical_path<P: AsRef<Path>>(&self, path: P) -> Option<PathBuf> {
+        let _ = path;
+        None
+    }
+
     /// Attempts to open a file in read-only mode.
     ///
     ///See the [`UniOpenOptions::open`] method for more details.
@@ -313,6 +405,30 @@ impl<T: UniFs + ?Sized> UniFs for &T {
         (**self).write(path, contents)
     }
 
+    fn write_reporting<P: AsRef<Path>, C: AsRef<[u8]>>(
+        &self,
+        path: P,
+        contents: C,
+    ) -> Result<WriteReport> {
+        (**self).write_reporting(path, contents)
+    }
+
+    fn create_special<P: AsRef<Path>>(&self, path: P, kind: SpecialFileKind) -> Result<()> {
+        (**self).create_special(path, kind)
+    }
+
+    fn set_owner<P: AsRef<Path>>(&self, path: P, uid: Option<u32>, gid: Option<u32>) -> Result<()> {
+        (**self).set_owner(path, uid, gid)
+    }
+
+    fn symlink<P: AsRef<Path>, Q: AsRef<Path>>(&self, original: P, link: Q) -> Result<()> {
+        (**self).symlink(original, link)
+    }
+
+    fn physical_path<P: AsRef<Path>>(&self, path: P) -> Option<PathBuf> {
+        (**self).physical_path(path)
+    }
+
     fn open_file<P: AsRef<Path>>(&self, path: P) -> Result<Self::File> {
         (**self).open_file(path)
     }
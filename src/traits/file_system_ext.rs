@@ -1,6 +1,17 @@
-use std::path::Path;
+use std::{
+    cmp::Ordering,
+    collections::BTreeMap,
+    ffi::OsString,
+    io::{ErrorKind, Read as _, Seek as _, SeekFrom, Write as _},
+    ops::ControlFlow,
+    path::{Component, Path, PathBuf},
+};
 
-use crate::{UniDirEntry, UniFileType, UniFs};
+use crate::{
+    altroot_fs::DirHandle, AltrootFs, CopyOptions, EntrySummary, FileRecord, FileType,
+    LockFileGuard, UniDirEntry, UniFile, UniFileTimes as _, UniFileType, UniFs, UniMetadata,
+    UniOpenOptions as _,
+};
 
 /// Extends the `UniFs` trait with additional methods for filesystem operations.
 pub trait UniFsExt: UniFs {
@@ -16,10 +27,1088 @@ pub trait UniFsExt: UniFs {
     {
         WalkDirIterator::new(self, path.as_ref())
     }
+
+    /// Recursively walks through the directory at the specified path in a
+    /// deterministic order, yielding each directory entry found.
+    ///
+    /// The traversal is depth-first and pre-order: each directory is yielded before
+    /// its contents, and the children of every directory are visited in the order
+    /// given by `cmp`. The order is therefore independent of the order in which the
+    /// underlying filesystem returns directory entries.
+    fn walk_dir_sorted<'a, P, C>(
+        &'a self,
+        path: P,
+        cmp: C,
+    ) -> impl Iterator<Item = crate::Result<Self::DirEntry>> + 'a
+    where
+        P: AsRef<Path>,
+        C: FnMut(&Self::DirEntry, &Self::DirEntry) -> Ordering + 'a,
+        Self: Sized,
+    {
+        SortedWalkDirIterator::new(self, path.as_ref(), cmp)
+    }
+
+    /// Recursively walks through the directory at the specified path, calling `f`
+    /// with each directory entry found.
+    ///
+    /// Unlike [`UniFsExt::walk_dir`], no iterator borrows the filesystem during the
+    /// traversal: the entries of each directory are collected before `f` is called
+    /// for them, and the directory is descended into afterwards. The callback may
+    /// therefore modify the filesystem; subdirectories it removes are skipped.
+    ///
+    /// The traversal stops as soon as `f` returns [`ControlFlow::Break`] or an error.
+    fn for_each_entry<P, F>(&self, root: P, mut f: F) -> crate::Result<()>
+    where
+        P: AsRef<Path>,
+        F: FnMut(&Self::DirEntry) -> crate::Result<ControlFlow<()>>,
+        Self: Sized,
+    {
+        let root = root.as_ref();
+        let mut dirs = vec![root.to_path_buf()];
+
+        while let Some(dir) = dirs.pop() {
+            let entries = match self.read_dir(&dir) {
+                Ok(read_dir) => read_dir.collect::<crate::Result<Vec<_>>>()?,
+                // The directory was removed by the callback.
+                Err(err) if err.kind() == ErrorKind::NotFound && dir != root => continue,
+                Err(err) => return Err(err),
+            };
+
+            let mut subdirs = Vec::new();
+            for entry in entries {
+                let is_dir = entry.file_type()?.is_dir();
+                if f(&entry)?.is_break() {
+                    return Ok(());
+                }
+                if is_dir {
+                    subdirs.push(entry.path());
+                }
+            }
+            dirs.extend(subdirs.into_iter().rev());
+        }
+
+        Ok(())
+    }
+
+    /// Lists the names and file types of the entries of the directory at the
+    /// specified path.
+    ///
+    /// Only [`UniDirEntry::file_type`] is used, which is usually cheaper than fetching
+    /// the metadata of each entry.
+    fn list_types<P>(
+        &self,
+        path: P,
+    ) -> crate::Result<Vec<(OsString, <Self::DirEntry as UniDirEntry>::FileType)>>
+    where
+        P: AsRef<Path>,
+        Self: Sized,
+    {
+        self.read_dir(path)?
+            .map(|entry| {
+                let entry = entry?;
+                Ok((entry.file_name(), entry.file_type()?))
+            })
+            .collect()
+    }
+
+    /// Reads at most `max` entries of the directory at the specified path.
+    ///
+    /// Iteration stops as soon as the cap is reached. The returned boolean is `true`
+    /// if the directory contains more entries than were returned.
+    fn read_dir_capped<P>(&self, path: P, max: usize) -> crate::Result<(Vec<Self::DirEntry>, bool)>
+    where
+        P: AsRef<Path>,
+        Self: Sized,
+    {
+        let mut read_dir = self.read_dir(path)?;
+        let entries = read_dir
+            .by_ref()
+            .take(max)
+            .collect::<crate::Result<Vec<_>>>()?;
+        let truncated = read_dir.next().is_some();
+
+        Ok((entries, truncated))
+    }
+
+    /// Returns `Ok(true)` if an entry exists at `path`, without following a symbolic
+    /// link at `path`.
+    ///
+    /// Unlike [`UniFs::exists`], this returns `Ok(true)` for a dangling symbolic link.
+    /// Errors other than [`ErrorKind::NotFound`] are propagated.
+    fn symlink_exists<P>(&self, path: P) -> crate::Result<bool>
+    where
+        P: AsRef<Path>,
+        Self: Sized,
+    {
+        match self.symlink_metadata(path) {
+            Ok(_) => Ok(true),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Copies a file or a directory, dispatching on the type of `from`.
+    ///
+    /// Files are copied with [`UniFs::copy`], directories are copied recursively
+    /// into `to`, creating it if necessary.
+    ///
+    /// On success, the total number of bytes copied is returned.
+    fn copy_any<P, Q>(&self, from: P, to: Q) -> crate::Result<u64>
+    where
+        P: AsRef<Path>,
+        Q: AsRef<Path>,
+        Self: Sized,
+    {
+        let from = from.as_ref();
+        let to = to.as_ref();
+
+        if self.metadata(from)?.is_dir() {
+            self.copy_dir_all(from, to)
+        } else {
+            self.copy(from, to)
+        }
+    }
+
+    /// Copies the file `from` like `cp`, treating `to` as the directory to copy into if
+    /// it ends with a path separator or is an existing directory.
+    ///
+    /// In that case, the file name of `from` is appended to `to` to form the destination.
+    /// Otherwise, `to` is used as-is like with [`UniFs::copy`].
+    ///
+    /// On success, the total number of bytes copied is returned.
+    ///
+    /// # Errors
+    /// - [`ErrorKind::InvalidInput`] if `to` is a directory and `from` has no file name.
+    /// - Any error of [`UniFs::copy`].
+    fn copy_smart<P, Q>(&self, from: P, to: Q) -> crate::Result<u64>
+    where
+        P: AsRef<Path>,
+        Q: AsRef<Path>,
+        Self: Sized,
+    {
+        let from = from.as_ref();
+        let to = smart_destination(self, from, to.as_ref())?;
+        self.copy(from, to)
+    }
+
+    /// Renames the file or directory `from` like `mv`, treating `to` as the directory to
+    /// move into if it ends with a path separator or is an existing directory.
+    ///
+    /// In that case, the file name of `from` is appended to `to` to form the destination.
+    /// Otherwise, `to` is used as-is like with [`UniFs::rename`].
+    ///
+    /// # Errors
+    /// - [`ErrorKind::InvalidInput`] if `to` is a directory and `from` has no file name.
+    /// - Any error of [`UniFs::rename`].
+    fn rename_smart<P, Q>(&self, from: P, to: Q) -> crate::Result<()>
+    where
+        P: AsRef<Path>,
+        Q: AsRef<Path>,
+        Self: Sized,
+    {
+        let from = from.as_ref();
+        let to = smart_destination(self, from, to.as_ref())?;
+        self.rename(from, to)
+    }
+
+    /// Recursively copies the directory `from` and all of its contents to `to`.
+    ///
+    /// The destination directory and all of its missing parents are created, even if
+    /// `from` is empty. Empty subdirectories are recreated at the destination.
+    ///
+    /// If parts of the destination tree exist already, the trees are merged: files of
+    /// `from` overwrite files with the same path in `to`, while entries that only exist
+    /// in `to` are left intact. It is an error if a directory of `from` corresponds to a
+    /// file in `to`.
+    ///
+    /// On success, the total number of bytes copied is returned.
+    fn copy_dir_all<P, Q>(&self, from: P, to: Q) -> crate::Result<u64>
+    where
+        P: AsRef<Path>,
+        Q: AsRef<Path>,
+        Self: Sized,
+    {
+        self.copy_dir_with(from, to, CopyOptions::default())
+    }
+
+    /// Merges the directory `from` and all of its contents into the directory `to`.
+    ///
+    /// This is [`UniFsExt::copy_dir_all`] under a name that states the merge: files of
+    /// `from` overwrite files with the same path in `to`, while entries that only exist
+    /// in `to` are left intact.
+    ///
+    /// On success, the total number of bytes copied is returned.
+    fn copy_dir_merge<P, Q>(&self, from: P, to: Q) -> crate::Result<u64>
+    where
+        P: AsRef<Path>,
+        Q: AsRef<Path>,
+        Self: Sized,
+    {
+        self.copy_dir_all(from, to)
+    }
+
+    /// Recursively copies the directory `from` and all of its contents to the directory
+    /// `to` of the filesystem `target`, which may be a different filesystem than this one.
+    ///
+    /// The destination directory and all of its missing parents are created. If parts
+    /// of the destination tree exist already, the trees are merged: existing files are
+    /// overwritten and all other entries are left intact. Symbolic links are recreated
+    /// with the same target instead of being followed, which fails if `target` does not
+    /// support them.
+    ///
+    /// On success, the total number of bytes copied is returned.
+    ///
+    /// # Errors
+    /// - [`ErrorKind::NotADirectory`] if `from` is not a directory.
+    /// - Any error of the operations used to read `from` and to populate `to`.
+    fn copy_dir_to<T, P, Q>(&self, from: P, target: &T, to: Q) -> crate::Result<u64>
+    where
+        T: UniFs,
+        P: AsRef<Path>,
+        Q: AsRef<Path>,
+        Self: Sized,
+    {
+        let from = self.canonicalize(from)?;
+        if !self.metadata(&from)?.is_dir() {
+            return Err(std::io::Error::new(
+                ErrorKind::NotADirectory,
+                format!("Path '{}' is not a directory", from.display()),
+            ));
+        }
+        let to = to.as_ref();
+        target.create_dir_all(to)?;
+
+        let mut copied = 0;
+        for entry in self.walk_dir(&from) {
+            let entry = entry?;
+            let path = entry.path();
+            let dest = to.join(
+                path.strip_prefix(&from)
+                    .map_err(|_| std::io::Error::other("failed stripping path prefix"))?,
+            );
+
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                target.create_dir_all(&dest)?;
+            } else if file_type.is_symlink() {
+                target.symlink(self.read_link(&path)?, &dest)?;
+            } else {
+                let mut original = self.open_file(&path)?;
+                let mut copy = target.create_file(&dest)?;
+                copied += std::io::copy(&mut original, &mut copy)?;
+            }
+        }
+
+        Ok(copied)
+    }
+
+    /// Recursively copies the directory `from` and all of its contents to `to` like
+    /// [`UniFsExt::copy_dir_all`], preserving the attributes selected by `options`.
+    ///
+    /// The attributes of each entry are applied once its contents have been copied,
+    /// so read-only directories can be copied as well. Times are only preserved for
+    /// files. Preserving owners requires [`UniFs::set_owner`], which usually needs
+    /// elevated privileges on a physical filesystem.
+    ///
+    /// On success, the total number of bytes copied is returned.
+    fn copy_dir_with<P, Q>(&self, from: P, to: Q, options: CopyOptions) -> crate::Result<u64>
+    where
+        P: AsRef<Path>,
+        Q: AsRef<Path>,
+        Self: Sized,
+    {
+        copy_dir_with_options(
+            self,
+            from.as_ref(),
+            to.as_ref(),
+            options,
+            &mut |from, to| self.copy(from, to),
+        )
+    }
+
+    /// Recursively copies the directory `from` to `to` like [`UniFsExt::copy_dir_all`],
+    /// but hard-links files whose contents were already copied instead of copying them
+    /// again.
+    ///
+    /// Files are compared by their SHA-256 digest, see [`UniFsExt::hash_file`]. Files
+    /// are copied instead of linked if the destination already exists or the
+    /// filesystem does not support hard links.
+    ///
+    /// On success, the total number of bytes copied is returned, which does not include
+    /// the linked files.
+    #[cfg(feature = "sha2")]
+    fn copy_dir_dedup<P, Q>(&self, from: P, to: Q) -> crate::Result<u64>
+    where
+        P: AsRef<Path>,
+        Q: AsRef<Path>,
+        Self: Sized,
+    {
+        // The first destination of every digest, which later copies are linked to.
+        let mut copies = std::collections::HashMap::<[u8; 32], PathBuf>::new();
+        let options = CopyOptions::default();
+        copy_dir_with_options(
+            self,
+            from.as_ref(),
+            to.as_ref(),
+            options,
+            &mut |from, to| {
+                let digest = self.hash_file(from)?;
+                if let Some(copy) = copies.get(&digest) {
+                    match self.hard_link(copy, to) {
+                        Ok(()) => return Ok(0),
+                        Err(err)
+                            if matches!(
+                                err.kind(),
+                                ErrorKind::AlreadyExists | ErrorKind::Unsupported
+                            ) => {}
+                        Err(err) => return Err(err),
+                    }
+                }
+
+                let copied = self.copy(from, to)?;
+                copies.entry(digest).or_insert_with(|| to.to_path_buf());
+                Ok(copied)
+            },
+        )
+    }
+
+    /// Creates the directory `dir` and populates it with the contents of the
+    /// directory `template_root` of the `template` filesystem.
+    ///
+    /// If `dir` already exists, nothing is done and `Ok(false)` is returned. Creating
+    /// `dir` itself is the atomic step: if several callers race, only one of them
+    /// populates the directory. Missing parents of `dir` are created.
+    ///
+    /// The template is copied with [`UniFsExt::copy_dir_to`], so symbolic links are
+    /// recreated instead of being followed. If copying the template fails, `dir` is
+    /// removed again before the error is returned.
+    fn init_from_template<T, P, Q>(
+        &self,
+        dir: P,
+        template: &T,
+        template_root: Q,
+    ) -> crate::Result<bool>
+    where
+        T: UniFs,
+        P: AsRef<Path>,
+        Q: AsRef<Path>,
+        Self: Sized,
+    {
+        let dir = dir.as_ref();
+
+        if let Some(parent) = dir.parent() {
+            if !parent.as_os_str().is_empty() {
+                self.create_dir_all(parent)?;
+            }
+        }
+        match self.create_dir(dir) {
+            Ok(()) => {}
+            Err(err) if err.kind() == ErrorKind::AlreadyExists => return Ok(false),
+            Err(err) => return Err(err),
+        }
+
+        match template.copy_dir_to(template_root, self, dir) {
+            Ok(_) => Ok(true),
+            Err(err) => {
+                let _ = self.remove_dir_all(dir);
+                Err(err)
+            }
+        }
+    }
+
+    /// Writes a slice as the entire contents of a new file.
+    ///
+    /// This is the safe counterpart to [`UniFs::write`]: it fails with
+    /// [`ErrorKind::AlreadyExists`] instead of overwriting an existing file.
+    /// Parent directories are not created.
+    fn write_new<P, C>(&self, path: P, contents: C) -> crate::Result<()>
+    where
+        P: AsRef<Path>,
+        C: AsRef<[u8]>,
+        Self: Sized,
+    {
+        let mut file = self
+            .new_openoptions()
+            .write(true)
+            .create_new(true)
+            .open(path)?;
+        file.write_all(contents.as_ref())
+    }
+
+    /// Opens a file for reading and writing, first creating it with `default` as its
+    /// contents if it does not exist.
+    ///
+    /// The returned handle is positioned at the start of the file. Whether the file
+    /// exists is decided by creating it exclusively, so if several callers race, only
+    /// one of them writes the defaults and the contents of an existing file are never
+    /// replaced. On [`crate::MemoryFs`], this decision is made under the write lock
+    /// of the filesystem.
+    fn open_or_init<P, C>(&self, path: P, default: C) -> crate::Result<Self::File>
+    where
+        P: AsRef<Path>,
+        C: AsRef<[u8]>,
+        Self: Sized,
+    {
+        let path = path.as_ref();
+
+        match self
+            .new_openoptions()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(path)
+        {
+            Ok(mut file) => {
+                file.write_all(default.as_ref())?;
+                file.seek(SeekFrom::Start(0))?;
+                Ok(file)
+            }
+            Err(err) if err.kind() == ErrorKind::AlreadyExists => {
+                self.new_openoptions().read(true).write(true).open(path)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Acquires a lock by exclusively creating the file at `path`, which is removed
+    /// again when the returned guard is dropped.
+    ///
+    /// This can be used to make sure that only one process works on a directory at a
+    /// time. A lock file left behind by a crashed process has to be removed manually.
+    ///
+    /// The guard remembers the canonical path of the lock file, so it is removed even
+    /// if the current directory changes while the lock is held.
+    ///
+    /// # Errors
+    /// - [`ErrorKind::AlreadyExists`] if the lock is already held.
+    fn acquire_lockfile<P>(&self, path: P) -> crate::Result<LockFileGuard<'_, Self>>
+    where
+        P: AsRef<Path>,
+        Self: Sized,
+    {
+        let path = path.as_ref();
+        self.create_new_file(path)?;
+        let path = match self.canonicalize(path) {
+            Ok(canonical) => canonical,
+            Err(err) => {
+                let _ = self.remove_file(path);
+                return Err(err);
+            }
+        };
+
+        Ok(LockFileGuard {
+            fs: self,
+            path,
+            held: true,
+        })
+    }
+
+    /// Reads the entire contents of a file into a string, stripping a leading
+    /// UTF-8 byte order mark if present.
+    fn read_to_string_no_bom<P>(&self, path: P) -> crate::Result<String>
+    where
+        P: AsRef<Path>,
+        Self: Sized,
+    {
+        let contents = self.read_to_string(path)?;
+
+        match contents.strip_prefix(UTF8_BOM) {
+            Some(stripped) => Ok(stripped.to_owned()),
+            None => Ok(contents),
+        }
+    }
+
+    /// Writes a string as the entire contents of a file, optionally prefixed with
+    /// a UTF-8 byte order mark.
+    fn write_string<P>(&self, path: P, contents: &str, with_bom: bool) -> crate::Result<()>
+    where
+        P: AsRef<Path>,
+        Self: Sized,
+    {
+        if with_bom {
+            self.write(path, [UTF8_BOM, contents].concat())
+        } else {
+            self.write(path, contents)
+        }
+    }
+
+    /// Returns `Ok(true)` if `ancestor` is an ancestor of `descendant`.
+    ///
+    /// Both paths are canonicalized by this filesystem first, so they must exist on
+    /// backends that require it. Paths are compared by components, so `/a` is not an
+    /// ancestor of `/ab`. Like [`Path::ancestors`], a path is its own ancestor.
+    fn is_ancestor<P, Q>(&self, ancestor: P, descendant: Q) -> crate::Result<bool>
+    where
+        P: AsRef<Path>,
+        Q: AsRef<Path>,
+        Self: Sized,
+    {
+        let ancestor = self.canonicalize(ancestor)?;
+        let descendant = self.canonicalize(descendant)?;
+
+        Ok(descendant.starts_with(ancestor))
+    }
+
+    /// Opens the file at `path` for appending, creating it if necessary, and wraps it
+    /// in a [`std::io::LineWriter`], which flushes the written data after every line.
+    fn line_writer<P>(&self, path: P) -> crate::Result<std::io::LineWriter<Self::File>>
+    where
+        P: AsRef<Path>,
+        Self: Sized,
+    {
+        let file = self
+            .new_openoptions()
+            .append(true)
+            .create(true)
+            .open(path)?;
+        Ok(std::io::LineWriter::new(file))
+    }
+
+    /// Writes `text` as the entire contents of a file, encoded with `encoding`.
+    ///
+    /// No byte order mark is written. Unlike [`encoding_rs::Encoding::encode`], UTF-16
+    /// encodings produce UTF-16 output.
+    ///
+    /// # Errors
+    /// - [`ErrorKind::InvalidData`] if `text` contains characters that cannot be
+    ///   represented in `encoding`.
+    #[cfg(feature = "encoding_rs")]
+    fn write_text_encoded<P>(
+        &self,
+        path: P,
+        text: &str,
+        encoding: &'static encoding_rs::Encoding,
+    ) -> crate::Result<()>
+    where
+        P: AsRef<Path>,
+        Self: Sized,
+    {
+        let bytes = if encoding == encoding_rs::UTF_16LE {
+            text.encode_utf16().flat_map(u16::to_le_bytes).collect()
+        } else if encoding == encoding_rs::UTF_16BE {
+            text.encode_utf16().flat_map(u16::to_be_bytes).collect()
+        } else {
+            let (bytes, _, had_errors) = encoding.encode(text);
+            if had_errors {
+                return Err(std::io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Text cannot be represented in {}", encoding.name()),
+                ));
+            }
+            bytes.into_owned()
+        };
+
+        self.write(path, bytes)
+    }
+
+    /// Reads the entire contents of a file into a string, decoded from `encoding`.
+    ///
+    /// A byte order mark is not interpreted and is kept in the returned string.
+    ///
+    /// # Errors
+    /// - [`ErrorKind::InvalidData`] if the contents are not valid in `encoding`.
+    #[cfg(feature = "encoding_rs")]
+    fn read_text_decoded<P>(
+        &self,
+        path: P,
+        encoding: &'static encoding_rs::Encoding,
+    ) -> crate::Result<String>
+    where
+        P: AsRef<Path>,
+        Self: Sized,
+    {
+        let bytes = self.read(path)?;
+
+        encoding
+            .decode_without_bom_handling_and_without_replacement(&bytes)
+            .map(|text| text.into_owned())
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!("File contents are not valid {}", encoding.name()),
+                )
+            })
+    }
+
+    /// Computes the relative path from the directory containing `from` to `to`.
+    ///
+    /// Both paths are canonicalized by this filesystem first, so the result follows
+    /// the path resolution rules of the backend. The result contains `..` components
+    /// where necessary, and is `.` if `to` is the directory containing `from`.
+    fn relative_path<P, Q>(&self, from: P, to: Q) -> crate::Result<PathBuf>
+    where
+        P: AsRef<Path>,
+        Q: AsRef<Path>,
+        Self: Sized,
+    {
+        let from = self.canonicalize(from)?;
+        let to = self.canonicalize(to)?;
+        let base = from.parent().unwrap_or(&from);
+
+        let mut base_components = base.components().peekable();
+        let mut to_components = to.components().peekable();
+        while let (Some(a), Some(b)) = (base_components.peek(), to_components.peek()) {
+            if a != b {
+                break;
+            }
+            base_components.next();
+            to_components.next();
+        }
+
+        let relative = base_components
+            .map(|_| Component::ParentDir)
+            .chain(to_components)
+            .collect::<PathBuf>();
+
+        if relative.as_os_str().is_empty() {
+            Ok(PathBuf::from("."))
+        } else {
+            Ok(relative)
+        }
+    }
+
+    /// Opens the directory at the specified path as a handle.
+    ///
+    /// All operations on the returned handle resolve paths relative to the directory,
+    /// in the same way as [`AltrootFs`]. Fails if the path does not exist or is not a directory.
+    fn open_dir<P>(&self, path: P) -> crate::Result<DirHandle<'_, Self>>
+    where
+        P: AsRef<Path>,
+        Self: Sized,
+    {
+        AltrootFs::new(self, path.as_ref())
+    }
+
+    /// Builds a manifest of all files below the directory at `root`, for example to
+    /// compare it with a manifest of a remote copy using [`crate::manifest_diff`].
+    ///
+    /// The manifest maps the path of every file relative to `root` to its size,
+    /// modification time and content hash. Directories are not included. `root` is
+    /// canonicalized first, so that the paths of the entries found below it start
+    /// with it.
+    fn manifest<P>(&self, root: P) -> crate::Result<BTreeMap<PathBuf, EntrySummary>>
+    where
+        P: AsRef<Path>,
+        Self: Sized,
+    {
+        let root = self.canonicalize(root)?;
+        let root = root.as_path();
+        let mut manifest = BTreeMap::new();
+
+        for entry in self.walk_dir(root) {
+            let entry = entry?;
+            if !entry.file_type()?.is_file() {
+                continue;
+            }
+
+            let path = entry.path();
+            let metadata = entry.metadata()?;
+            let summary = EntrySummary {
+                len: metadata.len(),
+                modified: metadata.modified().ok(),
+                hash: fnv1a_hash(self.open_file(&path)?)?,
+            };
+            manifest.insert(relative_to_root(&path, root)?, summary);
+        }
+
+        Ok(manifest)
+    }
+
+    /// Reads the tree below the directory at `root` into a list of records, for example
+    /// to export it to external tools.
+    ///
+    /// The records are sorted by their paths, which are relative to `root`, so every
+    /// directory comes before its contents. Files include their contents and symbolic
+    /// links their targets. Other kinds of entries, such as named pipes, are skipped.
+    /// The tree can be rebuilt with [`crate::MemoryFs::from_records`]. Like for
+    /// [`UniFsExt::manifest`], `root` is canonicalized first.
+    fn to_records<P>(&self, root: P) -> crate::Result<Vec<FileRecord>>
+    where
+        P: AsRef<Path>,
+        Self: Sized,
+    {
+        let root = self.canonicalize(root)?;
+        let root = root.as_path();
+        let mut records = Vec::new();
+
+        for entry in self.walk_dir(root) {
+            let entry = entry?;
+            let path = entry.path();
+            let file_type = entry.file_type()?;
+            let (kind, contents, target) = if file_type.is_dir() {
+                (FileType::Directory, None, None)
+            } else if file_type.is_file() {
+                (FileType::File, Some(self.read(&path)?), None)
+            } else if file_type.is_symlink() {
+                (FileType::Symlink, None, Some(self.read_link(&path)?))
+            } else {
+                continue;
+            };
+
+            let metadata = entry.metadata()?;
+            records.push(FileRecord {
+                path: relative_to_root(&path, root)?,
+                kind,
+                len: metadata.len(),
+                modified: metadata.modified().ok(),
+                contents,
+                target,
+            });
+        }
+
+        records.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(records)
+    }
+
+    /// Reads up to `len` bytes of the file at `path`, starting at byte offset `start`.
+    ///
+    /// The returned buffer is shorter than `len` if the range extends past the end
+    /// of the file, and empty if `start` is at or beyond the end of the file.
+    fn read_range<P>(&self, path: P, start: u64, len: u64) -> crate::Result<Vec<u8>>
+    where
+        P: AsRef<Path>,
+        Self: Sized,
+    {
+        let mut file = self.open_file(path)?;
+        file.seek(SeekFrom::Start(start))?;
+
+        let mut buf = Vec::new();
+        file.take(len).read_to_end(&mut buf)?;
+        Ok(buf)
+    }
+
+    /// Reads the entire contents of a file and computes its SHA-256 digest in the
+    /// same pass.
+    #[cfg(feature = "sha2")]
+    fn read_and_hash<P>(&self, path: P) -> crate::Result<(Vec<u8>, [u8; 32])>
+    where
+        P: AsRef<Path>,
+        Self: Sized,
+    {
+        use sha2::Digest as _;
+
+        let mut file = self.open_file(path)?;
+        let mut hasher = sha2::Sha256::new();
+        let mut contents = Vec::new();
+        let mut buf = [0; 8192];
+        loop {
+            let read = match file.read(&mut buf) {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            };
+            hasher.update(&buf[..read]);
+            contents.extend_from_slice(&buf[..read]);
+        }
+
+        Ok((contents, hasher.finalize().into()))
+    }
+
+    /// Computes the SHA-256 digest of a file without keeping its contents in memory.
+    #[cfg(feature = "sha2")]
+    fn hash_file<P>(&self, path: P) -> crate::Result<[u8; 32]>
+    where
+        P: AsRef<Path>,
+        Self: Sized,
+    {
+        use sha2::Digest as _;
+
+        let mut hasher = sha2::Sha256::new();
+        std::io::copy(&mut self.open_file(path)?, &mut hasher)?;
+
+        Ok(hasher.finalize().into())
+    }
+
+    /// Guesses the MIME type of the file at `path`.
+    ///
+    /// The type is guessed from the extension of the path first. If the extension is
+    /// missing or unknown, the first bytes of the file are compared against the
+    /// signatures of common binary formats. `application/octet-stream` is returned if
+    /// neither identifies the type.
+    ///
+    /// # Errors
+    /// - if the file has to be sniffed and cannot be read.
+    #[cfg(feature = "mime_guess")]
+    fn guess_mime<P>(&self, path: P) -> crate::Result<&'static str>
+    where
+        P: AsRef<Path>,
+        Self: Sized,
+    {
+        let path = path.as_ref();
+        if let Some(mime) = mime_guess::from_path(path).first_raw() {
+            return Ok(mime);
+        }
+
+        let header = self.read_range(path, 0, MIME_SNIFF_LEN)?;
+        Ok(MIME_SIGNATURES
+            .iter()
+            .find(|(offset, signature, _)| {
+                header
+                    .get(*offset..)
+                    .is_some_and(|header| header.starts_with(signature))
+            })
+            .map_or("application/octet-stream", |(_, _, mime)| mime))
+    }
+
+    /// Runs `f` and afterwards restores the directory at `root` to its state from
+    /// before `f` was run, for example to undo the changes of a test to a real directory.
+    ///
+    /// The contents of `root` are copied into a [`crate::MemoryFs`] first. Afterwards,
+    /// entries created by `f` are removed, removed entries are recreated and files with
+    /// changed contents are reverted. Files are only rewritten if their contents
+    /// changed and symbolic links only if their target changed. Permissions and
+    /// timestamps are not restored.
+    ///
+    /// The directory is not restored if `f` panics.
+    #[cfg(feature = "memory_fs")]
+    fn with_snapshot<P, R, F>(&self, root: P, f: F) -> crate::Result<R>
+    where
+        P: AsRef<Path>,
+        F: FnOnce(&Self) -> R,
+        Self: Sized,
+    {
+        let root = root.as_ref();
+        let snapshot = crate::MemoryFs::new();
+        self.copy_dir_to(root, &snapshot, "/")?;
+
+        let res = f(self);
+        restore_tree(self, root, &snapshot, Path::new("/"))?;
+
+        Ok(res)
+    }
+
+    /// Returns a [`std::fs::File`] with the contents of the file at `path`, for
+    /// interoperability with APIs that require a real file.
+    ///
+    /// If the path is backed by the physical filesystem (see [`UniFs::physical_path`]),
+    /// the real file is opened in read-only mode. Otherwise, the contents are copied
+    /// into an anonymous temporary file of the operating system, which is removed once
+    /// the returned handle is closed. Changes made through the handle are not
+    /// written back to this filesystem.
+    ///
+    /// In both cases, the returned handle is positioned at the start of the file.
+    #[cfg(feature = "fs_access")]
+    fn spill_to_temp<P>(&self, path: P) -> crate::Result<std::fs::File>
+    where
+        P: AsRef<Path>,
+        Self: Sized,
+    {
+        let path = path.as_ref();
+        if let Some(physical_path) = self.physical_path(path) {
+            return std::fs::File::open(physical_path);
+        }
+
+        let mut file = self.open_file(path)?;
+        let mut temp_file = tempfile::tempfile()?;
+        std::io::copy(&mut file, &mut temp_file)?;
+        temp_file.seek(SeekFrom::Start(0))?;
+
+        Ok(temp_file)
+    }
 }
 
 impl<T: UniFs> UniFsExt for T {}
 
+/// The UTF-8 encoded byte order mark.
+const UTF8_BOM: &str = "\u{feff}";
+
+/// The number of bytes read by [`UniFsExt::guess_mime`] to sniff the type of a file.
+#[cfg(feature = "mime_guess")]
+const MIME_SNIFF_LEN: u64 = 16;
+
+/// The offset and signature of common binary formats, with their MIME type.
+#[cfg(feature = "mime_guess")]
+const MIME_SIGNATURES: &[(usize, &[u8], &str)] = &[
+    (0, b"\x89PNG\r\n\x1a\n", "image/png"),
+    (0, b"\xff\xd8\xff", "image/jpeg"),
+    (0, b"GIF87a", "image/gif"),
+    (0, b"GIF89a", "image/gif"),
+    (8, b"WEBP", "image/webp"),
+    (0, b"%PDF-", "application/pdf"),
+    (0, b"PK\x03\x04", "application/zip"),
+    (0, b"\x1f\x8b", "application/gzip"),
+    (0, b"\x7fELF", "application/x-executable"),
+    (0, b"\0asm", "application/wasm"),
+];
+
+/// Computes the 64-bit FNV-1a hash of everything read from `reader`.
+fn fnv1a_hash<R: std::io::Read>(mut reader: R) -> crate::Result<u64> {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+
+    let mut hash = OFFSET_BASIS;
+    let mut buf = [0; 8192];
+    loop {
+        let read = match reader.read(&mut buf) {
+            Ok(0) => return Ok(hash),
+            Ok(read) => read,
+            Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        };
+        for byte in &buf[..read] {
+            hash = (hash ^ u64::from(*byte)).wrapping_mul(PRIME);
+        }
+    }
+}
+
+/// Returns `path`, found by walking the directory `root`, relative to `root`.
+fn relative_to_root(path: &Path, root: &Path) -> crate::Result<PathBuf> {
+    path.strip_prefix(root).map(Path::to_path_buf).map_err(|_| {
+        std::io::Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "Path '{}' is not below '{}'",
+                path.display(),
+                root.display()
+            ),
+        )
+    })
+}
+
+/// Returns the destination of [`UniFsExt::copy_smart`] and [`UniFsExt::rename_smart`].
+fn smart_destination<F: UniFs>(fs: &F, from: &Path, to: &Path) -> crate::Result<PathBuf> {
+    let trailing_separator = to
+        .as_os_str()
+        .as_encoded_bytes()
+        .last()
+        .is_some_and(|&byte| std::path::is_separator(byte.into()));
+    if !trailing_separator && !fs.metadata(to).is_ok_and(|metadata| metadata.is_dir()) {
+        return Ok(to.to_path_buf());
+    }
+
+    let name = from.file_name().ok_or_else(|| {
+        std::io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("Path '{}' has no file name", from.display()),
+        )
+    })?;
+    Ok(to.join(name))
+}
+
+/// Copies the directory `from` to `to`, copying every file with `copy_file` and
+/// applying the attributes selected by `options` afterwards.
+fn copy_dir_with_options<F, C>(
+    fs: &F,
+    from: &Path,
+    to: &Path,
+    options: CopyOptions,
+    copy_file: &mut C,
+) -> crate::Result<u64>
+where
+    F: UniFs,
+    C: FnMut(&Path, &Path) -> crate::Result<u64>,
+{
+    fs.create_dir_all(to)?;
+
+    let mut copied = 0;
+    for entry in fs.read_dir(from)? {
+        let entry = entry?;
+        let file_name = entry.file_name();
+        let from = from.join(&file_name);
+        let to = to.join(&file_name);
+
+        if entry.file_type()?.is_dir() {
+            copied += copy_dir_with_options(fs, &from, &to, options, copy_file)?;
+        } else {
+            copied += copy_file(&from, &to)?;
+            copy_attributes(fs, &from, &to, options)?;
+        }
+    }
+
+    copy_attributes(fs, from, to, options)?;
+    Ok(copied)
+}
+
+/// Applies the attributes of `from` selected by `options` to `to`.
+fn copy_attributes<F: UniFs>(
+    fs: &F,
+    from: &Path,
+    to: &Path,
+    options: CopyOptions,
+) -> crate::Result<()> {
+    if options == CopyOptions::default() {
+        return Ok(());
+    }
+    let metadata = fs.metadata(from)?;
+
+    if options.preserve_owner && (metadata.uid().is_some() || metadata.gid().is_some()) {
+        fs.set_owner(to, metadata.uid(), metadata.gid())?;
+    }
+    if options.preserve_permissions {
+        fs.set_permissions(to, metadata.permissions())?;
+    }
+    // Times come last, as changing the permissions may update the modification time.
+    if options.preserve_times && metadata.is_file() {
+        let mut times = <F::File as UniFile>::FileTimes::default();
+        if let Ok(modified) = metadata.modified() {
+            times = times.set_modified(modified);
+        }
+        if let Ok(accessed) = metadata.accessed() {
+            times = times.set_accessed(accessed);
+        }
+        fs.open_file(to)?.set_times(times)?;
+    }
+
+    Ok(())
+}
+
+/// Restores the directory `dir` of `fs` to the contents of the directory `snapshot_dir`
+/// of `snapshot`.
+#[cfg(feature = "memory_fs")]
+fn restore_tree<F: UniFs>(
+    fs: &F,
+    dir: &Path,
+    snapshot: &crate::MemoryFs,
+    snapshot_dir: &Path,
+) -> crate::Result<()> {
+    // Remove entries that were created, or whose type changed.
+    for entry in fs.read_dir(dir)?.collect::<crate::Result<Vec<_>>>()? {
+        let file_name = entry.file_name();
+        let path = dir.join(&file_name);
+        let file_type = entry.file_type()?;
+        let kind = (file_type.is_dir(), file_type.is_symlink());
+
+        match snapshot.symlink_metadata(snapshot_dir.join(&file_name)) {
+            Ok(metadata) if (metadata.is_dir(), metadata.is_symlink()) == kind => {}
+            _ if file_type.is_dir() => fs.remove_dir_all(&path)?,
+            _ => fs.remove_file(&path)?,
+        }
+    }
+
+    // Recreate removed entries and revert changed files.
+    for entry in snapshot.read_dir(snapshot_dir)? {
+        let entry = entry?;
+        let file_name = entry.file_name();
+        let path = dir.join(&file_name);
+        let snapshot_path = snapshot_dir.join(&file_name);
+
+        if entry.file_type()?.is_dir() {
+            if !fs.exists(&path)? {
+                fs.create_dir(&path)?;
+            }
+            restore_tree(fs, &path, snapshot, &snapshot_path)?;
+        } else if entry.file_type()?.is_symlink() {
+            let target = snapshot.read_link(&snapshot_path)?;
+            match fs.read_link(&path) {
+                Ok(current) if current == target => {}
+                Ok(_) => {
+                    fs.remove_file(&path)?;
+                    fs.symlink(target, &path)?;
+                }
+                Err(_) => fs.symlink(target, &path)?,
+            }
+        } else {
+            let contents = snapshot.read(&snapshot_path)?;
+            if !fs.exists(&path)? || fs.read(&path)? != contents {
+                fs.write(&path, contents)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
 struct WalkDirIterator<'a, F: UniFs> {
     fs: &'a F,
     stack: Vec<F::DirEntry>,
@@ -96,3 +1185,70 @@ where
         }
     }
 }
+
+struct SortedWalkDirIterator<'a, F: UniFs, C> {
+    fs: &'a F,
+    cmp: C,
+    stack: Vec<F::DirEntry>,
+    error: Option<std::io::Error>,
+}
+
+impl<'a, F, C> SortedWalkDirIterator<'a, F, C>
+where
+    F: UniFs,
+    C: FnMut(&F::DirEntry, &F::DirEntry) -> Ordering,
+{
+    fn new(fs: &'a F, path: &Path, cmp: C) -> Self {
+        let mut iter = Self {
+            fs,
+            cmp,
+            stack: Vec::new(),
+            error: None,
+        };
+        if let Err(err) = iter.push_children(path) {
+            iter.error = Some(err);
+        }
+        iter
+    }
+
+    /// Pushes the children of `path` onto the stack so that they are popped in `cmp` order.
+    fn push_children(&mut self, path: &Path) -> crate::Result<()> {
+        let mut children = self.fs.read_dir(path)?.collect::<crate::Result<Vec<_>>>()?;
+        children.sort_by(|a, b| (self.cmp)(b, a));
+        self.stack.extend(children);
+
+        Ok(())
+    }
+}
+
+impl<'a, F, C> Iterator for SortedWalkDirIterator<'a, F, C>
+where
+    F: UniFs,
+    C: FnMut(&F::DirEntry, &F::DirEntry) -> Ordering,
+{
+    type Item = crate::Result<F::DirEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(err) = self.error.take() {
+            self.stack.clear();
+            return Some(Err(err));
+        }
+
+        let entry = self.stack.pop()?;
+        let res = entry.file_type().and_then(|file_type| {
+            if file_type.is_dir() {
+                self.push_children(&entry.path())
+            } else {
+                Ok(())
+            }
+        });
+
+        match res {
+            Ok(()) => Some(Ok(entry)),
+            Err(err) => {
+                self.stack.clear();
+                Some(Err(err))
+            }
+        }
+    }
+}
@@ -3,6 +3,7 @@ use std::{ffi::OsString, path::PathBuf, time::SystemTime};
 use crate::Result;
 
 pub(crate) mod dir_builder;
+pub(crate) mod dyn_file_system;
 pub(crate) mod file;
 pub(crate) mod file_system;
 pub(crate) mod file_system_ext;
@@ -69,6 +70,33 @@ pub trait UniMetadata {
     ///
     /// This function mirrors the [`std::fs::Metadata::created`] function.
     fn created(&self) -> Result<std::time::SystemTime>;
+
+    /// Returns the number of hard links to the file this metadata is for, if the
+    /// filesystem records it.
+    ///
+    /// This function mirrors the [`std::os::unix::fs::MetadataExt::nlink`] function.
+    /// The default implementation returns `None`.
+    fn nlink(&self) -> Option<u64> {
+        None
+    }
+
+    /// Returns the user ID of the owner of the file this metadata is for, if the
+    /// filesystem records one.
+    ///
+    /// This function mirrors the [`std::os::unix::fs::MetadataExt::uid`] function.
+    /// The default implementation returns `None`.
+    fn uid(&self) -> Option<u32> {
+        None
+    }
+
+    /// Returns the group ID of the owner of the file this metadata is for, if the
+    /// filesystem records one.
+    ///
+    /// This function mirrors the [`std::os::unix::fs::MetadataExt::gid`] function.
+    /// The default implementation returns `None`.
+    fn gid(&self) -> Option<u32> {
+        None
+    }
 }
 
 /// A trait that represents permissions for a file or directory.
@@ -63,6 +63,17 @@ pub trait UniOpenOptions {
     /// This function mirrors the [`std::fs::OpenOptions::create_new`] function.
     fn create_new(&mut self, create_new: bool) -> &mut Self;
 
+    /// Sets the option to bypass the caches of the operating system, if supported.
+    ///
+    /// This is a hint for large sequential I/O, which filesystems without such caches
+    /// ignore. Direct I/O usually requires buffers, offsets and lengths to be aligned
+    /// to the block size of the underlying storage; other accesses may fail.
+    ///
+    /// The default implementation does nothing.
+    fn direct(&mut self, _direct: bool) -> &mut Self {
+        self
+    }
+
     /// Opens a file at `path` with the options specified by `self`.
     ///
     /// This function mirrors the [`std::fs::OpenOptions::open`] function.
@@ -0,0 +1,94 @@
+//! This module provides the construction of filesystems from URIs.
+
+use std::io::ErrorKind;
+
+use crate::{DynUniFs, Result};
+
+/// Creates the filesystem described by `uri`, for example to select the backend
+/// in a configuration file.
+///
+/// The following URIs are supported:
+///
+/// - `fs://mem`: an empty [`crate::MemoryFs`] (requires the `memory_fs` feature).
+/// - `fs:///abs/path`: the directory `/abs/path` of the [`crate::PhysicalFs`], used as
+///   the root through an [`crate::AltrootFs`] (requires the `fs_access` feature).
+/// - `zip://archive.zip`: a [`crate::ZipFs`] reading the zip archive at `archive.zip`
+///   on the real disk (requires the `zip` feature).
+///
+/// # Errors
+/// - [`ErrorKind::InvalidInput`] if the scheme is unknown or the URI is malformed.
+/// - [`ErrorKind::Unsupported`] if the backend is disabled by a feature.
+/// - Any error of constructing the backend, for example if the directory or archive
+///   does not exist.
+///
+/// # Example
+///
+/// ```
+/// # fn main() -> unifs::Result<()> {
+/// let fs = unifs::from_uri("fs://mem")?;
+/// fs.write("/file.txt", "Hello, World!")?;
+/// assert_eq!(fs.read_to_string("/file.txt")?, "Hello, World!");
+/// # Ok(())
+/// # }
+/// ```
+pub fn from_uri(uri: &str) -> Result<Box<dyn DynUniFs>> {
+    let (scheme, rest) = uri.split_once("://").ok_or_else(|| {
+        std::io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("Missing scheme in filesystem URI '{uri}'"),
+        )
+    })?;
+
+    match scheme {
+        "fs" if rest == "mem" => memory_fs(),
+        "fs" if rest.starts_with('/') => physical_fs(rest),
+        "zip" if !rest.is_empty() => zip_fs(rest),
+        "fs" | "zip" => Err(std::io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("Invalid filesystem URI '{uri}'"),
+        )),
+        _ => Err(std::io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("Unknown scheme '{scheme}' in filesystem URI '{uri}'"),
+        )),
+    }
+}
+
+#[cfg(not(all(feature = "memory_fs", feature = "fs_access", feature = "zip")))]
+fn unsupported(feature: &str) -> std::io::Error {
+    std::io::Error::new(
+        ErrorKind::Unsupported,
+        format!("Filesystem URI requires the `{feature}` feature"),
+    )
+}
+
+#[cfg(feature = "memory_fs")]
+fn memory_fs() -> Result<Box<dyn DynUniFs>> {
+    Ok(Box::new(crate::MemoryFs::new()))
+}
+
+#[cfg(not(feature = "memory_fs"))]
+fn memory_fs() -> Result<Box<dyn DynUniFs>> {
+    Err(unsupported("memory_fs"))
+}
+
+#[cfg(feature = "fs_access")]
+fn physical_fs(root: &str) -> Result<Box<dyn DynUniFs>> {
+    Ok(Box::new(crate::AltrootFs::new(crate::PhysicalFs, root)?))
+}
+
+#[cfg(not(feature = "fs_access"))]
+fn physical_fs(_root: &str) -> Result<Box<dyn DynUniFs>> {
+    Err(unsupported("fs_access"))
+}
+
+#[cfg(feature = "zip")]
+fn zip_fs(path: &str) -> Result<Box<dyn DynUniFs>> {
+    let file = std::fs::File::open(path)?;
+    Ok(Box::new(crate::ZipFs::new(std::io::BufReader::new(file))?))
+}
+
+#[cfg(not(feature = "zip"))]
+fn zip_fs(_path: &str) -> Result<Box<dyn DynUniFs>> {
+    Err(unsupported("zip"))
+}
@@ -0,0 +1,621 @@
+//! This module provides a read-only filesystem backed by a zip archive.
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    ffi::OsString,
+    io::{Cursor, ErrorKind, Read, Seek, Write},
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
+
+use zip::ZipArchive;
+
+use crate::{
+    rw_lock::RwLock, FileTimes, FileType, Permissions, Result, SpecialFileKind, UniDirBuilder,
+    UniDirEntry, UniFile, UniFileType as _, UniFs, UniMetadata, UniOpenOptions, WriteReport,
+};
+
+/// A read-only filesystem that reads files directly from a zip archive.
+///
+/// Only the central directory is read when the filesystem is created. The contents
+/// of a file are decompressed when it is read or opened, so large archives can be
+/// accessed without extracting them. Directories that are not stored in the archive
+/// but contain stored entries are listed as well.
+///
+/// Every operation that would modify the filesystem fails with
+/// [`ErrorKind::ReadOnlyFilesystem`]. Use it as the fallback of a [`crate::FallbackFs`]
+/// to overlay changes on top of the archive.
+///
+/// # Example
+///
+/// ```
+/// use unifs::{MemoryFs, UniFs as _, ZipFs};
+///
+/// # fn main() -> unifs::Result<()> {
+/// let memory_fs = MemoryFs::default();
+/// memory_fs.write("/data.txt", "Hello, World!")?;
+///
+/// let fs = ZipFs::new(std::io::Cursor::new(memory_fs.zip()?))?;
+/// assert_eq!(fs.read_to_string("/data.txt")?, "Hello, World!");
+/// # Ok(())
+/// # }
+/// ```
+pub struct ZipFs<R: Read + Seek> {
+    inner: Arc<ZipFsInner<R>>,
+}
+
+struct ZipFsInner<R: Read + Seek> {
+    archive: RwLock<ZipArchive<R>>,
+    entries: HashMap<PathBuf, ZipEntry>,
+}
+
+enum ZipEntry {
+    File { index: usize, len: u64 },
+    Directory(BTreeSet<OsString>),
+}
+
+/// Represents the metadata of an entry in a zip filesystem.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZipMetadata {
+    file_type: FileType,
+    len: u64,
+}
+
+/// Represents a directory entry in a zip filesystem.
+pub struct ZipDirEntry {
+    path: PathBuf,
+    file_name: OsString,
+    metadata: ZipMetadata,
+}
+
+/// Represents a directory iterator for a zip filesystem.
+pub struct ZipReadDir {
+    entries: std::vec::IntoIter<ZipDirEntry>,
+}
+
+/// Represents a file opened from a zip filesystem.
+///
+/// The decompressed contents are held in memory and cannot be modified.
+#[derive(Debug, Clone)]
+pub struct ZipFile {
+    data: Cursor<Vec<u8>>,
+    metadata: ZipMetadata,
+}
+
+/// Represents options for opening files in a zip filesystem.
+pub struct ZipOpenOptions<R: Read + Seek> {
+    fs: ZipFs<R>,
+    mutating: bool,
+}
+
+/// Represents a directory builder for a zip filesystem, which always fails.
+pub struct ZipDirBuilder;
+
+fn error(msg: &str) -> std::io::Error {
+    std::io::Error::new(ErrorKind::ReadOnlyFilesystem, msg)
+}
+
+fn zip_error(err: zip::result::ZipError) -> std::io::Error {
+    std::io::Error::new(
+        ErrorKind::InvalidData,
+        format!("Failed to read zip archive: {}", err),
+    )
+}
+
+/// Resolves `path` against the archive root without accessing the archive.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::from("/");
+    for component in path.components() {
+        match component {
+            Component::Normal(name) => normalized.push(name),
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            Component::CurDir | Component::RootDir | Component::Prefix(_) => {}
+        }
+    }
+    normalized
+}
+
+impl<R: Read + Seek> ZipFs<R> {
+    /// Creates a new `ZipFs` reading the zip archive from `reader`.
+    ///
+    /// Entries with paths escaping the archive root are rejected.
+    ///
+    /// # Errors
+    /// - [`ErrorKind::InvalidData`] if the archive cannot be read.
+    pub fn new(reader: R) -> Result<Self> {
+        let mut archive = ZipArchive::new(reader).map_err(zip_error)?;
+        let mut entries = HashMap::new();
+        entries.insert(PathBuf::from("/"), ZipEntry::Directory(BTreeSet::new()));
+
+        for index in 0..archive.len() {
+            let file = archive.by_index_raw(index).map_err(zip_error)?;
+            let path = file
+                .enclosed_name()
+                .map(|name| normalize(&name))
+                .ok_or_else(|| {
+                    std::io::Error::new(
+                        ErrorKind::InvalidData,
+                        format!("Invalid path in zip archive: {}", file.name()),
+                    )
+                })?;
+
+            let entry = if file.is_dir() {
+                ZipEntry::Directory(BTreeSet::new())
+            } else {
+                ZipEntry::File {
+                    index,
+                    len: file.size(),
+                }
+            };
+            insert_entry(&mut entries, path, entry)?;
+        }
+
+        Ok(Self {
+            inner: Arc::new(ZipFsInner {
+                archive: RwLock::new(archive),
+                entries,
+            }),
+        })
+    }
+
+    fn entry(&self, path: &Path) -> Result<(PathBuf, &ZipEntry)> {
+        let path = normalize(path);
+        match self.inner.entries.get(&path) {
+            Some(entry) => Ok((path, entry)),
+            None => Err(std::io::Error::new(
+                ErrorKind::NotFound,
+                format!("Path '{}' does not exist", path.display()),
+            )),
+        }
+    }
+
+    fn open_read(&self, path: &Path) -> Result<ZipFile> {
+        let (path, entry) = self.entry(path)?;
+        match entry {
+            ZipEntry::File { index, len } => {
+                let mut archive = self.inner.archive.write();
+                let file = archive.by_index(*index).map_err(zip_error)?;
+                // The declared size comes from the archive, so it is not trusted for
+                // allocating the buffer. Reading one byte more tells if it is exceeded.
+                let mut data = Vec::new();
+                file.take(len.saturating_add(1)).read_to_end(&mut data)?;
+                if data.len() as u64 != *len {
+                    return Err(std::io::Error::new(
+                        ErrorKind::InvalidData,
+                        format!(
+                            "File '{}' does not match its size in the archive",
+                            path.display()
+                        ),
+                    ));
+                }
+
+                Ok(ZipFile {
+                    data: Cursor::new(data),
+                    metadata: ZipMetadata {
+                        file_type: FileType::File,
+                        len: *len,
+                    },
+                })
+            }
+            ZipEntry::Directory(_) => Err(std::io::Error::new(
+                ErrorKind::IsADirectory,
+                format!("Path '{}' is a directory", path.display()),
+            )),
+        }
+    }
+}
+
+/// Inserts `entry` at `path`, creating missing parent directories.
+fn insert_entry(
+    entries: &mut HashMap<PathBuf, ZipEntry>,
+    path: PathBuf,
+    entry: ZipEntry,
+) -> Result<()> {
+    let mut child = path.as_path();
+    while let (Some(parent), Some(name)) = (child.parent(), child.file_name()) {
+        match entries
+            .entry(parent.to_path_buf())
+            .or_insert_with(|| ZipEntry::Directory(BTreeSet::new()))
+        {
+            ZipEntry::Directory(children) => {
+                children.insert(name.to_os_string());
+            }
+            ZipEntry::File { .. } => {
+                return Err(std::io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "Invalid path in zip archive: '{}' is not a directory",
+                        parent.display()
+                    ),
+                ))
+            }
+        }
+        child = parent;
+    }
+
+    match (entries.get(&path), &entry) {
+        // Explicit directory entries may follow entries below them.
+        (Some(ZipEntry::Directory(_)), ZipEntry::Directory(_)) => {}
+        (Some(_), _) => {
+            return Err(std::io::Error::new(
+                ErrorKind::InvalidData,
+                format!("Duplicate path in zip archive: '{}'", path.display()),
+            ))
+        }
+        (None, _) => {
+            entries.insert(path, entry);
+        }
+    }
+
+    Ok(())
+}
+
+impl ZipEntry {
+    fn metadata(&self) -> ZipMetadata {
+        match self {
+            ZipEntry::File { len, .. } => ZipMetadata {
+                file_type: FileType::File,
+                len: *len,
+            },
+            ZipEntry::Directory(_) => ZipMetadata {
+                file_type: FileType::Directory,
+                len: 0,
+            },
+        }
+    }
+}
+
+impl<R: Read + Seek> Clone for ZipFs<R> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<R: Read + Seek> UniFs for ZipFs<R> {
+    type Metadata = ZipMetadata;
+    type ReadDir = ZipReadDir;
+    type DirEntry = ZipDirEntry;
+    type Permissions = Permissions;
+    type File = ZipFile;
+    type OpenOptions = ZipOpenOptions<R>;
+    type DirBuilder = ZipDirBuilder;
+
+    fn canonicalize<P: AsRef<Path>>(&self, path: P) -> Result<PathBuf> {
+        self.entry(path.as_ref()).map(|(path, _)| path)
+    }
+
+    fn copy<P: AsRef<Path>, Q: AsRef<Path>>(&self, _from: P, _to: Q) -> Result<u64> {
+        Err(error("Cannot copy files in a zip filesystem"))
+    }
+
+    fn create_dir<P: AsRef<Path>>(&self, _path: P) -> Result<()> {
+        Err(error("Cannot create directories in a zip filesystem"))
+    }
+
+    fn exists<P: AsRef<Path>>(&self, path: P) -> Result<bool> {
+        Ok(self.inner.entries.contains_key(&normalize(path.as_ref())))
+    }
+
+    fn hard_link<P: AsRef<Path>, Q: AsRef<Path>>(&self, _original: P, _link: Q) -> Result<()> {
+        Err(error("Cannot create hard links in a zip filesystem"))
+    }
+
+    fn metadata<P: AsRef<Path>>(&self, path: P) -> Result<Self::Metadata> {
+        self.entry(path.as_ref()).map(|(_, entry)| entry.metadata())
+    }
+
+    fn read<P: AsRef<Path>>(&self, path: P) -> Result<Vec<u8>> {
+        self.open_read(path.as_ref())
+            .map(|file| file.data.into_inner())
+    }
+
+    fn read_dir<P: AsRef<Path>>(&self, path: P) -> Result<Self::ReadDir> {
+        let (path, entry) = self.entry(path.as_ref())?;
+        match entry {
+            ZipEntry::Directory(children) => {
+                let entries = children
+                    .iter()
+                    .map(|file_name| {
+                        let path = path.join(file_name);
+                        let metadata = self.inner.entries[&path].metadata();
+                        ZipDirEntry {
+                            path,
+                            file_name: file_name.clone(),
+                            metadata,
+                        }
+                    })
+                    .collect::<Vec<_>>();
+
+                Ok(ZipReadDir {
+                    entries: entries.into_iter(),
+                })
+            }
+            ZipEntry::File { .. } => Err(std::io::Error::new(
+                ErrorKind::NotADirectory,
+                format!("Path '{}' is not a directory", path.display()),
+            )),
+        }
+    }
+
+    fn read_link<P: AsRef<Path>>(&self, path: P) -> Result<PathBuf> {
+        let (path, _) = self.entry(path.as_ref())?;
+        Err(std::io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("Path '{}' is not a symbolic link", path.display()),
+        ))
+    }
+
+    fn read_to_string<P: AsRef<Path>>(&self, path: P) -> Result<String> {
+        String::from_utf8(self.read(path)?).map_err(|err| {
+            std::io::Error::new(
+                ErrorKind::InvalidData,
+                format!("Failed to convert bytes to string: {}", err),
+            )
+        })
+    }
+
+    fn remove_dir<P: AsRef<Path>>(&self, _path: P) -> Result<()> {
+        Err(error("Cannot remove directories in a zip filesystem"))
+    }
+
+    fn remove_dir_all<P: AsRef<Path>>(&self, _path: P) -> Result<()> {
+        Err(error("Cannot remove directories in a zip filesystem"))
+    }
+
+    fn remove_file<P: AsRef<Path>>(&self, _path: P) -> Result<()> {
+        Err(error("Cannot remove files in a zip filesystem"))
+    }
+
+    fn rename<P: AsRef<Path>, Q: AsRef<Path>>(&self, _from: P, _to: Q) -> Result<()> {
+        Err(error("Cannot rename files in a zip filesystem"))
+    }
+
+    fn set_permissions<P: AsRef<Path>>(&self, _path: P, _perm: Self::Permissions) -> Result<()> {
+        Err(error("Cannot set permissions in a zip filesystem"))
+    }
+
+    fn symlink_metadata<P: AsRef<Path>>(&self, path: P) -> Result<Self::Metadata> {
+        self.metadata(path)
+    }
+
+    fn write<P: AsRef<Path>, C: AsRef<[u8]>>(&self, _path: P, _contents: C) -> Result<()> {
+        Err(error("Cannot write files in a zip filesystem"))
+    }
+
+    fn write_reporting<P: AsRef<Path>, C: AsRef<[u8]>>(
+        &self,
+        _path: P,
+        _contents: C,
+    ) -> Result<WriteReport> {
+        Err(error("Cannot write files in a zip filesystem"))
+    }
+
+    fn create_special<P: AsRef<Path>>(&self, _path: P, _kind: SpecialFileKind) -> Result<()> {
+        Err(error("Cannot create special files in a zip filesystem"))
+    }
+
+    fn set_owner<P: AsRef<Path>>(
+        &self,
+        _path: P,
+        _uid: Option<u32>,
+        _gid: Option<u32>,
+    ) -> Result<()> {
+        Err(error("Cannot change the owner in a zip filesystem"))
+    }
+
+    fn symlink<P: AsRef<Path>, Q: AsRef<Path>>(&self, _original: P, _link: Q) -> Result<()> {
+        Err(error("Cannot create symbolic links in a zip filesystem"))
+    }
+
+    fn open_file<P: AsRef<Path>>(&self, path: P) -> Result<Self::File> {
+        self.open_read(path.as_ref())
+    }
+
+    fn new_openoptions(&self) -> Self::OpenOptions {
+        ZipOpenOptions {
+            fs: self.clone(),
+            mutating: false,
+        }
+    }
+
+    fn new_dirbuilder(&self) -> Self::DirBuilder {
+        ZipDirBuilder
+    }
+}
+
+impl UniMetadata for ZipMetadata {
+    type Permissions = Permissions;
+    type FileType = FileType;
+
+    fn file_type(&self) -> Self::FileType {
+        self.file_type
+    }
+
+    fn is_dir(&self) -> bool {
+        self.file_type.is_dir()
+    }
+
+    fn is_file(&self) -> bool {
+        self.file_type.is_file()
+    }
+
+    fn is_symlink(&self) -> bool {
+        self.file_type.is_symlink()
+    }
+
+    fn len(&self) -> u64 {
+        self.len
+    }
+
+    fn permissions(&self) -> Self::Permissions {
+        Permissions { readonly: true }
+    }
+
+    fn modified(&self) -> Result<SystemTime> {
+        Err(std::io::Error::new(
+            ErrorKind::Unsupported,
+            "Zip filesystems do not support timestamps",
+        ))
+    }
+
+    fn accessed(&self) -> Result<SystemTime> {
+        Err(std::io::Error::new(
+            ErrorKind::Unsupported,
+            "Zip filesystems do not support timestamps",
+        ))
+    }
+
+    fn created(&self) -> Result<SystemTime> {
+        Err(std::io::Error::new(
+            ErrorKind::Unsupported,
+            "Zip filesystems do not support timestamps",
+        ))
+    }
+}
+
+impl UniDirEntry for ZipDirEntry {
+    type Metadata = ZipMetadata;
+    type FileType = FileType;
+
+    fn path(&self) -> PathBuf {
+        self.path.clone()
+    }
+
+    fn metadata(&self) -> Result<Self::Metadata> {
+        Ok(self.metadata.clone())
+    }
+
+    fn file_type(&self) -> Result<Self::FileType> {
+        Ok(self.metadata.file_type)
+    }
+
+    fn file_name(&self) -> OsString {
+        self.file_name.clone()
+    }
+}
+
+impl Iterator for ZipReadDir {
+    type Item = Result<ZipDirEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.entries.next().map(Ok)
+    }
+}
+
+impl Read for ZipFile {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.data.read(buf)
+    }
+}
+
+impl Write for ZipFile {
+    fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+        Err(error("Cannot write files in a zip filesystem"))
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Seek for ZipFile {
+    fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+        self.data.seek(pos)
+    }
+}
+
+impl UniFile for ZipFile {
+    type Metadata = ZipMetadata;
+    type Permissions = Permissions;
+    type FileTimes = FileTimes;
+
+    fn sync_all(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn sync_data(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn set_len(&self, _size: u64) -> Result<()> {
+        Err(error("Cannot set the length of files in a zip filesystem"))
+    }
+
+    fn metadata(&self) -> Result<Self::Metadata> {
+        Ok(self.metadata.clone())
+    }
+
+    fn try_clone(&self) -> Result<Self> {
+        Ok(self.clone())
+    }
+
+    fn set_permissions(&self, _perm: Self::Permissions) -> Result<()> {
+        Err(error("Cannot set permissions in a zip filesystem"))
+    }
+
+    fn set_times(&self, _times: Self::FileTimes) -> Result<()> {
+        Err(error("Cannot set times in a zip filesystem"))
+    }
+
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> Result<usize> {
+        let mut data = Cursor::new(self.data.get_ref().as_slice());
+        data.set_position(offset);
+        data.read(buf)
+    }
+}
+
+impl<R: Read + Seek> UniOpenOptions for ZipOpenOptions<R> {
+    type File = ZipFile;
+
+    fn read(&mut self, _read: bool) -> &mut Self {
+        self
+    }
+
+    fn write(&mut self, write: bool) -> &mut Self {
+        self.mutating |= write;
+        self
+    }
+
+    fn append(&mut self, append: bool) -> &mut Self {
+        self.mutating |= append;
+        self
+    }
+
+    fn truncate(&mut self, truncate: bool) -> &mut Self {
+        self.mutating |= truncate;
+        self
+    }
+
+    fn create(&mut self, create: bool) -> &mut Self {
+        self.mutating |= create;
+        self
+    }
+
+    fn create_new(&mut self, create_new: bool) -> &mut Self {
+        self.mutating |= create_new;
+        self
+    }
+
+    fn open<P: AsRef<Path>>(&self, path: P) -> Result<Self::File> {
+        if self.mutating {
+            return Err(error("Cannot open files for writing in a zip filesystem"));
+        }
+
+        self.fs.open_read(path.as_ref())
+    }
+}
+
+impl UniDirBuilder for ZipDirBuilder {
+    fn recursive(&mut self, _recursive: bool) -> &mut Self {
+        self
+    }
+
+    fn create<P: AsRef<Path>>(&self, _path: P) -> Result<()> {
+        Err(error("Cannot create directories in a zip filesystem"))
+    }
+}
@@ -1,4 +1,4 @@
-use std::{collections::HashSet, ffi::OsString};
+use std::{collections::HashSet, ffi::OsString, io::ErrorKind};
 
 use unifs::{AltrootFs, MemoryFs, UniDirEntry, UniFs as _, UniMetadata};
 
@@ -97,3 +97,35 @@ fn general_test() -> unifs::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn escaping_paths() -> unifs::Result<()> {
+    let root_fs = MemoryFs::default();
+    let fs = AltrootFs::new_or_create(&root_fs, "root")?;
+    fs.create_dir("/sandbox")?;
+    fs.write("/sandbox/file.txt", b"secret")?;
+
+    let err = fs.rename("/sandbox/file.txt", "../escape").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+    let err = fs
+        .rename("/sandbox/file.txt", "/sandbox/../../escape")
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+    let err = fs
+        .rename("../root/sandbox/file.txt", "moved.txt")
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+    assert!(fs.exists("/sandbox/file.txt")?);
+    assert!(!root_fs.exists("/escape")?);
+
+    let err = fs.copy("/sandbox/file.txt", "../escape").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+    let err = fs.hard_link("/sandbox/file.txt", "../escape").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+    assert!(!root_fs.exists("/escape")?);
+
+    fs.rename("/sandbox/file.txt", "/sandbox/../moved.txt")?;
+    assert_eq!(root_fs.read("root/moved.txt")?, b"secret");
+
+    Ok(())
+}
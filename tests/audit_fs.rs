@@ -0,0 +1,62 @@
+use std::io::ErrorKind;
+
+use unifs::{AuditFs, MemoryFs, UniFs as _};
+
+/// Returns the operation, paths and outcome of each line of the audit log.
+fn entries(log: &MemoryFs) -> unifs::Result<Vec<String>> {
+    Ok(log
+        .read_to_string("/audit.log")?
+        .lines()
+        .map(|line| {
+            let (time, entry) = line.split_once('\t').unwrap();
+            assert!(time.parse::<f64>().is_ok());
+            entry.to_string()
+        })
+        .collect())
+}
+
+#[test]
+fn operations_are_logged() -> unifs::Result<()> {
+    let log = MemoryFs::default();
+    let fs = AuditFs::new(MemoryFs::default(), &log, "/audit.log");
+
+    fs.create_dir_all("/data")?;
+    fs.write("/data/file.txt", b"Hello, World!")?;
+    fs.rename("/data/file.txt", "/data/renamed.txt")?;
+    assert_eq!(fs.read("/data/renamed.txt")?, b"Hello, World!");
+    assert_eq!(
+        fs.remove_file("/data/missing.txt")
+            .err()
+            .map(|err| err.kind()),
+        Some(ErrorKind::NotFound)
+    );
+
+    assert_eq!(
+        entries(&log)?,
+        [
+            "create_dir_all\t\"/data\"\tok",
+            "write\t\"/data/file.txt\"\tok",
+            "rename\t\"/data/file.txt\" \"/data/renamed.txt\"\tok",
+            "remove_file\t\"/data/missing.txt\"\terror(NotFound)",
+        ]
+    );
+    assert!(fs.take_log_error().is_none());
+
+    Ok(())
+}
+
+#[test]
+fn log_errors_are_separate() -> unifs::Result<()> {
+    let memory = MemoryFs::default();
+    let fs = AuditFs::new(&memory, MemoryFs::default(), "/missing/audit.log");
+
+    fs.write("/file.txt", b"Hello, World!")?;
+    assert_eq!(memory.read("/file.txt")?, b"Hello, World!");
+    assert_eq!(
+        fs.take_log_error().map(|err| err.kind()),
+        Some(ErrorKind::NotFound)
+    );
+    assert!(fs.take_log_error().is_none());
+
+    Ok(())
+}
@@ -0,0 +1,83 @@
+use std::path::PathBuf;
+
+use unifs::{BatchWriteFs, UniFs};
+
+mod common;
+
+use common::RecordingFs;
+
+#[test]
+fn writes_are_buffered_until_flush() -> unifs::Result<()> {
+    let inner = RecordingFs::default();
+    let fs = BatchWriteFs::new(&inner);
+
+    fs.write("/b.txt", b"first")?;
+    fs.write("/a.txt", b"Hello, World!")?;
+    fs.write("/b.txt", b"second")?;
+    assert_eq!(fs.pending_len(), 2);
+    assert!(inner.writes().is_empty());
+    assert!(!inner.fs.exists("/a.txt")?);
+
+    fs.flush()?;
+    assert_eq!(fs.pending_len(), 0);
+    assert_eq!(inner.writes(), ["/a.txt", "/b.txt"].map(PathBuf::from));
+    assert_eq!(inner.fs.read("/b.txt")?, b"second");
+
+    // Other operations observe the buffered writes.
+    fs.write("/c.txt", b"Hello, World!")?;
+    assert_eq!(fs.read("/c.txt")?, b"Hello, World!");
+    assert_eq!(inner.writes().len(), 3);
+
+    Ok(())
+}
+
+#[test]
+fn flush_on_capacity_and_drop() -> unifs::Result<()> {
+    let inner = RecordingFs::default();
+
+    let fs = BatchWriteFs::with_capacity(&inner, 0);
+    fs.write("/direct.txt", b"Hello, World!")?;
+    assert_eq!(inner.writes(), [PathBuf::from("/direct.txt")]);
+    assert_eq!(
+        fs.write("/missing/file.txt", b"")
+            .err()
+            .map(|err| err.kind()),
+        Some(std::io::ErrorKind::NotFound)
+    );
+    assert_eq!(fs.pending_len(), 0);
+
+    let fs = BatchWriteFs::with_capacity(&inner, 8);
+    fs.write("/small.txt", b"1234")?;
+    assert_eq!(inner.writes().len(), 2);
+    fs.write("/large.txt", b"12345")?;
+    assert_eq!(inner.writes().len(), 4);
+
+    fs.write("/dropped.txt", b"1234")?;
+    assert_eq!(inner.writes().len(), 4);
+    drop(fs);
+    assert_eq!(inner.fs.read("/dropped.txt")?, b"1234");
+
+    Ok(())
+}
+
+#[test]
+fn failed_writes_are_reported_by_flush() -> unifs::Result<()> {
+    let inner = RecordingFs::default();
+    let fs = BatchWriteFs::new(&inner);
+
+    fs.write("/missing/a.txt", b"lost")?;
+    fs.write("/missing/b.txt", b"lost")?;
+    fs.write("/ok.txt", b"Hello, World!")?;
+
+    // Unrelated operations succeed, and the other buffered files are written.
+    assert!(fs.exists("/ok.txt")?);
+    assert_eq!(fs.read("/ok.txt")?, b"Hello, World!");
+
+    let err = fs.flush().unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+    assert!(err.to_string().contains("'/missing/a.txt'"));
+    assert!(err.to_string().contains("1 more"));
+    fs.flush()?;
+
+    Ok(())
+}
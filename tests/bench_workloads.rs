@@ -0,0 +1,49 @@
+use std::path::Path;
+
+use unifs::{MemoryFs, PhysicalFs, UniFs};
+
+#[allow(dead_code)]
+#[path = "../benches/workloads/mod.rs"]
+mod workloads;
+
+use workloads::Size;
+
+/// A small size to check that the workloads run.
+const SMALL: Size = Size {
+    files: 8,
+    file_len: 16,
+    depth: 4,
+    width: 2,
+};
+
+/// Runs every workload below `root` with a small size.
+fn run_workloads<F: UniFs>(fs: &F, root: &Path) -> unifs::Result<()> {
+    let size = &SMALL;
+    let len = (size.files * size.file_len) as u64;
+
+    workloads::bulk_create(fs, &root.join("bulk"), size)?;
+    assert_eq!(workloads::bulk_read(fs, &root.join("bulk"), size)?, len);
+    assert_eq!(
+        workloads::copy(fs, &root.join("bulk"), &root.join("copy"))?,
+        len
+    );
+
+    workloads::deep_tree(fs, &root.join("tree"), size)?;
+    assert_eq!(
+        workloads::walk(fs, &root.join("tree"))?,
+        size.depth * (size.width + 1)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn memory_fs() -> unifs::Result<()> {
+    run_workloads(&MemoryFs::default(), Path::new("/bench"))
+}
+
+#[test]
+fn physical_fs() -> unifs::Result<()> {
+    let dir = tempfile::tempdir()?;
+    run_workloads(&PhysicalFs, dir.path())
+}
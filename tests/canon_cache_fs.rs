@@ -0,0 +1,75 @@
+use std::path::PathBuf;
+
+use unifs::{CanonCacheFs, MemoryFs, UniFs};
+
+mod common;
+
+use common::RecordingFs;
+
+#[test]
+fn cache_hits() -> unifs::Result<()> {
+    let fs = CanonCacheFs::new(RecordingFs::default());
+    fs.create_dir("/dir")?;
+    fs.write("/dir/file.txt", b"Hello, World!")?;
+
+    let first = fs.canonicalize("/dir/../dir/file.txt")?;
+    let second = fs.canonicalize("/dir/../dir/file.txt")?;
+    assert_eq!(first, PathBuf::from("/dir/file.txt"));
+    assert_eq!(first, second);
+    assert_eq!(fs.cached_len(), 1);
+
+    let inner = fs.into_inner();
+    assert_eq!(inner.canonicalize_calls(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn rename_invalidates() -> unifs::Result<()> {
+    let fs = CanonCacheFs::new(RecordingFs::default());
+    fs.create_dir("/a")?;
+    fs.write("/a/file.txt", b"Hello, World!")?;
+    fs.create_dir("/b")?;
+
+    fs.canonicalize("/a/file.txt")?;
+    fs.canonicalize("/a/file.txt")?;
+    assert_eq!(fs.cached_len(), 1);
+
+    fs.rename("/a/file.txt", "/b/file.txt")?;
+    assert_eq!(fs.cached_len(), 0);
+
+    fs.canonicalize("/a/file.txt")?;
+    assert_eq!(fs.into_inner().canonicalize_calls(), 2);
+
+    Ok(())
+}
+
+#[test]
+fn relative_paths_pass_through() -> unifs::Result<()> {
+    let inner = MemoryFs::default();
+    let fs = CanonCacheFs::new(RecordingFs::new(inner.clone()));
+    fs.create_dir_all("/a/x")?;
+    fs.create_dir_all("/b/x")?;
+
+    inner.set_current_dir("/a")?;
+    assert_eq!(fs.canonicalize("x")?, PathBuf::from("/a/x"));
+    inner.set_current_dir("/b")?;
+    assert_eq!(fs.canonicalize("x")?, PathBuf::from("/b/x"));
+    assert_eq!(fs.cached_len(), 0);
+    assert_eq!(fs.into_inner().canonicalize_calls(), 2);
+
+    Ok(())
+}
+
+#[test]
+fn bounded() -> unifs::Result<()> {
+    let fs = CanonCacheFs::with_capacity(MemoryFs::default(), 2);
+
+    fs.canonicalize("/a")?;
+    fs.canonicalize("/b")?;
+    assert_eq!(fs.cached_len(), 2);
+    fs.canonicalize("/c")?;
+    assert_eq!(fs.cached_len(), 1);
+
+    Ok(())
+}
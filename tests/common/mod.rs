@@ -0,0 +1,214 @@
+use std::{
+    ffi::OsString,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use unifs::{MemoryFs, UniDirEntry, UniFs};
+
+/// A filesystem that delegates to a [`MemoryFs`] and records the calls made to it.
+///
+/// Calls to `canonicalize`, `metadata` and `symlink_metadata` are counted, as are
+/// calls to `metadata` on the directory entries returned by `read_dir`. The paths
+/// passed to `write` are recorded. Reads sleep for `read_delay` before delegating.
+#[derive(Default)]
+pub struct RecordingFs {
+    pub fs: MemoryFs,
+    pub read_delay: Duration,
+    canonicalize_calls: AtomicUsize,
+    metadata_calls: AtomicUsize,
+    entry_metadata_calls: Arc<AtomicUsize>,
+    writes: Mutex<Vec<PathBuf>>,
+}
+
+#[allow(dead_code)]
+impl RecordingFs {
+    /// Wraps the given filesystem.
+    pub fn new(fs: MemoryFs) -> Self {
+        Self {
+            fs,
+            ..Self::default()
+        }
+    }
+
+    /// Wraps the given filesystem, delaying every read by `delay`.
+    pub fn with_read_delay(fs: MemoryFs, delay: Duration) -> Self {
+        Self {
+            read_delay: delay,
+            ..Self::new(fs)
+        }
+    }
+
+    /// Returns the number of calls to `canonicalize`.
+    pub fn canonicalize_calls(&self) -> usize {
+        self.canonicalize_calls.load(Ordering::SeqCst)
+    }
+
+    /// Returns the number of calls to `metadata` and `symlink_metadata`.
+    pub fn metadata_calls(&self) -> usize {
+        self.metadata_calls.load(Ordering::SeqCst)
+    }
+
+    /// Returns the number of calls to `metadata` on directory entries.
+    pub fn entry_metadata_calls(&self) -> usize {
+        self.entry_metadata_calls.load(Ordering::SeqCst)
+    }
+
+    /// Returns the paths passed to `write`, in call order.
+    pub fn writes(&self) -> Vec<PathBuf> {
+        self.writes.lock().unwrap().clone()
+    }
+}
+
+impl UniFs for RecordingFs {
+    type Metadata = <MemoryFs as UniFs>::Metadata;
+    type ReadDir = RecordingReadDir;
+    type DirEntry = RecordingDirEntry;
+    type Permissions = <MemoryFs as UniFs>::Permissions;
+    type File = <MemoryFs as UniFs>::File;
+    type OpenOptions = <MemoryFs as UniFs>::OpenOptions;
+    type DirBuilder = <MemoryFs as UniFs>::DirBuilder;
+
+    fn canonicalize<P: AsRef<Path>>(&self, path: P) -> unifs::Result<PathBuf> {
+        self.canonicalize_calls.fetch_add(1, Ordering::SeqCst);
+        self.fs.canonicalize(path)
+    }
+
+    fn copy<P: AsRef<Path>, Q: AsRef<Path>>(&self, from: P, to: Q) -> unifs::Result<u64> {
+        self.fs.copy(from, to)
+    }
+
+    fn create_dir<P: AsRef<Path>>(&self, path: P) -> unifs::Result<()> {
+        self.fs.create_dir(path)
+    }
+
+    fn exists<P: AsRef<Path>>(&self, path: P) -> unifs::Result<bool> {
+        self.fs.exists(path)
+    }
+
+    fn hard_link<P: AsRef<Path>, Q: AsRef<Path>>(&self, original: P, link: Q) -> unifs::Result<()> {
+        self.fs.hard_link(original, link)
+    }
+
+    fn metadata<P: AsRef<Path>>(&self, path: P) -> unifs::Result<Self::Metadata> {
+        self.metadata_calls.fetch_add(1, Ordering::SeqCst);
+        self.fs.metadata(path)
+    }
+
+    fn read<P: AsRef<Path>>(&self, path: P) -> unifs::Result<Vec<u8>> {
+        thread::sleep(self.read_delay);
+        self.fs.read(path)
+    }
+
+    fn read_dir<P: AsRef<Path>>(&self, path: P) -> unifs::Result<Self::ReadDir> {
+        Ok(RecordingReadDir {
+            entries: self.fs.read_dir(path)?,
+            metadata_calls: Arc::clone(&self.entry_metadata_calls),
+        })
+    }
+
+    fn read_link<P: AsRef<Path>>(&self, path: P) -> unifs::Result<PathBuf> {
+        self.fs.read_link(path)
+    }
+
+    fn read_to_string<P: AsRef<Path>>(&self, path: P) -> unifs::Result<String> {
+        thread::sleep(self.read_delay);
+        self.fs.read_to_string(path)
+    }
+
+    fn remove_dir<P: AsRef<Path>>(&self, path: P) -> unifs::Result<()> {
+        self.fs.remove_dir(path)
+    }
+
+    fn remove_dir_all<P: AsRef<Path>>(&self, path: P) -> unifs::Result<()> {
+        self.fs.remove_dir_all(path)
+    }
+
+    fn remove_file<P: AsRef<Path>>(&self, path: P) -> unifs::Result<()> {
+        self.fs.remove_file(path)
+    }
+
+    fn rename<P: AsRef<Path>, Q: AsRef<Path>>(&self, from: P, to: Q) -> unifs::Result<()> {
+        self.fs.rename(from, to)
+    }
+
+    fn set_permissions<P: AsRef<Path>>(
+        &self,
+        path: P,
+        perm: Self::Permissions,
+    ) -> unifs::Result<()> {
+        self.fs.set_permissions(path, perm)
+    }
+
+    fn symlink_metadata<P: AsRef<Path>>(&self, path: P) -> unifs::Result<Self::Metadata> {
+        self.metadata_calls.fetch_add(1, Ordering::SeqCst);
+        self.fs.symlink_metadata(path)
+    }
+
+    fn write<P: AsRef<Path>, C: AsRef<[u8]>>(&self, path: P, contents: C) -> unifs::Result<()> {
+        self.writes
+            .lock()
+            .unwrap()
+            .push(path.as_ref().to_path_buf());
+        self.fs.write(path, contents)
+    }
+
+    fn new_openoptions(&self) -> Self::OpenOptions {
+        self.fs.new_openoptions()
+    }
+
+    fn new_dirbuilder(&self) -> Self::DirBuilder {
+        self.fs.new_dirbuilder()
+    }
+}
+
+/// The directory iterator of [`RecordingFs`].
+pub struct RecordingReadDir {
+    entries: <MemoryFs as UniFs>::ReadDir,
+    metadata_calls: Arc<AtomicUsize>,
+}
+
+impl Iterator for RecordingReadDir {
+    type Item = unifs::Result<RecordingDirEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry = self.entries.next()?;
+        Some(entry.map(|entry| RecordingDirEntry {
+            entry,
+            metadata_calls: Arc::clone(&self.metadata_calls),
+        }))
+    }
+}
+
+/// A directory entry of [`RecordingFs`] that counts the calls to `metadata`.
+pub struct RecordingDirEntry {
+    entry: <MemoryFs as UniFs>::DirEntry,
+    metadata_calls: Arc<AtomicUsize>,
+}
+
+impl UniDirEntry for RecordingDirEntry {
+    type Metadata = <MemoryFs as UniFs>::Metadata;
+    type FileType = unifs::FileType;
+
+    fn path(&self) -> PathBuf {
+        self.entry.path()
+    }
+
+    fn metadata(&self) -> unifs::Result<Self::Metadata> {
+        self.metadata_calls.fetch_add(1, Ordering::SeqCst);
+        self.entry.metadata()
+    }
+
+    fn file_type(&self) -> unifs::Result<Self::FileType> {
+        self.entry.file_type()
+    }
+
+    fn file_name(&self) -> OsString {
+        self.entry.file_name()
+    }
+}
@@ -0,0 +1,32 @@
+use std::io::ErrorKind;
+
+use unifs::{AltrootFs, ContextFs, MemoryFs, UniFs as _};
+
+#[test]
+fn general_test() -> unifs::Result<()> {
+    let root_fs = MemoryFs::default();
+    let fs = ContextFs::new(AltrootFs::new_or_create(&root_fs, "/root")?);
+
+    fs.write("/file.txt", b"Hello, World!")?;
+    assert_eq!(fs.read("/file.txt")?, b"Hello, World!");
+
+    let err = fs.read("/missing.txt").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+    assert!(err.to_string().contains("read '/missing.txt'"));
+
+    let err = fs.rename("/missing.txt", "/other.txt").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+    assert!(err
+        .to_string()
+        .contains("rename '/missing.txt' to '/other.txt'"));
+
+    let err = fs
+        .write_reporting("/missing/file.txt", b"data")
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+    assert!(err
+        .to_string()
+        .contains("write_reporting '/missing/file.txt'"));
+
+    Ok(())
+}
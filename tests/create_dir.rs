@@ -0,0 +1,82 @@
+use std::{borrow::Cow, io::ErrorKind, path::Path, time::Duration};
+
+use unifs::{
+    AltrootFs, BatchWriteFs, CanonCacheFs, ContextFs, FallbackFs, HideFs, MapPathFs, MemoryFs,
+    PhysicalFs, StackedFs, TimeoutFs, UniFs, UniMetadata as _,
+};
+
+/// Checks that directories below the missing directory `root/missing` cannot be created
+/// without creating their parents first.
+fn assert_parent_required<F: UniFs>(fs: &F, root: &Path) -> unifs::Result<()> {
+    let missing = root.join("missing");
+    for path in [missing.join("dir"), missing.join("deeper/dir")] {
+        assert_eq!(
+            fs.create_dir(&path).err().map(|err| err.kind()),
+            Some(ErrorKind::NotFound),
+            "creating {}",
+            path.display()
+        );
+    }
+    assert!(!fs.exists(&missing)?);
+
+    fs.create_dir(&missing)?;
+    fs.create_dir(missing.join("dir"))?;
+    assert!(fs.metadata(missing.join("dir"))?.is_dir());
+
+    Ok(())
+}
+
+#[test]
+fn memory_fs() -> unifs::Result<()> {
+    assert_parent_required(&MemoryFs::new(), Path::new("/"))
+}
+
+#[test]
+fn physical_fs() -> unifs::Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    assert_parent_required(&PhysicalFs, temp_dir.path())
+}
+
+#[test]
+fn altroot_fs() -> unifs::Result<()> {
+    let inner = MemoryFs::new();
+    inner.create_dir("/sandbox")?;
+    let fs = AltrootFs::new(inner.clone(), "/sandbox")?;
+    assert_parent_required(&fs, Path::new("/"))?;
+    assert!(!inner.exists("/missing")?);
+
+    // The root is not created again once it was removed from the inner filesystem.
+    inner.remove_dir_all("/sandbox")?;
+    assert_eq!(
+        fs.create_dir("/dir").err().map(|err| err.kind()),
+        Some(ErrorKind::NotFound)
+    );
+    assert!(!inner.exists("/sandbox")?);
+
+    Ok(())
+}
+
+#[test]
+fn stacked_fs() -> unifs::Result<()> {
+    let fs = StackedFs::new(MemoryFs::new(), MemoryFs::new(), "/mnt");
+    assert_parent_required(&fs, Path::new("/"))?;
+    assert_parent_required(&fs, Path::new("/mnt"))
+}
+
+#[test]
+fn wrappers() -> unifs::Result<()> {
+    let root = Path::new("/");
+    assert_parent_required(&BatchWriteFs::new(MemoryFs::new()), root)?;
+    assert_parent_required(&CanonCacheFs::new(MemoryFs::new()), root)?;
+    assert_parent_required(&ContextFs::new(MemoryFs::new()), root)?;
+    assert_parent_required(&FallbackFs::new(MemoryFs::new(), MemoryFs::new()), root)?;
+    assert_parent_required(&HideFs::new(MemoryFs::new(), ["/hidden"]), root)?;
+    assert_parent_required(
+        &MapPathFs::new(MemoryFs::new(), |path: &Path| Cow::Borrowed(path)),
+        root,
+    )?;
+    assert_parent_required(
+        &TimeoutFs::new(MemoryFs::new(), Duration::from_secs(10)),
+        root,
+    )
+}
@@ -0,0 +1,116 @@
+use std::io::{ErrorKind, Read as _, Write as _};
+
+use unifs::{
+    dry_run_fs::PlannedOp, DryRunFs, MemoryFs, UniFs as _, UniMetadata as _, UniOpenOptions as _,
+    UniPermissions as _,
+};
+
+/// Moves the logs of the old layout into a new directory and drops the cache.
+fn migrate(fs: &impl unifs::UniFs) -> unifs::Result<()> {
+    fs.create_dir_all("/data/logs")?;
+    fs.rename("/app.log", "/data/logs/app.log")?;
+    fs.copy("/config.toml", "/data/config.toml")?;
+    fs.remove_dir_all("/cache")?;
+    fs.write("/data/VERSION", "2")?;
+
+    Ok(())
+}
+
+#[test]
+fn migration() -> unifs::Result<()> {
+    let memory_fs = MemoryFs::default();
+    memory_fs.write("/app.log", "started")?;
+    memory_fs.write("/config.toml", "debug = false")?;
+    memory_fs.create_dir_all("/cache/thumbnails")?;
+    let original = MemoryFs::load_from_dir(&memory_fs, "/")?;
+
+    let fs = DryRunFs::new(&memory_fs);
+    migrate(&fs)?;
+
+    assert!(memory_fs == original);
+    assert_eq!(
+        fs.planned(),
+        [
+            PlannedOp::CreateDir {
+                path: "/data/logs".into(),
+                recursive: true,
+            },
+            PlannedOp::Rename {
+                from: "/app.log".into(),
+                to: "/data/logs/app.log".into(),
+            },
+            PlannedOp::Copy {
+                from: "/config.toml".into(),
+                to: "/data/config.toml".into(),
+            },
+            PlannedOp::RemoveDirAll {
+                path: "/cache".into(),
+            },
+            PlannedOp::Write {
+                path: "/data/VERSION".into(),
+                len: 1,
+            },
+        ]
+    );
+
+    // Applying the migration for real afterwards changes the filesystem.
+    migrate(&memory_fs)?;
+    assert_eq!(memory_fs.read_to_string("/data/logs/app.log")?, "started");
+
+    Ok(())
+}
+
+#[test]
+fn files() -> unifs::Result<()> {
+    let memory_fs = MemoryFs::default();
+    memory_fs.write("/file.txt", "Hello")?;
+    let fs = DryRunFs::new(&memory_fs);
+
+    let mut file = fs
+        .new_openoptions()
+        .read(true)
+        .append(true)
+        .open("/file.txt")?;
+    file.write_all(b", World!")?;
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)?;
+    assert_eq!(contents, "");
+    drop(file);
+    assert_eq!(memory_fs.read_to_string("/file.txt")?, "Hello");
+
+    let mut file = fs.open_file("/file.txt")?;
+    assert_eq!(
+        file.write(b"data").err().map(|err| err.kind()),
+        Some(ErrorKind::PermissionDenied)
+    );
+
+    fs.create_file("/new.txt")?.write_all(b"new")?;
+    let mut readonly = fs.metadata("/file.txt")?.permissions();
+    readonly.set_readonly(true);
+    fs.set_permissions("/file.txt", readonly)?;
+    assert!(!memory_fs.exists("/new.txt")?);
+    assert_eq!(
+        fs.create_new_file("/file.txt").err().map(|err| err.kind()),
+        Some(ErrorKind::AlreadyExists)
+    );
+
+    assert_eq!(
+        fs.planned(),
+        [
+            PlannedOp::OpenWrite {
+                path: "/file.txt".into(),
+                truncate: false,
+            },
+            PlannedOp::OpenWrite {
+                path: "/new.txt".into(),
+                truncate: true,
+            },
+            PlannedOp::SetPermissions {
+                path: "/file.txt".into(),
+                readonly: true,
+            },
+        ]
+    );
+
+    Ok(())
+}
@@ -0,0 +1,79 @@
+use unifs::{AltrootFs, DynUniFs, MemoryFs, PhysicalFs};
+
+#[test]
+fn boxed_backends() -> unifs::Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+
+    let backends: Vec<Box<dyn DynUniFs>> = vec![
+        Box::new(MemoryFs::default()),
+        Box::new(AltrootFs::new_or_create(MemoryFs::default(), "/root")?),
+        Box::new(AltrootFs::new(PhysicalFs, temp_dir.path())?),
+    ];
+
+    for fs in &backends {
+        fs.create_dir_all("/dir")?;
+        fs.write("/dir/file.txt", b"Hello, World!")?;
+        assert!(fs.exists("/dir/file.txt")?);
+        assert_eq!(fs.read("/dir/file.txt")?, b"Hello, World!");
+        assert_eq!(fs.read_to_string("/dir/file.txt")?, "Hello, World!");
+
+        fs.remove_file("/dir/file.txt")?;
+        assert!(!fs.exists("/dir/file.txt")?);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn shared_between_threads() -> unifs::Result<()> {
+    let fs: std::sync::Arc<dyn DynUniFs + Send + Sync> = std::sync::Arc::new(MemoryFs::default());
+
+    let writer = std::sync::Arc::clone(&fs);
+    std::thread::spawn(move || writer.write("/file.txt", b"Hello, World!"))
+        .join()
+        .unwrap()?;
+    assert_eq!(fs.read_to_string("/file.txt")?, "Hello, World!");
+
+    Ok(())
+}
+
+#[test]
+fn from_uri() -> unifs::Result<()> {
+    let fs = unifs::from_uri("fs://mem")?;
+    fs.write("/file.txt", b"memory")?;
+    assert_eq!(fs.read_to_string("/file.txt")?, "memory");
+
+    let temp_dir = tempfile::tempdir()?;
+    let uri = format!("fs://{}", temp_dir.path().display());
+    let fs = unifs::from_uri(&uri)?;
+    fs.write("/file.txt", b"physical")?;
+    assert_eq!(fs.read_to_string("/file.txt")?, "physical");
+    assert_eq!(
+        std::fs::read_to_string(temp_dir.path().join("file.txt"))?,
+        "physical"
+    );
+
+    for uri in ["ftp://example.com", "fs://relative/path", "no scheme"] {
+        assert_eq!(
+            unifs::from_uri(uri).err().map(|err| err.kind()),
+            Some(std::io::ErrorKind::InvalidInput)
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "zip")]
+#[test]
+fn downcast() -> unifs::Result<()> {
+    let fs: Box<dyn DynUniFs> = Box::new(MemoryFs::default());
+    fs.write("/file.txt", b"Hello, World!")?;
+    assert!(fs.as_any().downcast_ref::<PhysicalFs>().is_none());
+
+    let memory_fs = fs.as_any().downcast_ref::<MemoryFs>().unwrap();
+    let restored: Box<dyn DynUniFs> =
+        Box::new(MemoryFs::from_zip(std::io::Cursor::new(memory_fs.zip()?))?);
+    assert_eq!(restored.read("/file.txt")?, b"Hello, World!");
+
+    Ok(())
+}
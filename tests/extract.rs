@@ -0,0 +1,153 @@
+#![cfg(all(feature = "memory_fs", any(feature = "tar", feature = "zip")))]
+
+use std::io::ErrorKind;
+
+use unifs::{AltrootFs, MemoryFs, UniFs, UniMetadata as _};
+
+#[cfg(feature = "zip")]
+#[test]
+fn extract_zip() -> unifs::Result<()> {
+    use std::io::{Cursor, Write as _};
+    use zip::{write::SimpleFileOptions, ZipWriter};
+
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    writer.add_directory("dir/empty", SimpleFileOptions::default())?;
+    writer.start_file("dir/file.txt", SimpleFileOptions::default())?;
+    writer.write_all(b"Hello, World!")?;
+    writer.start_file("../escape", SimpleFileOptions::default())?;
+    writer.write_all(b"escaped")?;
+    let archive = writer.finish()?.into_inner();
+
+    let memory = MemoryFs::default();
+    let sandbox = AltrootFs::new_or_create(&memory, "/sandbox")?;
+    let err = unifs::extract_zip(Cursor::new(archive), &sandbox, "/dest").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+    assert_eq!(sandbox.read("/dest/dir/file.txt")?, b"Hello, World!");
+    assert!(sandbox.exists("/dest/dir/empty")?);
+    assert!(!sandbox.exists("/escape")?);
+    assert!(!memory.exists("/escape")?);
+    assert!(!memory.exists("/sandbox/escape")?);
+
+    // An existing link is replaced by the entry instead of being written through.
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    writer.start_file("evil", SimpleFileOptions::default())?;
+    writer.write_all(b"replaced")?;
+    let archive = writer.finish()?.into_inner();
+
+    memory.write("/outside.txt", b"original")?;
+    memory.create_dir("/links")?;
+    memory.symlink("/outside.txt", "/links/evil")?;
+    unifs::extract_zip(Cursor::new(archive), &memory, "/links")?;
+    assert_eq!(memory.read("/outside.txt")?, b"original");
+    assert!(!memory.symlink_metadata("/links/evil")?.is_symlink());
+    assert_eq!(memory.read("/links/evil")?, b"replaced");
+
+    Ok(())
+}
+
+#[cfg(feature = "tar")]
+#[test]
+fn extract_tar() -> unifs::Result<()> {
+    use tar::{Builder, EntryType, Header};
+
+    /// Appends an entry, setting the name directly so that `..` is not rejected.
+    fn append(builder: &mut Builder<Vec<u8>>, name: &str, kind: EntryType, data: &[u8]) {
+        let mut header = Header::new_old();
+        header.as_old_mut().name[..name.len()].copy_from_slice(name.as_bytes());
+        header.set_entry_type(kind);
+        header.set_mode(0o644);
+        header.set_size(data.len() as u64);
+        header.set_cksum();
+        builder.append(&header, data).unwrap();
+    }
+
+    let mut builder = Builder::new(Vec::new());
+    append(&mut builder, "dir/file.txt", EntryType::Regular, b"Hello");
+    let mut link = Header::new_gnu();
+    link.set_entry_type(EntryType::Symlink);
+    link.set_size(0);
+    builder.append_link(&mut link, "dir/link", "/")?;
+    append(
+        &mut builder,
+        "dir/link/escape",
+        EntryType::Regular,
+        b"escaped",
+    );
+    let through_link = builder.into_inner()?;
+
+    let mut builder = Builder::new(Vec::new());
+    append(&mut builder, "../escape", EntryType::Regular, b"escaped");
+    let parent_dir = builder.into_inner()?;
+
+    let memory = MemoryFs::default();
+    let sandbox = AltrootFs::new_or_create(&memory, "/sandbox")?;
+    for archive in [through_link, parent_dir] {
+        let err = unifs::extract_tar(archive.as_slice(), &sandbox, "/dest").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+
+    assert_eq!(sandbox.read("/dest/dir/file.txt")?, b"Hello");
+    assert_eq!(
+        sandbox.read_link("/dest/dir/link")?,
+        std::path::Path::new("/")
+    );
+    assert!(!sandbox.exists("/escape")?);
+    assert!(!sandbox.exists("/dest/escape")?);
+    assert!(!memory.exists("/escape")?);
+    assert!(!memory.exists("/sandbox/escape")?);
+
+    Ok(())
+}
+
+#[cfg(feature = "tar")]
+#[test]
+fn extract_tar_symlink_then_file() -> unifs::Result<()> {
+    use tar::{Builder, EntryType, Header};
+
+    /// Extracts an archive with a link `evil` to `outside`, followed by a regular file
+    /// `evil`, and checks that the file replaced the link.
+    fn check<F: UniFs>(
+        fs: &F,
+        outside: &std::path::Path,
+        dest: &std::path::Path,
+    ) -> unifs::Result<()> {
+        let mut builder = Builder::new(Vec::new());
+        let mut link = Header::new_gnu();
+        link.set_entry_type(EntryType::Symlink);
+        link.set_size(0);
+        builder.append_link(&mut link, "evil", outside)?;
+        let mut file = Header::new_gnu();
+        file.set_entry_type(EntryType::Regular);
+        file.set_mode(0o644);
+        file.set_size(8);
+        builder.append_data(&mut file, "evil", &b"replaced"[..])?;
+        let archive = builder.into_inner()?;
+
+        fs.write(outside, b"original")?;
+        unifs::extract_tar(archive.as_slice(), fs, dest)?;
+        assert_eq!(fs.read(outside)?, b"original");
+        assert!(!fs.symlink_metadata(dest.join("evil"))?.is_symlink());
+        assert_eq!(fs.read(dest.join("evil"))?, b"replaced");
+
+        Ok(())
+    }
+
+    check(
+        &MemoryFs::default(),
+        std::path::Path::new("/outside.txt"),
+        std::path::Path::new("/dest"),
+    )?;
+
+    #[cfg(all(unix, feature = "fs_access"))]
+    {
+        let temp_dir = tempfile::tempdir()?;
+        check(
+            &unifs::PhysicalFs,
+            &temp_dir.path().join("outside.txt"),
+            &temp_dir.path().join("dest"),
+        )?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,66 @@
+use std::{collections::HashSet, ffi::OsString, io::Read as _};
+
+use unifs::{FallbackFs, MemoryFs, UniDirEntry as _, UniFs as _, UniOpenOptions as _};
+
+#[test]
+fn general_test() -> unifs::Result<()> {
+    let user = MemoryFs::default();
+    let system = MemoryFs::default();
+
+    system.create_dir("/config")?;
+    system.write("/config/defaults.toml", b"system defaults")?;
+    system.write("/config/app.toml", b"system app")?;
+    user.create_dir("/config")?;
+    user.write("/config/app.toml", b"user app")?;
+
+    let fs = FallbackFs::new(&user, &system);
+
+    assert!(fs.exists("/config/defaults.toml")?);
+    assert_eq!(fs.read("/config/defaults.toml")?, b"system defaults");
+    assert_eq!(fs.read_to_string("/config/app.toml")?, "user app");
+
+    let directory_files = fs
+        .read_dir("/config")?
+        .map(|entry| entry.map(|e| e.file_name()))
+        .collect::<unifs::Result<Vec<_>>>()?;
+    assert_eq!(directory_files.len(), 2);
+    assert_eq!(
+        directory_files.into_iter().collect::<HashSet<_>>(),
+        HashSet::<OsString>::from(["app.toml".into(), "defaults.toml".into()])
+    );
+
+    fs.write("/config/defaults.toml", b"user defaults")?;
+    assert_eq!(fs.read("/config/defaults.toml")?, b"user defaults");
+    assert_eq!(user.read("/config/defaults.toml")?, b"user defaults");
+    assert_eq!(system.read("/config/defaults.toml")?, b"system defaults");
+
+    assert!(!fs.exists("/config/missing.toml")?);
+    assert!(fs.read("/config/missing.toml").is_err());
+
+    Ok(())
+}
+
+#[test]
+fn open_options_reset() -> unifs::Result<()> {
+    let user = MemoryFs::default();
+    let system = MemoryFs::default();
+    system.write("/defaults.toml", b"system defaults")?;
+
+    let fs = FallbackFs::new(&user, &system);
+
+    // Only the final state of the flags decides whether the fallback may be used.
+    let mut file = fs
+        .new_openoptions()
+        .read(true)
+        .write(true)
+        .create(true)
+        .write(false)
+        .create(false)
+        .open("/defaults.toml")?;
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)?;
+    assert_eq!(contents, "system defaults");
+    assert!(!user.exists("/defaults.toml")?);
+
+    Ok(())
+}
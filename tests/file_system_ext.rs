@@ -0,0 +1,837 @@
+use std::{
+    ffi::OsString,
+    io::{ErrorKind, Read as _, Write as _},
+    ops::ControlFlow,
+    path::{Path, PathBuf},
+};
+
+use unifs::{
+    manifest_diff, CopyOptions, FileType, MemoryFs, PhysicalFs, UniDirEntry as _, UniFile as _,
+    UniFs, UniFsExt as _, UniMetadata as _, UniPermissions as _,
+};
+
+mod common;
+
+use common::RecordingFs;
+
+#[test]
+fn copy_any() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    fs.create_dir_all("/src/sub")?;
+    fs.write("/src/a.txt", b"Hello")?;
+    fs.write("/src/sub/b.txt", b"World!")?;
+
+    assert_eq!(fs.copy_any("/src/a.txt", "/a-copy.txt")?, 5);
+    assert_eq!(fs.read("/a-copy.txt")?, b"Hello");
+
+    assert_eq!(fs.copy_any("/src", "/dst")?, 11);
+    assert_eq!(fs.read("/dst/a.txt")?, b"Hello");
+    assert_eq!(fs.read("/dst/sub/b.txt")?, b"World!");
+
+    Ok(())
+}
+
+#[test]
+fn copy_smart() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    fs.create_dir("/dir")?;
+    fs.write("/file.txt", b"Hello")?;
+
+    assert_eq!(fs.copy_smart("/file.txt", "/dir/")?, 5);
+    assert_eq!(fs.read("/dir/file.txt")?, b"Hello");
+    fs.copy_smart("/file.txt", "/dir")?;
+    assert_eq!(fs.read_dir("/dir")?.count(), 1);
+
+    fs.copy_smart("/file.txt", "/copy.txt")?;
+    assert_eq!(fs.read("/copy.txt")?, b"Hello");
+    assert_eq!(
+        fs.copy_smart("/file.txt", "/missing/")
+            .err()
+            .map(|err| err.kind()),
+        Some(ErrorKind::NotFound)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn rename_smart() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    fs.create_dir_all("/dir/sub")?;
+    fs.write("/file.txt", b"Hello")?;
+
+    fs.rename_smart("/file.txt", "/dir/")?;
+    assert_eq!(fs.read("/dir/file.txt")?, b"Hello");
+    fs.rename_smart("/dir/sub", "/")?;
+    assert!(fs.metadata("/sub")?.is_dir());
+
+    fs.rename_smart("/dir/file.txt", "/renamed.txt")?;
+    assert_eq!(fs.read("/renamed.txt")?, b"Hello");
+    assert!(!fs.exists("/dir/file.txt")?);
+
+    Ok(())
+}
+
+#[test]
+fn read_range() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    fs.write("/file.txt", b"Hello, World!")?;
+
+    assert_eq!(fs.read_range("/file.txt", 7, 5)?, b"World");
+    assert_eq!(fs.read_range("/file.txt", 7, 100)?, b"World!");
+    assert_eq!(fs.read_range("/file.txt", 100, 5)?, b"");
+
+    Ok(())
+}
+
+fn sorted_walk<F: UniFs>(fs: &F, root: &Path) -> unifs::Result<Vec<PathBuf>> {
+    fs.walk_dir_sorted(root, |a, b| a.file_name().cmp(&b.file_name()))
+        .map(|entry| {
+            let path = entry?.path();
+            Ok(path.strip_prefix(root).unwrap_or(&path).to_path_buf())
+        })
+        .collect()
+}
+
+#[test]
+fn walk_dir_sorted() -> unifs::Result<()> {
+    let layout = ["b/z.txt", "b/a.txt", "a.txt", "c/d/e.txt", "c/b.txt"];
+
+    let memory_fs = MemoryFs::default();
+    let memory_root = Path::new("/root");
+    let temp_dir = tempfile::tempdir()?;
+    let physical_root = temp_dir.path();
+
+    for file in layout {
+        let path = Path::new(file);
+        memory_fs.create_dir_all(memory_root.join(path.parent().unwrap()))?;
+        memory_fs.write(memory_root.join(path), file)?;
+        PhysicalFs.create_dir_all(physical_root.join(path.parent().unwrap()))?;
+        PhysicalFs.write(physical_root.join(path), file)?;
+    }
+
+    let expected = [
+        "a.txt",
+        "b",
+        "b/a.txt",
+        "b/z.txt",
+        "c",
+        "c/b.txt",
+        "c/d",
+        "c/d/e.txt",
+    ]
+    .map(PathBuf::from);
+
+    assert_eq!(sorted_walk(&memory_fs, memory_root)?, expected);
+    assert_eq!(sorted_walk(&PhysicalFs, physical_root)?, expected);
+
+    Ok(())
+}
+
+#[test]
+fn spill_to_temp() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    fs.write("/file.txt", b"Hello, World!")?;
+
+    let mut buf = Vec::new();
+    fs.spill_to_temp("/file.txt")?.read_to_end(&mut buf)?;
+    assert_eq!(buf, b"Hello, World!");
+
+    let temp_dir = tempfile::tempdir()?;
+    let path = temp_dir.path().join("file.txt");
+    PhysicalFs.write(&path, b"Hello, Disk!")?;
+
+    let mut buf = Vec::new();
+    PhysicalFs.spill_to_temp(&path)?.read_to_end(&mut buf)?;
+    assert_eq!(buf, b"Hello, Disk!");
+
+    Ok(())
+}
+
+#[test]
+fn read_dir_capped() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    fs.create_dir("/dir")?;
+    for i in 0..10 {
+        fs.write(format!("/dir/{i}.txt"), b"")?;
+    }
+
+    let (entries, truncated) = fs.read_dir_capped("/dir", 4)?;
+    assert_eq!(entries.len(), 4);
+    assert!(truncated);
+
+    let (entries, truncated) = fs.read_dir_capped("/dir", 10)?;
+    assert_eq!(entries.len(), 10);
+    assert!(!truncated);
+
+    Ok(())
+}
+
+#[test]
+fn copy_dir_all() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    fs.create_dir_all("/src/empty")?;
+    fs.create_dir_all("/src/sub/nested-empty")?;
+    fs.write("/src/sub/file.txt", b"Hello")?;
+    fs.create_dir("/empty-src")?;
+
+    assert_eq!(fs.copy_dir_all("/src", "/dst/copy")?, 5);
+    assert!(fs.metadata("/dst/copy/empty")?.is_dir());
+    assert!(fs.metadata("/dst/copy/sub/nested-empty")?.is_dir());
+    assert_eq!(fs.read("/dst/copy/sub/file.txt")?, b"Hello");
+
+    assert_eq!(fs.copy_dir_all("/empty-src", "/empty-dst")?, 0);
+    assert!(fs.metadata("/empty-dst")?.is_dir());
+
+    Ok(())
+}
+
+#[cfg(feature = "sha2")]
+#[test]
+fn copy_dir_dedup() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    fs.create_dir_all("/src/sub")?;
+    fs.write("/src/a.txt", b"same")?;
+    fs.write("/src/c.txt", b"different")?;
+    fs.write("/src/sub/b.txt", b"same")?;
+
+    // The second file with the same contents is linked instead of copied.
+    assert_eq!(fs.copy_dir_dedup("/src", "/dst")?, 13);
+    assert_eq!(fs.read("/dst/a.txt")?, b"same");
+    assert_eq!(fs.read("/dst/c.txt")?, b"different");
+    assert_eq!(fs.read("/dst/sub/b.txt")?, b"same");
+
+    // Both destination files share a single buffer.
+    fs.write("/dst/sub/b.txt", b"changed")?;
+    assert_eq!(fs.read("/dst/a.txt")?, b"changed");
+    assert_eq!(fs.read("/src/a.txt")?, b"same");
+
+    Ok(())
+}
+
+#[test]
+fn line_writer() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    fs.write("/log.txt", "first\n")?;
+
+    let mut writer = fs.line_writer("/log.txt")?;
+    writeln!(writer, "second")?;
+    assert_eq!(fs.read_to_string("/log.txt")?, "first\nsecond\n");
+    write!(writer, "third")?;
+    writeln!(writer)?;
+    drop(writer);
+
+    assert_eq!(
+        fs.read_to_string("/log.txt")?.lines().collect::<Vec<_>>(),
+        ["first", "second", "third"]
+    );
+
+    Ok(())
+}
+
+#[cfg(feature = "encoding_rs")]
+#[test]
+fn text_encoding() -> unifs::Result<()> {
+    use unifs::encoding_rs::{UTF_16LE, WINDOWS_1252};
+
+    let fs = MemoryFs::default();
+    fs.write_text_encoded("/utf16.txt", "Grüße, 世界!", UTF_16LE)?;
+    assert_eq!(&fs.read("/utf16.txt")?[..4], b"G\0r\0");
+    assert_eq!(
+        fs.read_text_decoded("/utf16.txt", UTF_16LE)?,
+        "Grüße, 世界!"
+    );
+
+    fs.write_text_encoded("/latin1.txt", "Grüße", WINDOWS_1252)?;
+    assert_eq!(fs.read("/latin1.txt")?, b"Gr\xfc\xdfe");
+    assert_eq!(
+        fs.write_text_encoded("/latin1.txt", "世界", WINDOWS_1252)
+            .err()
+            .map(|err| err.kind()),
+        Some(ErrorKind::InvalidData)
+    );
+
+    fs.write("/odd.txt", b"G\0r")?;
+    assert_eq!(
+        fs.read_text_decoded("/odd.txt", UTF_16LE)
+            .err()
+            .map(|err| err.kind()),
+        Some(ErrorKind::InvalidData)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn with_snapshot() -> unifs::Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let root = temp_dir.path();
+    let fs = PhysicalFs;
+    fs.create_dir_all(root.join("dir/sub"))?;
+    fs.write(root.join("a.txt"), b"a")?;
+    fs.write(root.join("dir/b.txt"), b"b")?;
+    fs.write(root.join("dir/sub/c.txt"), b"c")?;
+    let before = sorted_walk(&fs, root)?;
+
+    let res = fs.with_snapshot(root, |fs| -> unifs::Result<()> {
+        fs.write(root.join("a.txt"), b"modified")?;
+        fs.remove_dir_all(root.join("dir/sub"))?;
+        fs.remove_file(root.join("dir/b.txt"))?;
+        fs.create_dir(root.join("dir/b.txt"))?;
+        fs.create_dir_all(root.join("new/dir"))?;
+        fs.write(root.join("new.txt"), b"new")
+    })?;
+    res?;
+
+    assert_eq!(sorted_walk(&fs, root)?, before);
+    assert_eq!(fs.read(root.join("a.txt"))?, b"a");
+    assert_eq!(fs.read(root.join("dir/b.txt"))?, b"b");
+    assert_eq!(fs.read(root.join("dir/sub/c.txt"))?, b"c");
+
+    Ok(())
+}
+
+#[test]
+fn with_snapshot_restores_symlinks() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    fs.create_dir("/root")?;
+    fs.write("/root/a.txt", b"a")?;
+    fs.write("/root/b.txt", b"b")?;
+    fs.symlink("a.txt", "/root/removed")?;
+    fs.symlink("a.txt", "/root/retargeted")?;
+
+    fs.with_snapshot("/root", |fs| -> unifs::Result<()> {
+        fs.remove_file("/root/removed")?;
+        fs.remove_file("/root/retargeted")?;
+        fs.symlink("b.txt", "/root/retargeted")?;
+        fs.symlink("b.txt", "/root/created")
+    })??;
+
+    assert_eq!(fs.read_link("/root/removed")?, PathBuf::from("a.txt"));
+    assert_eq!(fs.read_link("/root/retargeted")?, PathBuf::from("a.txt"));
+    assert!(!fs.exists("/root/created")?);
+    assert_eq!(fs.read("/root/a.txt")?, b"a");
+
+    Ok(())
+}
+
+#[test]
+fn records_round_trip() -> unifs::Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let root = temp_dir.path();
+    let fs = PhysicalFs;
+    fs.create_dir_all(root.join("dir/empty"))?;
+    fs.write(root.join("a.txt"), b"Hello")?;
+    fs.write(root.join("dir/b.bin"), [0, 1, 2, 255])?;
+
+    let records = fs.to_records(root)?;
+    let paths = records.iter().map(|r| r.path.clone()).collect::<Vec<_>>();
+    assert_eq!(
+        paths,
+        ["a.txt", "dir", "dir/b.bin", "dir/empty"].map(PathBuf::from)
+    );
+    assert_eq!(records[0].kind, FileType::File);
+    assert_eq!(records[0].len, 5);
+    assert_eq!(records[0].contents.as_deref(), Some(&b"Hello"[..]));
+    assert_eq!(records[1].kind, FileType::Directory);
+    assert_eq!(records[1].contents, None);
+
+    let memory_fs = MemoryFs::from_records(records.clone())?;
+    assert_eq!(memory_fs.read("/a.txt")?, b"Hello");
+    assert_eq!(memory_fs.read("/dir/b.bin")?, [0, 1, 2, 255]);
+    assert!(memory_fs.metadata("/dir/empty")?.is_dir());
+    assert_eq!(
+        memory_fs.metadata("/a.txt")?.modified().ok(),
+        records[0].modified
+    );
+
+    // Symbolic links keep their targets.
+    memory_fs.symlink("a.txt", "/link")?;
+    let copy = MemoryFs::from_records(memory_fs.to_records("/")?)?;
+    assert_eq!(copy.read_link("/link")?, Path::new("a.txt"));
+    let without_times = |fs: &MemoryFs| -> unifs::Result<Vec<_>> {
+        Ok(fs
+            .to_records("/")?
+            .into_iter()
+            .map(|record| (record.path, record.kind, record.contents, record.target))
+            .collect())
+    };
+    assert_eq!(without_times(&copy)?, without_times(&memory_fs)?);
+
+    let mut invalid = records[0].clone();
+    invalid.path = PathBuf::from("../escape.txt");
+    assert_eq!(
+        MemoryFs::from_records([invalid])
+            .err()
+            .map(|err| err.kind()),
+        Some(ErrorKind::InvalidInput)
+    );
+
+    Ok(())
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn records_to_json() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    fs.create_dir("/dir")?;
+    fs.write("/dir/file.txt", b"Hello")?;
+
+    let records = fs.to_records("/")?;
+    let json = serde_json::to_string(&records).map_err(std::io::Error::other)?;
+    let parsed: Vec<unifs::FileRecord> =
+        serde_json::from_str(&json).map_err(std::io::Error::other)?;
+    assert_eq!(parsed, records);
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn symlink_exists() -> unifs::Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let link = temp_dir.path().join("link");
+    std::os::unix::fs::symlink(temp_dir.path().join("missing"), &link)?;
+
+    assert!(!PhysicalFs.exists(&link)?);
+    assert!(PhysicalFs.symlink_exists(&link)?);
+    assert!(!PhysicalFs.symlink_exists(temp_dir.path().join("missing"))?);
+
+    Ok(())
+}
+
+#[test]
+fn is_ancestor() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    fs.create_dir_all("/a/b/c")?;
+    fs.create_dir("/ab")?;
+
+    assert!(fs.is_ancestor("/a", "/a/b/c")?);
+    assert!(fs.is_ancestor("/a/b/..", "/a/b/c")?);
+    assert!(fs.is_ancestor("/", "/ab")?);
+    assert!(fs.is_ancestor("/a", "/a")?);
+    assert!(!fs.is_ancestor("/a", "/ab")?);
+    assert!(!fs.is_ancestor("/a/b/c", "/a")?);
+
+    Ok(())
+}
+
+#[test]
+fn bom_handling() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    fs.write("/bom.toml", b"\xEF\xBB\xBFkey = 1")?;
+    assert_eq!(fs.read_to_string_no_bom("/bom.toml")?, "key = 1");
+
+    fs.write_string("/with.toml", "key = 2", true)?;
+    assert_eq!(fs.read("/with.toml")?, b"\xEF\xBB\xBFkey = 2");
+    assert_eq!(fs.read_to_string_no_bom("/with.toml")?, "key = 2");
+
+    fs.write_string("/without.toml", "key = 3", false)?;
+    assert_eq!(fs.read("/without.toml")?, b"key = 3");
+    assert_eq!(fs.read_to_string_no_bom("/without.toml")?, "key = 3");
+
+    Ok(())
+}
+
+#[test]
+fn init_from_template() -> unifs::Result<()> {
+    let template = MemoryFs::default();
+    template.create_dir_all("/template/src")?;
+    template.create_dir("/template/empty")?;
+    template.write("/template/Cargo.toml", b"[package]")?;
+    template.write("/template/src/main.rs", b"fn main() {}")?;
+    template.symlink("src/main.rs", "/template/main.rs")?;
+
+    let fs = MemoryFs::default();
+    assert!(fs.init_from_template("/projects/new", &template, "/template")?);
+    assert_eq!(fs.read("/projects/new/Cargo.toml")?, b"[package]");
+    assert_eq!(fs.read("/projects/new/src/main.rs")?, b"fn main() {}");
+    assert!(fs.metadata("/projects/new/empty")?.is_dir());
+    assert_eq!(
+        fs.read_link("/projects/new/main.rs")?,
+        PathBuf::from("src/main.rs")
+    );
+
+    fs.write("/projects/new/Cargo.toml", b"[package]\nname = \"new\"")?;
+    assert!(!fs.init_from_template("/projects/new", &template, "/template")?);
+    assert_eq!(
+        fs.read("/projects/new/Cargo.toml")?,
+        b"[package]\nname = \"new\""
+    );
+
+    assert!(fs
+        .init_from_template("/projects/broken", &template, "/missing")
+        .is_err());
+    assert!(!fs.exists("/projects/broken")?);
+
+    Ok(())
+}
+
+#[test]
+fn copy_dir_merge() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    fs.create_dir_all("/src/sub")?;
+    fs.write("/src/shared.txt", b"new")?;
+    fs.write("/src/sub/new.txt", b"added")?;
+    fs.create_dir_all("/dst/sub")?;
+    fs.write("/dst/shared.txt", b"old")?;
+    fs.write("/dst/unrelated.txt", b"keep")?;
+    fs.write("/dst/sub/existing.txt", b"keep too")?;
+
+    assert_eq!(fs.copy_dir_merge("/src", "/dst")?, 8);
+    assert_eq!(fs.read("/dst/shared.txt")?, b"new");
+    assert_eq!(fs.read("/dst/sub/new.txt")?, b"added");
+    assert_eq!(fs.read("/dst/unrelated.txt")?, b"keep");
+    assert_eq!(fs.read("/dst/sub/existing.txt")?, b"keep too");
+
+    fs.remove_dir_all("/dst/sub")?;
+    fs.write("/dst/sub", b"file")?;
+    assert!(fs.copy_dir_all("/src", "/dst").is_err());
+
+    Ok(())
+}
+
+#[test]
+fn relative_path() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    fs.create_dir_all("/a/b/c")?;
+    fs.create_dir_all("/a/d")?;
+    fs.write("/a/b/c/from.txt", b"")?;
+    fs.write("/a/d/to.txt", b"")?;
+    fs.write("/a/b/c/sibling.txt", b"")?;
+
+    assert_eq!(
+        fs.relative_path("/a/b/c/from.txt", "/a/d/to.txt")?,
+        Path::new("../../d/to.txt")
+    );
+    assert_eq!(
+        fs.relative_path("/a/d/to.txt", "/a/b/c/from.txt")?,
+        Path::new("../b/c/from.txt")
+    );
+    assert_eq!(
+        fs.relative_path("/a/b/c/from.txt", "/a/b/c/sibling.txt")?,
+        Path::new("sibling.txt")
+    );
+    assert_eq!(
+        fs.relative_path("/a/b/c/from.txt", "/a/b/c")?,
+        Path::new(".")
+    );
+
+    Ok(())
+}
+
+#[test]
+fn open_dir() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    fs.create_dir_all("/work/dir")?;
+    fs.write("/file.txt", b"")?;
+
+    let dir = fs.open_dir("/work/dir")?;
+    assert_eq!(dir.root(), Path::new("/work/dir"));
+    dir.create_dir("sub")?;
+    dir.write("sub/file.txt", b"Hello, World!")?;
+    assert_eq!(dir.read("/sub/file.txt")?, b"Hello, World!");
+    assert_eq!(fs.read("/work/dir/sub/file.txt")?, b"Hello, World!");
+
+    assert_eq!(
+        fs.open_dir("/file.txt").err().map(|err| err.kind()),
+        Some(ErrorKind::NotADirectory)
+    );
+    assert_eq!(
+        fs.open_dir("/missing").err().map(|err| err.kind()),
+        Some(ErrorKind::NotFound)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn write_new() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    fs.write("/existing.txt", b"keep")?;
+
+    assert_eq!(
+        fs.write_new("/existing.txt", b"overwrite")
+            .err()
+            .map(|err| err.kind()),
+        Some(ErrorKind::AlreadyExists)
+    );
+    assert_eq!(fs.read("/existing.txt")?, b"keep");
+
+    fs.write_new("/new.txt", b"Hello, World!")?;
+    assert_eq!(fs.read("/new.txt")?, b"Hello, World!");
+
+    assert_eq!(
+        fs.write_new("/missing/new.txt", b"")
+            .err()
+            .map(|err| err.kind()),
+        Some(ErrorKind::NotFound)
+    );
+
+    Ok(())
+}
+
+#[cfg(feature = "mime_guess")]
+#[test]
+fn guess_mime() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    fs.write("/data.json", "{}")?;
+    fs.write("/image", b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR")?;
+    fs.write("/unknown", "just some text")?;
+    fs.write("/short", "R")?;
+
+    assert_eq!(fs.guess_mime("/data.json")?, "application/json");
+    assert_eq!(fs.guess_mime("/image")?, "image/png");
+    assert_eq!(fs.guess_mime("/unknown")?, "application/octet-stream");
+    assert_eq!(fs.guess_mime("/short")?, "application/octet-stream");
+    // The extension is enough, so the file is not read.
+    assert_eq!(fs.guess_mime("/missing.html")?, "text/html");
+    assert_eq!(
+        fs.guess_mime("/missing").err().map(|err| err.kind()),
+        Some(ErrorKind::NotFound)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn open_or_init() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+
+    let mut file = fs.open_or_init("/config.toml", "default = true\n")?;
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)?;
+    assert_eq!(contents, "default = true\n");
+    file.write_all(b"edited = true\n")?;
+    drop(file);
+
+    let mut file = fs.open_or_init("/config.toml", "default = true\n")?;
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)?;
+    assert_eq!(contents, "default = true\nedited = true\n");
+
+    Ok(())
+}
+
+#[test]
+fn acquire_lockfile() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+
+    let guard = fs.acquire_lockfile("/app.lock")?;
+    assert_eq!(guard.path(), Path::new("/app.lock"));
+    assert!(fs.exists("/app.lock")?);
+    assert_eq!(
+        fs.acquire_lockfile("/app.lock").err().map(|err| err.kind()),
+        Some(ErrorKind::AlreadyExists)
+    );
+
+    drop(guard);
+    assert!(!fs.exists("/app.lock")?);
+    let guard = fs.acquire_lockfile("/app.lock")?;
+    guard.release()?;
+    assert!(!fs.exists("/app.lock")?);
+
+    // Relative lock files are removed even if the current directory changes.
+    fs.create_dir("/dir")?;
+    fs.set_current_dir("/dir")?;
+    let guard = fs.acquire_lockfile("app.lock")?;
+    assert_eq!(guard.path(), Path::new("/dir/app.lock"));
+    fs.set_current_dir("/")?;
+    drop(guard);
+    assert!(!fs.exists("/dir/app.lock")?);
+
+    Ok(())
+}
+
+#[test]
+fn for_each_entry() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    fs.create_dir_all("/root/a/b")?;
+    fs.create_dir_all("/root/tmp/nested")?;
+    fs.write("/root/a/file.txt", b"")?;
+    fs.write("/root/a/b/file.txt", b"")?;
+    fs.write("/root/tmp/nested/file.txt", b"")?;
+
+    let mut count = 0;
+    fs.for_each_entry("/root", |_| {
+        count += 1;
+        Ok(ControlFlow::Continue(()))
+    })?;
+    assert_eq!(count, 7);
+
+    let mut visited = Vec::new();
+    fs.for_each_entry("/root", |entry| {
+        visited.push(entry.path());
+        if entry.file_name() == "b" {
+            Ok(ControlFlow::Break(()))
+        } else {
+            Ok(ControlFlow::Continue(()))
+        }
+    })?;
+    assert_eq!(
+        visited,
+        [
+            PathBuf::from("/root/a"),
+            PathBuf::from("/root/tmp"),
+            PathBuf::from("/root/a/b")
+        ]
+    );
+
+    fs.for_each_entry("/root", |entry| {
+        if entry.file_name() == "tmp" {
+            fs.remove_dir_all(entry.path())?;
+        }
+        Ok(ControlFlow::Continue(()))
+    })?;
+    assert!(!fs.exists("/root/tmp")?);
+
+    Ok(())
+}
+
+#[test]
+fn manifest() -> unifs::Result<()> {
+    let build = || -> unifs::Result<MemoryFs> {
+        let fs = MemoryFs::default();
+        fs.create_dir_all("/tree/sub")?;
+        fs.write("/tree/same.txt", b"same")?;
+        fs.write("/tree/sub/changed.txt", b"before")?;
+        fs.write("/tree/removed.txt", b"removed")?;
+        Ok(fs)
+    };
+
+    let a = build()?;
+    let b = build()?;
+    b.write("/tree/sub/changed.txt", b"after!")?;
+    b.remove_file("/tree/removed.txt")?;
+    b.write("/tree/sub/added.txt", b"added")?;
+
+    let manifest_a = a.manifest("/tree")?;
+    assert_eq!(manifest_a.len(), 3);
+    assert_eq!(manifest_a[Path::new("same.txt")].len, 4);
+    assert_ne!(
+        manifest_a[Path::new("same.txt")].hash,
+        manifest_a[Path::new("removed.txt")].hash
+    );
+
+    // Relative roots give the same paths, and touching a file does not change it.
+    a.set_current_dir("/tree/sub")?;
+    assert_eq!(a.manifest("..")?, manifest_a);
+    a.open_file("/tree/same.txt")?
+        .set_modified(std::time::SystemTime::UNIX_EPOCH)?;
+    assert!(manifest_diff(&manifest_a, &a.manifest("/tree")?).is_empty());
+
+    let diff = manifest_diff(&manifest_a, &b.manifest("/tree")?);
+    assert_eq!(diff.added, [PathBuf::from("sub/added.txt")]);
+    assert_eq!(diff.removed, [PathBuf::from("removed.txt")]);
+    assert_eq!(diff.changed, [PathBuf::from("sub/changed.txt")]);
+    assert!(manifest_diff(&manifest_a, &build()?.manifest("/tree")?).is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn copy_dir_to() -> unifs::Result<()> {
+    let source = MemoryFs::default();
+    source.create_dir_all("/src/a/b/empty")?;
+    source.write("/src/top.txt", b"Hello")?;
+    source.write("/src/a/b/deep.txt", b"World!")?;
+    source.symlink("a/b", "/src/link")?;
+
+    let target = MemoryFs::default();
+    target.create_dir_all("/dst/a")?;
+    target.write("/dst/a/kept.txt", b"kept")?;
+    target.write("/dst/top.txt", b"overwritten")?;
+
+    assert_eq!(source.copy_dir_to("/src", &target, "/dst")?, 11);
+    assert_eq!(target.read("/dst/top.txt")?, b"Hello");
+    assert_eq!(target.read("/dst/a/b/deep.txt")?, b"World!");
+    assert_eq!(target.read("/dst/a/kept.txt")?, b"kept");
+    assert!(target.metadata("/dst/a/b/empty")?.is_dir());
+    assert_eq!(target.read_link("/dst/link")?, Path::new("a/b"));
+    assert_eq!(target.read("/dst/link/deep.txt")?, b"World!");
+
+    Ok(())
+}
+
+#[test]
+fn copy_dir_with() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    fs.create_dir_all("/src/locked")?;
+    fs.write("/src/locked/inner.txt", b"Hello")?;
+    fs.write("/src/file.txt", b"World!")?;
+    fs.write("/src/writable.txt", b"")?;
+
+    let modified = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_000_000);
+    fs.set_owner("/src/file.txt", Some(1000), Some(100))?;
+    for path in ["/src/file.txt", "/src/locked"] {
+        let mut perm = fs.metadata(path)?.permissions();
+        perm.set_readonly(true);
+        fs.set_permissions(path, perm)?;
+    }
+    fs.open_file("/src/file.txt")?.set_modified(modified)?;
+
+    assert_eq!(
+        fs.copy_dir_with("/src", "/dst", CopyOptions::archive())?,
+        11
+    );
+    assert_eq!(fs.read("/dst/locked/inner.txt")?, b"Hello");
+    assert!(fs.metadata("/dst/locked")?.permissions().readonly());
+    assert!(!fs.metadata("/dst/writable.txt")?.permissions().readonly());
+    let metadata = fs.metadata("/dst/file.txt")?;
+    assert!(metadata.permissions().readonly());
+    assert_eq!(metadata.modified()?, modified);
+    assert_eq!((metadata.uid(), metadata.gid()), (Some(1000), Some(100)));
+
+    fs.copy_dir_with("/src", "/plain", CopyOptions::default())?;
+    assert!(!fs.metadata("/plain/locked")?.permissions().readonly());
+    assert_ne!(fs.metadata("/plain/file.txt")?.modified()?, modified);
+
+    Ok(())
+}
+
+#[test]
+fn list_types() -> unifs::Result<()> {
+    let fs = RecordingFs::default();
+    fs.create_dir("/dir")?;
+    fs.create_dir("/dir/sub")?;
+    fs.write("/dir/file.txt", b"Hello, World!")?;
+
+    assert_eq!(
+        fs.list_types("/dir")?,
+        [
+            (OsString::from("file.txt"), FileType::File),
+            (OsString::from("sub"), FileType::Directory)
+        ]
+    );
+
+    assert_eq!(fs.metadata_calls(), 0);
+    assert_eq!(fs.entry_metadata_calls(), 0);
+
+    // The counter observes lookups through the entries themselves.
+    for entry in fs.read_dir("/dir")? {
+        entry?.metadata()?;
+    }
+    assert_eq!(fs.entry_metadata_calls(), 2);
+
+    Ok(())
+}
+
+#[cfg(feature = "sha2")]
+#[test]
+fn read_and_hash() -> unifs::Result<()> {
+    use sha2::{Digest as _, Sha256};
+
+    let fs = MemoryFs::default();
+    let data = (0..20_000u32).map(|i| i as u8).collect::<Vec<_>>();
+    fs.write("/data.bin", &data)?;
+
+    let (contents, hash) = fs.read_and_hash("/data.bin")?;
+    assert_eq!(contents, data);
+    assert_eq!(hash, <[u8; 32]>::from(Sha256::digest(&contents)));
+    assert_eq!(fs.hash_file("/data.bin")?, hash);
+
+    fs.write("/empty.txt", b"")?;
+    assert_eq!(fs.hash_file("/empty.txt")?[..4], [0xe3, 0xb0, 0xc4, 0x42]);
+
+    Ok(())
+}
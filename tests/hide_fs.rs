@@ -0,0 +1,146 @@
+use std::{ffi::OsString, io::ErrorKind};
+
+use unifs::{
+    HideFs, MemoryFs, UniDirBuilder as _, UniDirEntry as _, UniFs as _, UniOpenOptions as _,
+};
+
+#[test]
+fn tenant_isolation() -> unifs::Result<()> {
+    let memory_fs = MemoryFs::default();
+    memory_fs.create_dir_all("/tenants/a")?;
+    memory_fs.create_dir_all("/tenants/b/nested")?;
+    memory_fs.write("/tenants/a/data.txt", "a")?;
+    memory_fs.write("/tenants/b/data.txt", "b")?;
+
+    let fs = HideFs::new(&memory_fs, ["/tenants/b"]);
+
+    // Tenant A works as usual.
+    assert_eq!(fs.read_to_string("/tenants/a/data.txt")?, "a");
+    fs.write("/tenants/a/new.txt", "new")?;
+    assert_eq!(memory_fs.read_to_string("/tenants/a/new.txt")?, "new");
+
+    // Tenant B cannot be read.
+    assert!(!fs.exists("/tenants/b")?);
+    assert!(!fs.exists("/tenants/b/data.txt")?);
+    assert_eq!(
+        fs.read("/tenants/b/data.txt").err().map(|err| err.kind()),
+        Some(ErrorKind::NotFound)
+    );
+    assert_eq!(
+        fs.read("/tenants/a/../b/data.txt")
+            .err()
+            .map(|err| err.kind()),
+        Some(ErrorKind::NotFound)
+    );
+    assert_eq!(
+        fs.metadata("/tenants/b").err().map(|err| err.kind()),
+        Some(ErrorKind::NotFound)
+    );
+    assert_eq!(
+        fs.new_openoptions()
+            .read(true)
+            .open("/tenants/b/data.txt")
+            .err()
+            .map(|err| err.kind()),
+        Some(ErrorKind::NotFound)
+    );
+
+    // Tenant B cannot be listed.
+    assert_eq!(
+        fs.read_dir("/tenants/b").err().map(|err| err.kind()),
+        Some(ErrorKind::NotFound)
+    );
+    let names = fs
+        .read_dir("/tenants")?
+        .map(|entry| entry.map(|entry| entry.file_name()))
+        .collect::<unifs::Result<Vec<_>>>()?;
+    assert_eq!(names, [OsString::from("a")]);
+
+    // Tenant B cannot be written.
+    for res in [
+        fs.write("/tenants/b/data.txt", "overwritten"),
+        fs.write("/tenants/b/new.txt", "new"),
+        fs.create_dir("/tenants/b/dir"),
+        fs.remove_file("/tenants/b/data.txt"),
+        fs.remove_dir_all("/tenants/b"),
+        fs.remove_dir_all("/tenants"),
+        fs.rename("/tenants/a/data.txt", "/tenants/b/moved.txt"),
+        fs.rename("/tenants", "/moved"),
+        fs.copy("/tenants/a/data.txt", "/tenants/b/copy.txt")
+            .map(|_| ()),
+    ] {
+        assert_eq!(
+            res.err().map(|err| err.kind()),
+            Some(ErrorKind::PermissionDenied)
+        );
+    }
+    assert_eq!(
+        fs.new_openoptions()
+            .write(true)
+            .create(true)
+            .open("/tenants/b/new.txt")
+            .err()
+            .map(|err| err.kind()),
+        Some(ErrorKind::PermissionDenied)
+    );
+
+    assert_eq!(memory_fs.read_to_string("/tenants/b/data.txt")?, "b");
+    assert!(!memory_fs.exists("/tenants/b/new.txt")?);
+    assert!(memory_fs.exists("/tenants/a/data.txt")?);
+
+    Ok(())
+}
+
+#[test]
+fn symlinks_into_hidden() -> unifs::Result<()> {
+    let memory_fs = MemoryFs::default();
+    memory_fs.create_dir_all("/tenants/a")?;
+    memory_fs.create_dir_all("/tenants/b")?;
+    memory_fs.write("/tenants/b/secret.txt", "secret")?;
+
+    let fs = HideFs::new(&memory_fs, ["/tenants/b"]);
+
+    // Links into a hidden prefix cannot be created, with absolute or relative targets.
+    for target in ["/tenants/b", "../b", "/tenants/a/../b/secret.txt"] {
+        assert_eq!(
+            fs.symlink(target, "/tenants/a/d")
+                .err()
+                .map(|err| err.kind()),
+            Some(ErrorKind::PermissionDenied),
+            "linking to {target}"
+        );
+    }
+    assert!(!memory_fs.exists("/tenants/a/d")?);
+    fs.symlink("/tenants/a", "/tenants/a/self")?;
+
+    // Existing links are resolved when opening files and creating directories.
+    memory_fs.symlink("/tenants/b", "/tenants/a/d")?;
+    assert_eq!(
+        fs.new_openoptions()
+            .read(true)
+            .open("/tenants/a/d/secret.txt")
+            .err()
+            .map(|err| err.kind()),
+        Some(ErrorKind::NotFound)
+    );
+    assert_eq!(
+        fs.new_openoptions()
+            .write(true)
+            .create(true)
+            .open("/tenants/a/d/new.txt")
+            .err()
+            .map(|err| err.kind()),
+        Some(ErrorKind::PermissionDenied)
+    );
+    assert_eq!(
+        fs.new_dirbuilder()
+            .create("/tenants/a/d/dir")
+            .err()
+            .map(|err| err.kind()),
+        Some(ErrorKind::PermissionDenied)
+    );
+    assert!(!memory_fs.exists("/tenants/b/new.txt")?);
+    assert!(!memory_fs.exists("/tenants/b/dir")?);
+
+    Ok(())
+}
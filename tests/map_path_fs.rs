@@ -0,0 +1,63 @@
+use std::{
+    borrow::Cow,
+    path::{Path, PathBuf},
+};
+
+use unifs::{AltrootFs, MapPathFs, MemoryFs, UniDirEntry as _, UniFs as _};
+
+fn v1_to_v2(path: &Path) -> Cow<'_, Path> {
+    match path.strip_prefix("/v1") {
+        Ok(rest) => Cow::Owned(Path::new("/v2").join(rest)),
+        Err(_) => Cow::Borrowed(path),
+    }
+}
+
+#[test]
+fn general_test() -> unifs::Result<()> {
+    let memory_fs = MemoryFs::default();
+    memory_fs.create_dir_all("/v2/sub")?;
+    memory_fs.write("/v2/data.txt", b"Hello, World!")?;
+    memory_fs.write("/other.txt", b"unmapped")?;
+
+    let fs = MapPathFs::new(&memory_fs, v1_to_v2);
+
+    assert_eq!(fs.read("/v1/data.txt")?, b"Hello, World!");
+    assert_eq!(fs.read("/other.txt")?, b"unmapped");
+
+    fs.write("/v1/sub/new.txt", b"new")?;
+    assert_eq!(memory_fs.read("/v2/sub/new.txt")?, b"new");
+
+    let paths = fs
+        .read_dir("/v1")?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<unifs::Result<Vec<_>>>()?;
+    assert_eq!(
+        paths,
+        [PathBuf::from("/v1/data.txt"), PathBuf::from("/v1/sub")]
+    );
+
+    assert_eq!(fs.canonicalize("/v1/data.txt")?, Path::new("/v1/data.txt"));
+    assert_eq!(
+        fs.canonicalize("/v1/sub/../data.txt")?,
+        Path::new("/v1/data.txt")
+    );
+    assert_eq!(
+        fs.canonicalize("/v1/../other.txt")?,
+        Path::new("/other.txt")
+    );
+
+    Ok(())
+}
+
+#[test]
+fn composes_with_altroot() -> unifs::Result<()> {
+    let memory_fs = MemoryFs::default();
+    let altroot = AltrootFs::new_or_create(&memory_fs, "/root")?;
+    let fs = MapPathFs::new(altroot, v1_to_v2);
+
+    fs.create_dir("/v1")?;
+    fs.write("/v1/file.txt", b"nested")?;
+    assert_eq!(memory_fs.read("/root/v2/file.txt")?, b"nested");
+
+    Ok(())
+}
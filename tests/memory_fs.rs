@@ -1,6 +1,15 @@
-use std::{collections::HashSet, ffi::OsString};
+use std::{
+    collections::HashSet,
+    ffi::OsString,
+    io::{ErrorKind, Seek as _, SeekFrom, Write as _},
+    path::{Path, PathBuf},
+};
 
-use unifs::{MemoryFs, UniDirEntry, UniFs as _, UniMetadata};
+use unifs::{
+    FileTimes, MemoryFs, SpecialFileKind, UniDirEntry, UniFile as _, UniFileTimes as _,
+    UniFileType as _, UniFs as _, UniFsExt as _, UniMetadata, UniOpenOptions as _,
+    UniPermissions as _, WriteReport,
+};
 
 #[test]
 fn general_test() -> unifs::Result<()> {
@@ -66,3 +75,1652 @@ fn general_test() -> unifs::Result<()> {
 
     Ok(())
 }
+
+fn kind<T>(res: unifs::Result<T>) -> ErrorKind {
+    res.map(|_| ()).unwrap_err().kind()
+}
+
+#[test]
+fn error_kinds() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    fs.create_dir("/dir")?;
+    fs.create_dir("/dir/sub")?;
+    fs.write("/file.txt", b"data")?;
+
+    assert_eq!(kind(fs.read_dir("/file.txt")), ErrorKind::NotADirectory);
+    assert_eq!(kind(fs.remove_dir("/file.txt")), ErrorKind::NotADirectory);
+    assert_eq!(
+        kind(fs.remove_dir_all("/file.txt")),
+        ErrorKind::NotADirectory
+    );
+    assert_eq!(kind(fs.remove_file("/dir")), ErrorKind::IsADirectory);
+    assert_eq!(kind(fs.read("/dir")), ErrorKind::IsADirectory);
+    assert_eq!(kind(fs.open_file("/dir")), ErrorKind::IsADirectory);
+    assert_eq!(kind(fs.remove_dir("/dir")), ErrorKind::DirectoryNotEmpty);
+    assert_eq!(
+        kind(fs.rename("/file.txt", "/dir")),
+        ErrorKind::IsADirectory
+    );
+    assert_eq!(
+        kind(fs.create_dir("/file.txt/sub")),
+        ErrorKind::NotADirectory
+    );
+    assert_eq!(
+        kind(fs.write("/file.txt/sub", b"")),
+        ErrorKind::NotADirectory
+    );
+    assert_eq!(kind(fs.read("/missing")), ErrorKind::NotFound);
+    assert_eq!(kind(fs.create_dir("/dir")), ErrorKind::AlreadyExists);
+
+    // Copying onto a directory leaves it and its children in place.
+    assert_eq!(kind(fs.copy("/file.txt", "/dir")), ErrorKind::IsADirectory);
+    assert!(fs.metadata("/dir")?.is_dir());
+    assert!(fs.exists("/dir/sub")?);
+    fs.verify()?;
+
+    Ok(())
+}
+
+#[test]
+fn write_reporting() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+
+    let report = fs.write_reporting("/file.txt", b"Hello, World!")?;
+    assert_eq!(
+        report,
+        WriteReport {
+            written: 13,
+            previous_len: None
+        }
+    );
+
+    let report = fs.write_reporting("/file.txt", b"Hello")?;
+    assert_eq!(
+        report,
+        WriteReport {
+            written: 5,
+            previous_len: Some(13)
+        }
+    );
+    assert_eq!(fs.read("/file.txt")?, b"Hello");
+
+    Ok(())
+}
+
+#[test]
+fn create_special() {
+    let fs = MemoryFs::default();
+
+    assert_eq!(
+        kind(fs.create_special("/fifo", SpecialFileKind::Fifo)),
+        ErrorKind::Unsupported
+    );
+    assert!(!fs.exists("/fifo").unwrap());
+}
+
+#[cfg(feature = "futures")]
+#[test]
+fn async_read() -> unifs::Result<()> {
+    use futures::{executor::block_on, io::AsyncReadExt as _};
+
+    let fs = MemoryFs::default();
+    fs.write("/file.txt", b"Hello, World!")?;
+
+    let mut file = fs.open_file("/file.txt")?;
+    let mut buf = Vec::new();
+    block_on(file.read_to_end(&mut buf))?;
+    assert_eq!(buf, b"Hello, World!");
+
+    Ok(())
+}
+
+#[test]
+fn copy_onto_itself() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    fs.create_dir("/dir")?;
+    fs.write("/dir/file.txt", b"Hello, World!")?;
+    let modified = fs.metadata("/dir/file.txt")?.modified().ok();
+
+    assert_eq!(fs.copy("/dir/file.txt", "/dir/file.txt")?, 13);
+    assert_eq!(fs.copy("/dir/file.txt", "/dir/../dir/./file.txt")?, 13);
+    assert_eq!(fs.read("/dir/file.txt")?, b"Hello, World!");
+    assert_eq!(fs.metadata("/dir/file.txt")?.modified().ok(), modified);
+
+    Ok(())
+}
+
+#[test]
+fn permissions_hash() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    fs.write("/file.txt", b"")?;
+
+    let writable = fs.metadata("/file.txt")?.permissions();
+    let mut readonly = writable.clone();
+    readonly.set_readonly(true);
+
+    let set = HashSet::from([writable.clone(), readonly.clone(), writable.clone()]);
+    assert_eq!(set.len(), 2);
+    assert!(set.contains(&writable));
+    assert!(set.contains(&readonly));
+    assert!(writable < readonly);
+
+    Ok(())
+}
+
+#[test]
+fn file_as_ancestor() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    fs.write("/a", b"file")?;
+
+    assert_eq!(kind(fs.metadata("/a/b")), ErrorKind::NotADirectory);
+    assert_eq!(kind(fs.metadata("/a/b/c")), ErrorKind::NotADirectory);
+    assert_eq!(kind(fs.read("/a/b")), ErrorKind::NotADirectory);
+    assert_eq!(kind(fs.open_file("/a/b")), ErrorKind::NotADirectory);
+    assert_eq!(kind(fs.metadata("/missing/b")), ErrorKind::NotFound);
+
+    Ok(())
+}
+
+#[test]
+fn read_dir_order() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    fs.create_dir("/dir")?;
+    for name in ["delta", "alpha", "Charlie", "bravo", "10", "2"] {
+        fs.write(format!("/dir/{name}"), b"")?;
+    }
+
+    let names = fs
+        .read_dir("/dir")?
+        .map(|entry| entry.map(|e| e.file_name()))
+        .collect::<unifs::Result<Vec<_>>>()?;
+    assert_eq!(
+        names,
+        ["10", "2", "Charlie", "alpha", "bravo", "delta"].map(OsString::from)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn read_dir_unicode_order() -> unifs::Result<()> {
+    let nfc = "\u{e9}.txt";
+    let nfd = "e\u{301}.txt";
+
+    let fs = MemoryFs::default();
+    fs.create_dir("/dir")?;
+    for name in [nfc, "f.txt", nfd] {
+        fs.write(format!("/dir/{name}"), b"")?;
+    }
+
+    let names = |fs: &MemoryFs| {
+        fs.read_dir("/dir")?
+            .map(|entry| entry.map(|e| e.file_name()))
+            .collect::<unifs::Result<Vec<_>>>()
+    };
+
+    // Both names are stored as written.
+    assert_eq!(names(&fs)?, [nfd, "f.txt", nfc].map(OsString::from));
+    #[cfg(feature = "unicode-normalization")]
+    {
+        fs.set_normalize_names(true);
+        assert_eq!(names(&fs)?, ["f.txt", nfd, nfc].map(OsString::from));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn file_set_permissions() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    let file = fs.create_file("/file.txt")?;
+
+    let mut perm = file.metadata()?.permissions();
+    assert!(!perm.readonly());
+    perm.set_readonly(true);
+    file.set_permissions(perm)?;
+
+    assert!(file.metadata()?.permissions().readonly());
+    assert!(fs.metadata("/file.txt")?.permissions().readonly());
+
+    Ok(())
+}
+
+#[test]
+fn readonly_file() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    fs.write("/file.txt", "data")?;
+    let mut handle = fs.new_openoptions().write(true).open("/file.txt")?;
+
+    let mut perm = fs.metadata("/file.txt")?.permissions();
+    perm.set_readonly(true);
+    fs.set_permissions("/file.txt", perm)?;
+
+    for res in [
+        fs.new_openoptions().write(true).open("/file.txt").map(drop),
+        fs.new_openoptions()
+            .append(true)
+            .open("/file.txt")
+            .map(drop),
+        fs.new_openoptions()
+            .read(true)
+            .truncate(true)
+            .open("/file.txt")
+            .map(drop),
+        fs.write("/file.txt", "changed"),
+    ] {
+        assert_eq!(
+            res.err().map(|err| err.kind()),
+            Some(ErrorKind::PermissionDenied)
+        );
+    }
+    assert_eq!(fs.read_to_string("/file.txt")?, "data");
+    fs.open_file("/file.txt")?;
+
+    // Like `std::fs`, permissions are checked when opening, so a handle opened for
+    // writing before the file became read-only can still write to it.
+    handle.write_all(b"DATA")?;
+    assert_eq!(fs.read_to_string("/file.txt")?, "DATA");
+
+    Ok(())
+}
+
+#[test]
+fn readonly_dir() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    fs.create_dir_all("/dir/sub")?;
+    fs.write("/dir/file.txt", "data")?;
+
+    let mut perm = fs.metadata("/dir")?.permissions();
+    perm.set_readonly(true);
+    fs.set_permissions("/dir", perm)?;
+
+    for res in [
+        fs.create_dir("/dir/new"),
+        fs.write("/dir/new.txt", "new"),
+        fs.remove_file("/dir/file.txt"),
+        fs.remove_dir("/dir/sub"),
+        fs.rename("/dir/file.txt", "/file.txt"),
+    ] {
+        assert_eq!(
+            res.err().map(|err| err.kind()),
+            Some(ErrorKind::PermissionDenied)
+        );
+    }
+
+    // The contents of existing files can still be changed.
+    fs.write("/dir/file.txt", "changed")?;
+    fs.create_dir("/dir/sub/new")?;
+
+    Ok(())
+}
+
+#[test]
+fn file_write_updates_modified() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    fs.write("/file.txt", b"")?;
+    let before = fs.metadata("/file.txt")?.modified().ok();
+
+    let mut file = fs.new_openoptions().write(true).open("/file.txt")?;
+    std::thread::sleep(std::time::Duration::from_millis(10));
+    file.write_all(b"Hello")?;
+    let written = fs.metadata("/file.txt")?.modified()?;
+    assert!(before.is_none_or(|before| written > before));
+
+    std::thread::sleep(std::time::Duration::from_millis(10));
+    file.set_len(2)?;
+    let truncated = fs.metadata("/file.txt")?.modified()?;
+    assert!(truncated > written);
+    assert_eq!(fs.read("/file.txt")?, b"He");
+
+    let time = std::time::SystemTime::UNIX_EPOCH;
+    file.set_times(FileTimes::default().set_modified(time))?;
+    assert_eq!(fs.metadata("/file.txt")?.modified()?, time);
+
+    Ok(())
+}
+
+#[test]
+fn text_and_bytes() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    fs.write("/a", "hi")?;
+    fs.write("/b", [0xff, 0x00])?;
+
+    assert_eq!(fs.text("/a"), "hi");
+    assert_eq!(fs.bytes("/a"), b"hi");
+    assert_eq!(fs.bytes("/b"), [0xff, 0x00]);
+
+    Ok(())
+}
+
+#[test]
+#[should_panic(expected = "failed to read '/missing'")]
+fn text_missing_file() {
+    MemoryFs::default().text("/missing");
+}
+
+#[test]
+fn equality() -> unifs::Result<()> {
+    let build = || -> unifs::Result<MemoryFs> {
+        let fs = MemoryFs::default();
+        fs.create_dir_all("/dir/sub")?;
+        fs.write("/dir/file.txt", b"Hello, World!")?;
+        fs.write("/dir/sub/other.txt", b"other")?;
+        Ok(fs)
+    };
+
+    let a = build()?;
+    let b = build()?;
+    assert_eq!(a, b);
+
+    b.write("/dir/file.txt", b"Hello, Moon!")?;
+    assert_ne!(a, b);
+
+    b.write("/dir/file.txt", b"Hello, World!")?;
+    assert_eq!(a, b);
+    b.create_dir("/dir/empty")?;
+    assert_ne!(a, b);
+
+    Ok(())
+}
+
+#[test]
+fn current_dir() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    fs.create_dir_all("/work/sub")?;
+    fs.write("/work/file.txt", b"Hello, World!")?;
+    fs.write("/file.txt", b"root")?;
+    assert_eq!(fs.current_dir(), Path::new("/"));
+    assert_eq!(fs.read("file.txt")?, b"root");
+
+    fs.set_current_dir("/work")?;
+    assert_eq!(fs.current_dir(), Path::new("/work"));
+    assert_eq!(fs.read("file.txt")?, b"Hello, World!");
+    assert_eq!(fs.read("./file.txt")?, b"Hello, World!");
+    assert_eq!(fs.read("../file.txt")?, b"root");
+    assert_eq!(fs.read("/file.txt")?, b"root");
+    assert_eq!(fs.canonicalize(".")?, Path::new("/work"));
+
+    fs.write("sub/new.txt", b"new")?;
+    assert_eq!(fs.read("/work/sub/new.txt")?, b"new");
+
+    fs.set_current_dir("sub")?;
+    assert_eq!(fs.current_dir(), Path::new("/work/sub"));
+
+    assert_eq!(kind(fs.set_current_dir("/missing")), ErrorKind::NotFound);
+    assert_eq!(
+        kind(fs.set_current_dir("/file.txt")),
+        ErrorKind::NotADirectory
+    );
+    assert_eq!(fs.current_dir(), Path::new("/work/sub"));
+
+    Ok(())
+}
+
+#[test]
+fn lock_range() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    fs.write("/file.db", [0u8; 100])?;
+    let a = fs.open_file("/file.db")?;
+    let b = fs.open_file("/file.db")?;
+
+    let guard_a = a.lock_range(0, 50, true)?;
+    let guard_b = b.lock_range(50, 50, true)?;
+    assert_eq!(kind(b.lock_range(40, 20, true)), ErrorKind::WouldBlock);
+    assert_eq!(kind(b.lock_range(10, 1, false)), ErrorKind::WouldBlock);
+
+    drop(guard_a);
+    let shared_a = a.lock_range(0, 50, false)?;
+    let shared_b = b.lock_range(10, 20, false)?;
+    assert_eq!(kind(a.lock_range(0, 0, true)), ErrorKind::WouldBlock);
+
+    drop((guard_b, shared_a, shared_b));
+    a.lock_range(0, 0, true)?;
+
+    Ok(())
+}
+
+#[test]
+fn file_locks() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    fs.write("/file.db", b"data")?;
+    let a = fs.open_file("/file.db")?;
+    let b = fs.open_file("/file.db")?;
+
+    a.try_lock_exclusive()?;
+    assert_eq!(kind(b.try_lock_exclusive()), ErrorKind::WouldBlock);
+    assert_eq!(kind(b.try_lock_shared()), ErrorKind::WouldBlock);
+    assert_eq!(kind(b.lock_range(0, 1, false)), ErrorKind::WouldBlock);
+
+    // Locking again replaces the lock of the handle, which is shared by its clones.
+    a.try_lock_shared()?;
+    b.try_lock_shared()?;
+    let clone = a.try_clone()?;
+    assert_eq!(kind(clone.try_lock_exclusive()), ErrorKind::WouldBlock);
+    b.unlock()?;
+    clone.try_lock_exclusive()?;
+
+    // The lock is released once the handle and its clones are dropped.
+    drop((a, clone));
+    b.try_lock_exclusive()?;
+
+    // A blocking lock waits until the lock is released.
+    let c = fs.open_file("/file.db")?;
+    let waiter = std::thread::spawn(move || c.lock_exclusive());
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    assert!(!waiter.is_finished());
+    drop(b);
+    waiter.join().unwrap()?;
+
+    Ok(())
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn save_to_file() -> unifs::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("fs.bin");
+
+    let fs = MemoryFs::open_or_create_from_file(&path)?;
+    assert_eq!(fs, MemoryFs::default());
+
+    fs.create_dir_all("/dir/empty")?;
+    fs.write("/dir/file.txt", b"Hello, World!")?;
+    fs.write("/root.bin", [0u8, 1, 2, 3])?;
+    fs.symlink("dir/file.txt", "/symlink")?;
+    let mut perm = fs.metadata("/root.bin")?.permissions();
+    perm.set_readonly(true);
+    fs.set_permissions("/root.bin", perm)?;
+    fs.set_current_dir("/dir")?;
+    fs.save_to_file(&path)?;
+
+    let loaded = MemoryFs::open_or_create_from_file(&path)?;
+    assert_eq!(loaded, fs);
+    assert_eq!(loaded.current_dir(), Path::new("/dir"));
+    assert_eq!(loaded.read("/dir/file.txt")?, b"Hello, World!");
+    assert_eq!(loaded.read_link("/symlink")?, Path::new("dir/file.txt"));
+    assert!(loaded.metadata("/root.bin")?.permissions().readonly());
+    assert_eq!(
+        loaded.metadata("/dir/file.txt")?.modified()?,
+        fs.metadata("/dir/file.txt")?.modified()?
+    );
+
+    // Saving again replaces the previous save without leaving a temporary file.
+    fs.remove_file("/symlink")?;
+    fs.save_to_file(&path)?;
+    assert_eq!(MemoryFs::open_or_create_from_file(&path)?, fs);
+    let names = std::fs::read_dir(dir.path())?
+        .map(|entry| entry.map(|entry| entry.file_name()))
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(names, ["fs.bin"]);
+
+    std::fs::write(&path, b"not a filesystem")?;
+    assert_eq!(
+        MemoryFs::open_or_create_from_file(&path)
+            .err()
+            .map(|err| err.kind()),
+        Some(ErrorKind::InvalidData)
+    );
+
+    Ok(())
+}
+
+#[cfg(feature = "tar")]
+#[test]
+fn tar_round_trip() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    fs.create_dir_all("/dir/empty")?;
+    fs.write("/dir/file.txt", b"Hello, World!")?;
+    fs.write("/root.bin", [0u8, 1, 2, 3])?;
+    fs.symlink("dir/file.txt", "/link")?;
+    fs.set_owner("/root.bin", Some(1000), Some(100))?;
+    let mut perm = fs.metadata("/root.bin")?.permissions();
+    perm.set_readonly(true);
+    fs.set_permissions("/root.bin", perm)?;
+
+    let loaded = MemoryFs::from_tar(fs.tar()?.as_slice())?;
+    loaded.verify()?;
+    assert!(loaded.metadata("/dir/empty")?.is_dir());
+    assert_eq!(loaded.read_dir("/dir/empty")?.count(), 0);
+    assert_eq!(loaded.read("/dir/file.txt")?, b"Hello, World!");
+    assert_eq!(loaded.read("/root.bin")?, [0, 1, 2, 3]);
+    assert_eq!(loaded.read_link("/link")?, Path::new("dir/file.txt"));
+
+    let metadata = loaded.metadata("/root.bin")?;
+    assert!(metadata.permissions().readonly());
+    assert_eq!((metadata.uid(), metadata.gid()), (Some(1000), Some(100)));
+    // Tar archives store modification times in whole seconds.
+    let modified = |fs: &MemoryFs| -> unifs::Result<u64> {
+        let modified = fs.metadata("/dir/file.txt")?.modified()?;
+        Ok(modified
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs())
+    };
+    assert_eq!(modified(&loaded)?, modified(&fs)?);
+
+    Ok(())
+}
+
+#[test]
+fn remove_dir_all_consistency() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    fs.create_dir_all("/dir/a/b")?;
+    fs.write("/dir/a/file.txt", b"Hello, World!")?;
+    fs.hard_link("/dir/a/file.txt", "/dir/a/b/link.txt")?;
+    fs.hard_link("/dir/a/b/link.txt", "/dir/link.txt")?;
+    fs.write("/keep.txt", b"keep")?;
+    fs.verify()?;
+
+    fs.remove_dir_all("/dir")?;
+    fs.verify()?;
+    assert!(!fs.exists("/dir")?);
+    assert!(!fs.exists("/dir/a/b/link.txt")?);
+    let names = fs
+        .read_dir("/")?
+        .map(|entry| entry.map(|e| e.file_name()))
+        .collect::<unifs::Result<Vec<_>>>()?;
+    assert_eq!(names, [OsString::from("keep.txt")]);
+
+    // Concurrent writers either finish before the removal or fail afterwards.
+    fs.create_dir("/dir")?;
+    std::thread::scope(|scope| {
+        for thread in 0..4 {
+            let fs = &fs;
+            scope.spawn(move || {
+                for i in 0..100 {
+                    let _ = fs.write(format!("/dir/{thread}-{i}.txt"), b"");
+                }
+            });
+        }
+        fs.remove_dir_all("/dir")
+    })?;
+    fs.verify()?;
+
+    Ok(())
+}
+
+#[test]
+fn open_direct() -> unifs::Result<()> {
+    use std::io::Read as _;
+
+    let fs = MemoryFs::default();
+    fs.write("/file.txt", b"Hello, World!")?;
+
+    let mut file = fs
+        .new_openoptions()
+        .read(true)
+        .direct(true)
+        .open("/file.txt")?;
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)?;
+    assert_eq!(contents, "Hello, World!");
+
+    Ok(())
+}
+
+#[cfg(feature = "notify")]
+#[test]
+fn notify_rename() -> unifs::Result<()> {
+    use unifs::memory_fs::FsEvent;
+
+    let fs = MemoryFs::default();
+    fs.create_dir_all("/dir/sub")?;
+    fs.write("/dir/sub/file.txt", b"contents")?;
+    fs.write("/file.txt", b"contents")?;
+
+    let events = fs.subscribe();
+
+    fs.rename("/file.txt", "/dir/moved.txt")?;
+    assert_eq!(
+        events.try_iter().collect::<Vec<_>>(),
+        [FsEvent::Renamed {
+            from: "/file.txt".into(),
+            to: "/dir/moved.txt".into(),
+        }]
+    );
+
+    fs.rename("/dir", "/renamed")?;
+    assert_eq!(
+        events.try_iter().collect::<Vec<_>>(),
+        [FsEvent::Renamed {
+            from: "/dir".into(),
+            to: "/renamed".into(),
+        }]
+    );
+
+    fs.write("/new.txt", b"")?;
+    fs.remove_dir_all("/renamed")?;
+    assert_eq!(
+        events.try_iter().collect::<Vec<_>>(),
+        [
+            FsEvent::Created("/new.txt".into()),
+            FsEvent::Removed("/renamed".into()),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn symlink_metadata_len() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    fs.create_dir("/dir")?;
+    fs.write("/dir/file.txt", b"Hello, World!")?;
+    fs.symlink("dir/file.txt", "/link.txt")?;
+
+    let metadata = fs.symlink_metadata("/link.txt")?;
+    assert!(metadata.is_symlink());
+    assert_eq!(metadata.len(), "dir/file.txt".len() as u64);
+
+    let metadata = fs.metadata("/link.txt")?;
+    assert!(metadata.is_file());
+    assert_eq!(metadata.len(), 13);
+
+    assert_eq!(fs.symlink_metadata("/dir/file.txt")?.len(), 13);
+    assert_eq!(
+        fs.symlink_metadata("/missing.txt")
+            .err()
+            .map(|err| err.kind()),
+        Some(ErrorKind::NotFound)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn walk_dir_does_not_follow_symlinks() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    fs.create_dir_all("/tree/dir")?;
+    fs.write("/tree/dir/file.txt", b"Hello, World!")?;
+    fs.symlink("dir", "/tree/link")?;
+
+    let mut symlinks = Vec::new();
+    let mut paths = Vec::new();
+    for entry in fs.walk_dir("/tree") {
+        let entry = entry?;
+        if entry.file_type()?.is_symlink() {
+            symlinks.push(entry.path());
+        }
+        paths.push(entry.path());
+    }
+    assert_eq!(symlinks, [PathBuf::from("/tree/link")]);
+    assert!(!paths.contains(&PathBuf::from("/tree/link/file.txt")));
+
+    // Only the last component is not followed.
+    assert!(fs.symlink_metadata("/tree/link")?.is_symlink());
+    assert!(fs.symlink_metadata("/tree/link/file.txt")?.is_file());
+    assert!(fs.metadata("/tree/link")?.is_dir());
+
+    Ok(())
+}
+
+#[test]
+fn case_insensitive() -> unifs::Result<()> {
+    let fs = MemoryFs::new_case_insensitive();
+    fs.create_dir("/Dir")?;
+    fs.write("/Dir/Foo.txt", "Hello")?;
+
+    assert_eq!(fs.read_to_string("/dir/foo.TXT")?, "Hello");
+    assert_eq!(fs.canonicalize("/DIR/FOO.txt")?, Path::new("/Dir/Foo.txt"));
+    assert_eq!(
+        fs.create_new_file("/DIR/FOO.TXT")
+            .err()
+            .map(|err| err.kind()),
+        Some(ErrorKind::AlreadyExists)
+    );
+    fs.write("/dir/foo.txt", "World")?;
+    fs.write("/dir/Bar.txt", "")?;
+
+    let names = |fs: &MemoryFs| -> unifs::Result<Vec<OsString>> {
+        fs.read_dir("/dir")?
+            .map(|entry| Ok(entry?.file_name()))
+            .collect()
+    };
+    assert_eq!(names(&fs)?, ["Bar.txt", "Foo.txt"]);
+    assert_eq!(fs.read_to_string("/Dir/Foo.txt")?, "World");
+
+    // Renaming can change the case of a name.
+    fs.rename("/dir/foo.txt", "/dir/FOO.txt")?;
+    assert_eq!(names(&fs)?, ["Bar.txt", "FOO.txt"]);
+    fs.verify()?;
+
+    // The default filesystem is case-sensitive.
+    let fs = MemoryFs::default();
+    fs.write("/Foo.txt", "Hello")?;
+    assert!(!fs.exists("/foo.txt")?);
+
+    // Case sensitivity can only be changed while the filesystem is empty.
+    assert_eq!(
+        fs.set_case_insensitive(true).err().map(|err| err.kind()),
+        Some(ErrorKind::DirectoryNotEmpty)
+    );
+    let fs = MemoryFs::default();
+    fs.set_case_insensitive(true)?;
+    fs.write("/Foo.txt", "Hello")?;
+    assert!(fs.exists("/foo.txt")?);
+
+    Ok(())
+}
+
+#[test]
+fn create_dir_all_root() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    fs.create_dir_all("/")?;
+    fs.create_dir_all("/dir/..")?;
+    assert!(fs.metadata("/")?.is_dir());
+
+    Ok(())
+}
+
+#[test]
+fn create_dir_all_through_file() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    fs.write("/a", "data")?;
+
+    let err = fs.create_dir_all("/a/b/c").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotADirectory);
+    assert!(err.to_string().contains("'/a'"));
+    assert_eq!(
+        fs.create_dir_all("/a").err().map(|err| err.kind()),
+        Some(ErrorKind::AlreadyExists)
+    );
+    assert_eq!(fs.read("/a")?, b"data");
+
+    Ok(())
+}
+
+#[test]
+fn remove_dir_all_detaches_entries() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    fs.create_dir_all("/tree/a/b/c")?;
+    fs.write("/tree/a/b/file.txt", "data")?;
+    fs.create_dir_all("/sibling/sub")?;
+
+    fs.remove_dir_all("/tree")?;
+    fs.verify()?;
+    for entry in fs.read_dir("/sibling")? {
+        entry?.metadata()?;
+    }
+
+    // The root itself cannot be removed, but all of its contents are.
+    let _ = fs.remove_dir_all("/");
+    fs.verify()?;
+    assert_eq!(fs.read_dir("/")?.count(), 0);
+
+    Ok(())
+}
+
+#[test]
+fn remove_dir_all_keeps_linked_files() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    fs.create_dir_all("/dir/sub")?;
+    fs.write("/dir/sub/file.txt", b"Hello, World!")?;
+    fs.hard_link("/dir/sub/file.txt", "/outside.txt")?;
+    fs.hard_link("/dir/sub/file.txt", "/second.txt")?;
+    fs.hard_link("/dir/sub/file.txt", "/dir/inside.txt")?;
+
+    fs.remove_dir_all("/dir")?;
+    fs.verify()?;
+    assert!(!fs.exists("/dir")?);
+    assert_eq!(fs.read("/outside.txt")?, b"Hello, World!");
+    assert_eq!(fs.read("/second.txt")?, b"Hello, World!");
+
+    // The remaining links still share the data.
+    fs.write("/second.txt", b"changed")?;
+    assert_eq!(fs.read("/outside.txt")?, b"changed");
+
+    Ok(())
+}
+
+#[test]
+fn hard_links_share_data() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    fs.create_dir("/dir")?;
+    fs.write("/original.txt", "Hello")?;
+    fs.hard_link("/original.txt", "/dir/link.txt")?;
+    assert_eq!(fs.metadata("/original.txt")?.nlink(), Some(2));
+    assert!(fs.symlink_metadata("/dir/link.txt")?.is_file());
+
+    let mut file = fs.new_openoptions().append(true).open("/dir/link.txt")?;
+    file.write_all(b", World!")?;
+    drop(file);
+    assert_eq!(fs.read_to_string("/original.txt")?, "Hello, World!");
+
+    // Renaming either name keeps the link intact.
+    fs.rename("/original.txt", "/renamed.txt")?;
+    fs.write("/renamed.txt", "changed")?;
+    assert_eq!(fs.read_to_string("/dir/link.txt")?, "changed");
+
+    fs.remove_file("/renamed.txt")?;
+    assert_eq!(fs.read_to_string("/dir/link.txt")?, "changed");
+    assert_eq!(fs.metadata("/dir/link.txt")?.nlink(), Some(1));
+
+    assert_eq!(
+        fs.hard_link("/dir", "/dir_link")
+            .err()
+            .map(|err| err.kind()),
+        Some(ErrorKind::PermissionDenied)
+    );
+    fs.verify()?;
+
+    Ok(())
+}
+
+#[test]
+fn hard_links_share_attributes() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    fs.write("/a.txt", "data")?;
+    fs.hard_link("/a.txt", "/b.txt")?;
+
+    let mut perm = fs.metadata("/a.txt")?.permissions();
+    perm.set_readonly(true);
+    fs.set_permissions("/a.txt", perm)?;
+    assert!(fs.metadata("/b.txt")?.permissions().readonly());
+
+    assert_eq!(
+        fs.write("/b.txt", "changed").err().map(|err| err.kind()),
+        Some(ErrorKind::PermissionDenied)
+    );
+    assert_eq!(
+        fs.new_openoptions()
+            .write(true)
+            .open("/b.txt")
+            .err()
+            .map(|err| err.kind()),
+        Some(ErrorKind::PermissionDenied)
+    );
+    assert_eq!(fs.read_to_string("/a.txt")?, "data");
+
+    let time = std::time::SystemTime::UNIX_EPOCH;
+    fs.open_file("/b.txt")?
+        .set_times(FileTimes::default().set_modified(time))?;
+    assert_eq!(fs.metadata("/a.txt")?.modified()?, time);
+
+    Ok(())
+}
+
+#[test]
+fn copy_overwrites_in_place() -> unifs::Result<()> {
+    use std::io::{Read as _, Seek as _, SeekFrom};
+
+    let fs = MemoryFs::default();
+    fs.write("/large.bin", vec![1; 1024 * 1024])?;
+    fs.write("/dest.bin", vec![2; 1024 * 1024])?;
+
+    let mut dest = fs.open_file("/dest.bin")?;
+    assert_eq!(fs.copy("/large.bin", "/dest.bin")?, 1024 * 1024);
+
+    // The destination buffer is reused, so open handles see the copied contents.
+    let mut contents = Vec::new();
+    dest.seek(SeekFrom::Start(0))?;
+    dest.read_to_end(&mut contents)?;
+    assert_eq!(contents, vec![1; 1024 * 1024]);
+    assert_eq!(fs.read("/dest.bin")?, vec![1; 1024 * 1024]);
+
+    assert_eq!(fs.copy("/large.bin", "/new.bin")?, 1024 * 1024);
+    assert_eq!(fs.read("/new.bin")?, vec![1; 1024 * 1024]);
+
+    Ok(())
+}
+
+#[test]
+fn remove_batch() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    fs.create_dir_all("/dir/sub")?;
+    fs.write("/dir/sub/file.txt", b"")?;
+    fs.write("/a.txt", b"")?;
+    fs.write("/b.txt", b"")?;
+    fs.write("/keep.txt", b"")?;
+
+    let results = fs.remove_batch([
+        PathBuf::from("/a.txt"),
+        PathBuf::from("/missing.txt"),
+        PathBuf::from("/dir"),
+        PathBuf::from("/b.txt"),
+    ]);
+    assert_eq!(
+        results
+            .iter()
+            .map(|res| res.as_ref().err().map(|err| err.kind()))
+            .collect::<Vec<_>>(),
+        [None, Some(ErrorKind::NotFound), None, None]
+    );
+
+    fs.verify()?;
+    let names = fs
+        .read_dir("/")?
+        .map(|entry| entry.map(|e| e.file_name()))
+        .collect::<unifs::Result<Vec<_>>>()?;
+    assert_eq!(names, [OsString::from("keep.txt")]);
+
+    Ok(())
+}
+
+#[test]
+fn symlinks() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    fs.create_dir_all("/dir/sub")?;
+    fs.write("/dir/sub/file.txt", "Hello, World!")?;
+    fs.symlink("sub/file.txt", "/dir/relative.txt")?;
+    fs.symlink("/dir/sub", "/absolute")?;
+    fs.symlink("../../dir/./sub/../relative.txt", "/dir/sub/chained.txt")?;
+
+    assert_eq!(
+        fs.read_link("/dir/relative.txt")?,
+        Path::new("sub/file.txt")
+    );
+    assert_eq!(fs.read_to_string("/dir/relative.txt")?, "Hello, World!");
+    assert_eq!(fs.read_to_string("/absolute/file.txt")?, "Hello, World!");
+    assert_eq!(fs.read_to_string("/dir/sub/chained.txt")?, "Hello, World!");
+    assert_eq!(
+        fs.canonicalize("/absolute/chained.txt")?,
+        Path::new("/dir/sub/file.txt")
+    );
+    assert!(fs.metadata("/absolute")?.is_dir());
+    assert!(fs.symlink_metadata("/absolute")?.is_symlink());
+    assert_eq!(
+        fs.read_link("/dir/sub/file.txt")
+            .err()
+            .map(|err| err.kind()),
+        Some(ErrorKind::InvalidInput)
+    );
+    assert_eq!(
+        fs.symlink("/dir", "/absolute").err().map(|err| err.kind()),
+        Some(ErrorKind::AlreadyExists)
+    );
+
+    // Removing a link leaves its target untouched.
+    fs.remove_file("/absolute")?;
+    assert!(!fs.exists("/absolute")?);
+    assert!(fs.exists("/dir/sub/file.txt")?);
+
+    Ok(())
+}
+
+#[test]
+fn parent_of_symlink_target() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    fs.create_dir_all("/a/b/c")?;
+    fs.write("/a/b/file.txt", "in b")?;
+    fs.write("/file.txt", "in root")?;
+    fs.symlink("/a/b/c", "/link")?;
+
+    // `..` after a link leads to the parent of its target, like on POSIX systems.
+    assert_eq!(fs.canonicalize("/link/..")?, Path::new("/a/b"));
+    assert_eq!(fs.read_to_string("/link/../file.txt")?, "in b");
+    assert_eq!(fs.canonicalize("/link/../../..")?, Path::new("/"));
+
+    fs.set_current_dir("/link")?;
+    assert_eq!(fs.read_to_string("../file.txt")?, "in b");
+
+    Ok(())
+}
+
+#[test]
+fn broken_symlink() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    fs.symlink("/missing.txt", "/broken.txt")?;
+
+    assert!(!fs.exists("/broken.txt")?);
+    assert!(fs.symlink_metadata("/broken.txt")?.is_symlink());
+    assert_eq!(
+        fs.read("/broken.txt").err().map(|err| err.kind()),
+        Some(ErrorKind::NotFound)
+    );
+    assert_eq!(
+        fs.create_new_file("/broken.txt")
+            .err()
+            .map(|err| err.kind()),
+        Some(ErrorKind::AlreadyExists)
+    );
+
+    // Writing through the link creates its target.
+    fs.write("/broken.txt", "now there")?;
+    assert_eq!(fs.read_to_string("/missing.txt")?, "now there");
+    assert!(fs.exists("/broken.txt")?);
+
+    Ok(())
+}
+
+#[test]
+fn symlink_loop() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    fs.symlink("/b", "/a")?;
+    fs.symlink("/a", "/b")?;
+    fs.symlink("self", "/self")?;
+
+    for path in ["/a", "/self", "/a/file.txt"] {
+        let err = fs.read(path).unwrap_err();
+        // `ErrorKind::FilesystemLoop` cannot be named on stable Rust.
+        #[cfg(any(unix, windows))]
+        assert_eq!(format!("{:?}", err.kind()), "FilesystemLoop");
+        #[cfg(not(any(unix, windows)))]
+        let _ = err;
+    }
+    assert!(fs.symlink_metadata("/a")?.is_symlink());
+    assert_eq!(fs.read_link("/self")?, Path::new("self"));
+
+    Ok(())
+}
+
+#[test]
+fn set_owner() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    fs.write("/file.txt", "data")?;
+    let metadata = fs.metadata("/file.txt")?;
+    assert!(metadata.uid().is_some());
+    assert!(metadata.gid().is_some());
+
+    fs.set_owner("/file.txt", Some(1000), Some(100))?;
+    fs.set_owner("/file.txt", Some(1001), None)?;
+    let metadata = fs.metadata("/file.txt")?;
+    assert_eq!(metadata.uid(), Some(1001));
+    assert_eq!(metadata.gid(), Some(100));
+
+    assert_eq!(
+        fs.set_owner("/missing.txt", Some(0), Some(0))
+            .err()
+            .map(|err| err.kind()),
+        Some(ErrorKind::NotFound)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn rename_updates_parents() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    fs.create_dir_all("/from/sub")?;
+    fs.create_dir("/to")?;
+    fs.write("/from/file.txt", "data")?;
+
+    fs.rename("/from/file.txt", "/to/moved.txt")?;
+    fs.rename("/from/sub", "/from/renamed")?;
+    fs.verify()?;
+
+    let names = |path: &str| -> unifs::Result<Vec<OsString>> {
+        fs.read_dir(path)?
+            .map(|entry| entry.map(|entry| entry.file_name()))
+            .collect()
+    };
+    assert_eq!(names("/from")?, [OsString::from("renamed")]);
+    assert_eq!(names("/to")?, [OsString::from("moved.txt")]);
+
+    Ok(())
+}
+
+#[test]
+fn rename_is_silent() {
+    const CHILD_VAR: &str = "UNIFS_RENAME_IS_SILENT_CHILD";
+
+    // Stderr of a test cannot be captured from within, so the test runs itself again.
+    if std::env::var_os(CHILD_VAR).is_some() {
+        let fs = MemoryFs::default();
+        fs.create_dir("/from").unwrap();
+        fs.create_dir("/to").unwrap();
+        fs.write("/from/file.txt", "data").unwrap();
+        fs.rename("/from/file.txt", "/to/file.txt").unwrap();
+        return;
+    }
+
+    let output = std::process::Command::new(std::env::current_exe().unwrap())
+        .args(["--exact", "rename_is_silent", "--nocapture", "--quiet"])
+        .env(CHILD_VAR, "1")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "");
+}
+
+#[test]
+fn read_at_concurrently() -> unifs::Result<()> {
+    use std::io::{Read as _, Seek as _, SeekFrom};
+
+    let fs = MemoryFs::default();
+    let contents = (0..=255).cycle().take(64 * 1024).collect::<Vec<u8>>();
+    fs.write("/file.bin", &contents)?;
+
+    let mut file = fs.open_file("/file.bin")?;
+    file.seek(SeekFrom::Start(3))?;
+
+    std::thread::scope(|scope| {
+        let readers = [0, 1000].map(|start| {
+            let file = &file;
+            let contents = &contents;
+            scope.spawn(move || {
+                let mut buf = [0; 100];
+                for offset in (start..contents.len()).step_by(2000) {
+                    let read = file.read_at(&mut buf, offset as u64).unwrap();
+                    let expected = &contents[offset..contents.len().min(offset + 100)];
+                    assert_eq!(&buf[..read], expected);
+                }
+            })
+        });
+        for reader in readers {
+            reader.join().unwrap();
+        }
+    });
+
+    let mut buf = [0; 4];
+    assert_eq!(file.read_at(&mut buf, contents.len() as u64 - 2)?, 2);
+    assert_eq!(file.read_at(&mut buf, contents.len() as u64 + 10)?, 0);
+
+    // The position of the handle is left untouched.
+    file.read_exact(&mut buf)?;
+    assert_eq!(buf, [3, 4, 5, 6]);
+
+    Ok(())
+}
+
+#[test]
+fn read_updates_accessed() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    fs.create_dir("/dir")?;
+    fs.write("/dir/file.txt", b"Hello, World!")?;
+    assert_eq!(
+        fs.metadata("/dir/file.txt")?
+            .accessed()
+            .err()
+            .map(|err| err.kind()),
+        Some(ErrorKind::NotFound)
+    );
+
+    fs.read("/dir/file.txt")?;
+    let accessed = fs.metadata("/dir/file.txt")?.accessed()?;
+    std::thread::sleep(std::time::Duration::from_millis(10));
+    fs.read_to_string("/dir/file.txt")?;
+    assert!(fs.metadata("/dir/file.txt")?.accessed()? > accessed);
+    fs.read_dir("/dir")?;
+    assert!(fs.metadata("/dir")?.accessed().is_ok());
+
+    fs.set_track_atime(false);
+    fs.write("/other.txt", b"")?;
+    fs.read("/other.txt")?;
+    assert!(fs.metadata("/other.txt")?.accessed().is_err());
+
+    Ok(())
+}
+
+#[test]
+fn illegal_path_characters() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    fs.create_dir("/dir")?;
+
+    assert_eq!(
+        kind(fs.write("/dir/nul\0.txt", b"")),
+        ErrorKind::InvalidInput
+    );
+    assert_eq!(kind(fs.create_dir("/nul\0dir")), ErrorKind::InvalidInput);
+    assert_eq!(kind(fs.exists("/dir/\0")), ErrorKind::InvalidInput);
+    assert_eq!(
+        kind(fs.symlink("/dir/\0", "/link")),
+        ErrorKind::InvalidInput
+    );
+    assert_eq!(fs.read_dir("/dir")?.count(), 0);
+
+    fs.write("/dir/a?b.txt", b"")?;
+    fs.set_illegal_chars(['?', '*']);
+    assert_eq!(kind(fs.write("/dir/c*d.txt", b"")), ErrorKind::InvalidInput);
+    assert_eq!(kind(fs.read("/dir/a?b.txt")), ErrorKind::InvalidInput);
+    fs.write("/dir/plain.txt", b"")?;
+
+    Ok(())
+}
+
+#[test]
+fn capacity() -> unifs::Result<()> {
+    let fs = MemoryFs::with_capacity(10);
+    assert_eq!(fs.remaining_capacity(), Some(10));
+
+    fs.write("/a.txt", b"12345")?;
+    let mut file = fs.create_file("/b.txt")?;
+    file.write_all(b"1234")?;
+    fs.hard_link("/a.txt", "/link.txt")?;
+    assert_eq!(fs.remaining_capacity(), Some(1));
+
+    assert_eq!(kind(file.write(b"56")), ErrorKind::QuotaExceeded);
+    assert_eq!(kind(file.set_len(20)), ErrorKind::QuotaExceeded);
+    assert_eq!(kind(fs.write("/c.txt", b"12")), ErrorKind::QuotaExceeded);
+    assert_eq!(kind(fs.copy("/a.txt", "/d.txt")), ErrorKind::QuotaExceeded);
+    assert!(!fs.exists("/d.txt")?);
+    assert_eq!(
+        kind(fs.write("/a.txt", b"1234567")),
+        ErrorKind::QuotaExceeded
+    );
+    assert_eq!(fs.read("/a.txt")?, b"12345");
+    assert_eq!(fs.remaining_capacity(), Some(1));
+    assert_eq!(fs.read("/b.txt")?, b"1234");
+    file.write_all(b"5")?;
+    assert_eq!(fs.remaining_capacity(), Some(0));
+
+    // The data is only freed with its last link.
+    fs.remove_file("/a.txt")?;
+    assert_eq!(fs.remaining_capacity(), Some(0));
+    fs.remove_file("/link.txt")?;
+    assert_eq!(fs.remaining_capacity(), Some(5));
+
+    file.set_len(2)?;
+    assert_eq!(fs.remaining_capacity(), Some(8));
+    fs.copy("/b.txt", "/d.txt")?;
+    fs.write("/c.txt", b"123456")?;
+    assert_eq!(fs.remaining_capacity(), Some(0));
+
+    fs.set_capacity(None);
+    assert_eq!(fs.remaining_capacity(), None);
+    fs.write("/e.txt", [0; 100])?;
+
+    Ok(())
+}
+
+#[test]
+fn open_handle_survives_rename() -> unifs::Result<()> {
+    use std::io::{Read as _, Seek as _, SeekFrom};
+
+    let fs = MemoryFs::default();
+    fs.create_dir("/dir")?;
+    fs.write("/a.txt", b"Hello")?;
+    let mut file = fs.new_openoptions().read(true).write(true).open("/a.txt")?;
+
+    fs.rename("/a.txt", "/dir/b.txt")?;
+    file.seek(SeekFrom::End(0))?;
+    file.write_all(b", World!")?;
+    assert_eq!(fs.read_to_string("/dir/b.txt")?, "Hello, World!");
+    assert_eq!(file.metadata()?.len(), 13);
+
+    fs.write("/dir/b.txt", b"Hi")?;
+    assert_eq!(file.metadata()?.len(), 2);
+    let mut contents = String::new();
+    file.seek(SeekFrom::Start(0))?;
+    file.read_to_string(&mut contents)?;
+    assert_eq!(contents, "Hi");
+
+    Ok(())
+}
+
+#[test]
+fn clear() -> unifs::Result<()> {
+    use std::io::Read as _;
+
+    let fs = MemoryFs::with_capacity(100);
+    fs.create_dir_all("/dir/sub")?;
+    fs.write("/dir/sub/file.txt", b"Hello, World!")?;
+    fs.write("/file.txt", b"Hello")?;
+    fs.set_current_dir("/dir")?;
+    let mut file = fs.open_file("/file.txt")?;
+
+    fs.clear();
+    fs.verify()?;
+    assert_eq!(fs.read_dir("/")?.count(), 0);
+    assert_eq!(fs.current_dir(), Path::new("/"));
+    assert_eq!(fs.remaining_capacity(), Some(100));
+
+    // Open files are detached from the filesystem.
+    fs.write("/file.txt", b"new")?;
+    let mut contents = Vec::new();
+    file.read_to_end(&mut contents)?;
+    assert_eq!(contents, b"Hello");
+
+    Ok(())
+}
+
+#[test]
+fn snapshot_restore() -> unifs::Result<()> {
+    use std::io::Read as _;
+
+    let fs = MemoryFs::with_capacity(100);
+    fs.create_dir("/dir")?;
+    fs.write("/dir/file.txt", b"original")?;
+    fs.hard_link("/dir/file.txt", "/link.txt")?;
+    let snapshot = fs.snapshot();
+
+    let mut open = fs.open_file("/dir/file.txt")?;
+    fs.write("/dir/file.txt", b"changed")?;
+    fs.write("/new.txt", b"new")?;
+    fs.remove_file("/link.txt")?;
+
+    fs.restore(snapshot.clone());
+    fs.verify()?;
+    assert_eq!(fs.read("/dir/file.txt")?, b"original");
+    assert_eq!(fs.read("/link.txt")?, b"original");
+    assert!(!fs.exists("/new.txt")?);
+    assert_eq!(fs.remaining_capacity(), Some(92));
+
+    // Open handles keep their data, which is not shared with the restored files.
+    let mut contents = Vec::new();
+    open.read_to_end(&mut contents)?;
+    assert_eq!(contents, b"changed");
+
+    // The restored links share their data, but not with the snapshot.
+    fs.write("/link.txt", b"again")?;
+    assert_eq!(fs.read("/dir/file.txt")?, b"again");
+    assert_eq!(fs.metadata("/link.txt")?.nlink(), Some(2));
+
+    fs.restore(snapshot);
+    assert_eq!(fs.read("/dir/file.txt")?, b"original");
+    assert_eq!(fs.remaining_capacity(), Some(92));
+
+    Ok(())
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+    let fs = MemoryFs::default();
+    fs.create_dir_all("/dir/sub")?;
+    fs.write("/dir/file.txt", b"Hello, World!")?;
+    fs.write("/dir/sub/empty", b"")?;
+    fs.hard_link("/dir/file.txt", "/link.txt")?;
+    fs.symlink("dir/sub", "/symlink")?;
+    let mut perm = fs.metadata("/dir/sub/empty")?.permissions();
+    perm.set_readonly(true);
+    fs.set_permissions("/dir/sub/empty", perm)?;
+    fs.set_current_dir("/dir")?;
+
+    let json = serde_json::to_string(&fs)?;
+    let copy = serde_json::from_str::<MemoryFs>(&json)?;
+    copy.verify()?;
+    assert_eq!(copy, fs);
+    assert_eq!(copy.current_dir(), Path::new("/dir"));
+
+    let names = copy
+        .read_dir("/dir")?
+        .map(|entry| entry.map(|e| e.file_name()))
+        .collect::<unifs::Result<Vec<_>>>()?;
+    assert_eq!(names, ["file.txt", "sub"].map(OsString::from));
+    assert_eq!(copy.read("/dir/file.txt")?, b"Hello, World!");
+    assert_eq!(copy.read_link("/symlink")?, Path::new("dir/sub"));
+    assert!(copy.metadata("/symlink/empty")?.permissions().readonly());
+    assert_eq!(
+        copy.metadata("/dir/file.txt")?.modified()?,
+        fs.metadata("/dir/file.txt")?.modified()?
+    );
+
+    // Hard links still share their contents.
+    copy.write("/link.txt", b"changed")?;
+    assert_eq!(copy.read("/dir/file.txt")?, b"changed");
+    assert_eq!(fs.read("/dir/file.txt")?, b"Hello, World!");
+
+    Ok(())
+}
+
+#[test]
+fn directory_len_counts_entries() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    fs.create_dir_all("/full/sub")?;
+    fs.create_dir("/empty")?;
+    fs.write("/full/file.txt", b"Hello, World!")?;
+
+    assert_eq!(fs.metadata("/empty")?.len(), 0);
+    assert_eq!(fs.metadata("/full")?.len(), 2);
+    assert_eq!(fs.metadata("/full/sub")?.len(), 0);
+
+    fs.remove_file("/full/file.txt")?;
+    fs.remove_dir("/full/sub")?;
+    assert_eq!(fs.metadata("/full")?.len(), 0);
+
+    Ok(())
+}
+
+#[test]
+fn clone_shares_and_deep_clone_copies() -> unifs::Result<()> {
+    let fs = MemoryFs::with_capacity(1024);
+    fs.write("/file.txt", b"Hello, World!")?;
+    fs.hard_link("/file.txt", "/link.txt")?;
+
+    let handle = fs.clone();
+    handle.write("/file.txt", b"changed")?;
+    handle.create_dir("/dir")?;
+    assert_eq!(fs.read("/file.txt")?, b"changed");
+    assert!(fs.exists("/dir")?);
+
+    let copy = fs.deep_clone();
+    assert_eq!(copy, fs);
+    assert_eq!(copy.remaining_capacity(), fs.remaining_capacity());
+    copy.write("/link.txt", b"copy")?;
+    assert_eq!(copy.read("/file.txt")?, b"copy");
+    assert_eq!(fs.read("/file.txt")?, b"changed");
+    fs.remove_dir("/dir")?;
+    assert!(copy.exists("/dir")?);
+
+    Ok(())
+}
+
+#[test]
+fn path_length_limits() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    let name = "a".repeat(MemoryFs::DEFAULT_MAX_NAME_LEN);
+    fs.write(format!("/{name}"), b"")?;
+    assert_eq!(
+        kind(fs.write(format!("/{name}b"), b"")),
+        ErrorKind::InvalidFilename
+    );
+
+    let dir = format!("/{}", "c".repeat(250)).repeat(16);
+    fs.create_dir_all(&dir)?;
+    let remaining = MemoryFs::DEFAULT_MAX_PATH_LEN - dir.len() - 1;
+    fs.write(format!("{dir}/{}", "b".repeat(remaining)), b"")?;
+    assert_eq!(
+        kind(fs.write(format!("{dir}/{}", "b".repeat(remaining + 1)), b"")),
+        ErrorKind::InvalidFilename
+    );
+
+    fs.set_max_name_len(Some(4));
+    fs.set_max_path_len(None);
+    assert_eq!(kind(fs.create_dir("/names")), ErrorKind::InvalidFilename);
+    fs.create_dir("/dir")?;
+
+    Ok(())
+}
+
+#[test]
+fn total_size_and_entry_count() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    assert_eq!((fs.total_size(), fs.entry_count()), (0, 1));
+
+    fs.create_dir_all("/dir/sub")?;
+    fs.write("/dir/file.txt", b"Hello, World!")?;
+    fs.write("/dir/sub/data.bin", [0u8; 100])?;
+    fs.hard_link("/dir/file.txt", "/link.txt")?;
+    fs.symlink("/dir", "/symlink")?;
+    assert_eq!(fs.total_size(), 113);
+    assert_eq!(fs.entry_count(), 7);
+
+    fs.remove_file("/dir/file.txt")?;
+    assert_eq!(fs.total_size(), 113);
+    fs.remove_file("/link.txt")?;
+    assert_eq!(fs.total_size(), 100);
+    assert_eq!(fs.entry_count(), 5);
+
+    Ok(())
+}
+
+#[test]
+fn manual_clock() -> unifs::Result<()> {
+    use std::time::{Duration, UNIX_EPOCH};
+    use unifs::memory_fs::ManualClock;
+
+    let start = UNIX_EPOCH + Duration::from_secs(1_000_000);
+    let clock = ManualClock::new(start);
+    let fs = MemoryFs::with_clock(clock.clone());
+    assert_eq!(fs.metadata("/")?.created()?, start);
+
+    fs.write("/file.txt", "Hello")?;
+    let metadata = fs.metadata("/file.txt")?;
+    assert_eq!(metadata.created()?, start);
+    assert_eq!(metadata.modified()?, start);
+
+    clock.advance(Duration::from_secs(10));
+    fs.write("/file.txt", "World")?;
+    let metadata = fs.metadata("/file.txt")?;
+    assert_eq!(metadata.created()?, start);
+    assert_eq!(metadata.modified()?, start + Duration::from_secs(10));
+
+    clock.advance(Duration::from_secs(5));
+    fs.read("/file.txt")?;
+    assert_eq!(
+        fs.metadata("/file.txt")?.accessed()?,
+        start + Duration::from_secs(15)
+    );
+
+    clock.set(start + Duration::from_secs(60));
+    let mut file = fs.new_openoptions().append(true).open("/file.txt")?;
+    file.write_all(b"!")?;
+    drop(file);
+    fs.create_dir("/dir")?;
+    assert_eq!(
+        fs.metadata("/file.txt")?.modified()?,
+        start + Duration::from_secs(60)
+    );
+    assert_eq!(
+        fs.metadata("/dir")?.created()?,
+        start + Duration::from_secs(60)
+    );
+
+    let later = start + Duration::from_secs(3600);
+    fs.set_clock(ManualClock::new(later));
+    fs.write("/other.txt", "")?;
+    assert_eq!(fs.metadata("/other.txt")?.created()?, later);
+    assert_eq!(
+        fs.metadata("/dir")?.created()?,
+        start + Duration::from_secs(60)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn compact_after_churn() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    fs.create_dir("/keep")?;
+    fs.write("/keep/file.txt", b"Hello, World!")?;
+
+    for cycle in 0..4 {
+        let dir = format!("/churn-{cycle}");
+        fs.create_dir(&dir)?;
+        for i in 0..256 {
+            fs.write(format!("{dir}/file-{i}.txt"), b"data")?;
+        }
+        fs.remove_dir_all(&dir)?;
+    }
+    let capacity = fs.entry_capacity();
+    assert!(capacity >= 256);
+
+    fs.compact();
+    assert!(fs.entry_capacity() < capacity);
+    fs.verify()?;
+    assert_eq!(fs.entry_count(), 3);
+    assert_eq!(fs.read("/keep/file.txt")?, b"Hello, World!");
+    fs.write("/keep/new.txt", b"")?;
+
+    Ok(())
+}
+
+#[test]
+fn sparse_files() -> unifs::Result<()> {
+    const LEN: u64 = 10 * 1024 * 1024 * 1024;
+
+    let fs = MemoryFs::default();
+    let mut file = fs.create_file("/sparse.img")?;
+    file.set_len(LEN)?;
+    assert_eq!(fs.metadata("/sparse.img")?.len(), LEN);
+
+    file.write_all(b"head")?;
+    file.seek(SeekFrom::End(-4))?;
+    file.write_all(b"tail")?;
+    assert_eq!(fs.metadata("/sparse.img")?.len(), LEN);
+
+    let mut buf = [0xff; 8];
+    assert_eq!(file.read_at(&mut buf, 0)?, 8);
+    assert_eq!(&buf, b"head\0\0\0\0");
+    assert_eq!(file.read_at(&mut buf, LEN / 2)?, 8);
+    assert_eq!(buf, [0; 8]);
+    assert_eq!(file.read_at(&mut buf, LEN - 6)?, 6);
+    assert_eq!(&buf[..6], b"\0\0tail");
+
+    // Truncating and extending again does not bring back the removed bytes.
+    file.set_len(2)?;
+    file.set_len(6)?;
+    let mut contents = Vec::new();
+    file.seek(SeekFrom::Start(0))?;
+    std::io::Read::read_to_end(&mut file, &mut contents)?;
+    assert_eq!(contents, b"he\0\0\0\0");
+
+    Ok(())
+}
+
+#[test]
+fn write_past_maximum_file_size() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    let mut file = fs.create_file("/huge.img")?;
+    file.set_len(u64::MAX)?;
+
+    assert_eq!(
+        file.seek(SeekFrom::End(1)).err().map(|err| err.kind()),
+        Some(ErrorKind::InvalidInput)
+    );
+    assert_eq!(file.seek(SeekFrom::End(-1))?, u64::MAX - 1);
+    file.write_all(b"x")?;
+    assert_eq!(
+        file.seek(SeekFrom::Current(1)).err().map(|err| err.kind()),
+        Some(ErrorKind::InvalidInput)
+    );
+    assert_eq!(
+        file.write(b"y").err().map(|err| err.kind()),
+        Some(ErrorKind::FileTooLarge)
+    );
+
+    let mut buf = [0; 2];
+    assert_eq!(file.read_at(&mut buf, u64::MAX - 2)?, 2);
+    assert_eq!(&buf, b"\0x");
+    assert_eq!(fs.metadata("/huge.img")?.len(), u64::MAX);
+
+    Ok(())
+}
+
+#[test]
+fn rename_replaces_existing_file() -> unifs::Result<()> {
+    let names = |fs: &MemoryFs, path: &str| -> unifs::Result<Vec<OsString>> {
+        fs.read_dir(path)?
+            .map(|entry| entry.map(|e| e.file_name()))
+            .collect()
+    };
+
+    let fs = MemoryFs::default();
+    fs.create_dir_all("/a")?;
+    fs.create_dir_all("/b")?;
+    fs.write("/a/old.txt", b"old contents")?;
+    fs.write("/a/new.txt", b"new")?;
+
+    // Overwrite within the same directory.
+    fs.rename("/a/new.txt", "/a/old.txt")?;
+    assert_eq!(names(&fs, "/a")?, [OsString::from("old.txt")]);
+    assert_eq!(fs.read("/a/old.txt")?, b"new");
+    assert_eq!(fs.total_size(), 3);
+
+    // Overwrite across directories.
+    fs.write("/b/target.txt", b"target")?;
+    fs.rename("/a/old.txt", "/b/target.txt")?;
+    assert!(names(&fs, "/a")?.is_empty());
+    assert_eq!(names(&fs, "/b")?, [OsString::from("target.txt")]);
+    assert_eq!(fs.read("/b/target.txt")?, b"new");
+    assert_eq!(fs.total_size(), 3);
+    fs.verify()?;
+
+    // Renaming onto itself or another link to the same file does nothing.
+    fs.rename("/b/target.txt", "/b/target.txt")?;
+    fs.hard_link("/b/target.txt", "/b/link.txt")?;
+    fs.rename("/b/target.txt", "/b/link.txt")?;
+    assert_eq!(
+        names(&fs, "/b")?,
+        [OsString::from("link.txt"), OsString::from("target.txt")]
+    );
+
+    assert_eq!(
+        kind(fs.rename("/a", "/b/link.txt")),
+        ErrorKind::NotADirectory
+    );
+    assert_eq!(kind(fs.rename("/a", "/a/sub")), ErrorKind::InvalidInput);
+    fs.verify()?;
+
+    Ok(())
+}
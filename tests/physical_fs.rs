@@ -0,0 +1,57 @@
+#[cfg(unix)]
+#[test]
+fn create_special_fifo() -> unifs::Result<()> {
+    use std::os::unix::fs::FileTypeExt as _;
+
+    use unifs::{PhysicalFs, SpecialFileKind, UniFs as _};
+
+    let temp_dir = tempfile::tempdir()?;
+    let path = temp_dir.path().join("fifo");
+
+    PhysicalFs.create_special(&path, SpecialFileKind::Fifo)?;
+    assert!(PhysicalFs.metadata(&path)?.file_type().is_fifo());
+
+    let err = PhysicalFs
+        .create_special(temp_dir.path().join("device"), SpecialFileKind::BlockDevice)
+        .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::Unsupported);
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn open_direct() -> unifs::Result<()> {
+    use std::io::{ErrorKind, Read as _};
+
+    use unifs::{PhysicalFs, UniFs as _, UniOpenOptions as _};
+
+    // Temporary directories in `/tmp` are often on tmpfs, which rejects direct I/O.
+    let temp_dir = tempfile::tempdir_in(env!("CARGO_TARGET_TMPDIR"))?;
+    let path = temp_dir.path().join("file.bin");
+    PhysicalFs.write(&path, b"Hello, World!")?;
+
+    match PhysicalFs
+        .new_openoptions()
+        .read(true)
+        .direct(true)
+        .open(&path)
+    {
+        Ok(_) => {}
+        // The filesystem does not support direct I/O.
+        Err(err) if err.kind() == ErrorKind::InvalidInput => {}
+        Err(err) => return Err(err),
+    }
+
+    let mut contents = String::new();
+    PhysicalFs
+        .new_openoptions()
+        .read(true)
+        .direct(true)
+        .direct(false)
+        .open(&path)?
+        .read_to_string(&mut contents)?;
+    assert_eq!(contents, "Hello, World!");
+
+    Ok(())
+}
@@ -1,6 +1,8 @@
-use std::{collections::HashSet, ffi::OsString};
+use std::{collections::HashSet, ffi::OsString, path::Path};
 
-use unifs::{MemoryFs, StackedFs, UniDirEntry as _, UniFs as _, UniMetadata as _};
+use unifs::{
+    stacked_fs::RenameKind, MemoryFs, StackedFs, UniDirEntry as _, UniFs as _, UniMetadata as _,
+};
 
 #[test]
 fn general_test() -> unifs::Result<()> {
@@ -139,3 +141,105 @@ fn general_test() -> unifs::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn mount_point() -> unifs::Result<()> {
+    let base = MemoryFs::default();
+    let overlay = MemoryFs::default();
+    overlay.create_dir("/dir")?;
+
+    let fs = StackedFs::new(&base, &overlay, "/stacked");
+
+    for path in ["/stacked", "/stacked/"] {
+        assert_eq!(fs.canonicalize(path)?, Path::new("/stacked"));
+        assert!(fs.exists(path)?);
+        assert!(fs.metadata(path)?.is_dir());
+    }
+
+    assert_eq!(fs.canonicalize("/stacked/dir")?, Path::new("/stacked/dir"));
+    assert_eq!(fs.canonicalize("/stacked/dir/")?, Path::new("/stacked/dir"));
+
+    Ok(())
+}
+
+#[test]
+fn merged_read_dir() -> unifs::Result<()> {
+    let base = MemoryFs::default();
+    let overlay = MemoryFs::default();
+    base.create_dir_all("/stacked/dir")?;
+    for name in ["a", "c", "d", "f"] {
+        base.write(format!("/stacked/dir/{name}"), b"base")?;
+    }
+    overlay.create_dir("/dir")?;
+    for name in ["b", "c", "e", "f", "g"] {
+        overlay.write(format!("/dir/{name}"), b"overlay!")?;
+    }
+    base.create_dir("/stacked/base_only")?;
+    base.write("/stacked/base_only/file", b"base")?;
+
+    let fs = StackedFs::new(&base, &overlay, "/stacked");
+
+    let mut entries = fs.read_dir("/stacked/dir")?;
+    let first = entries.next().unwrap()?;
+    assert_eq!(first.file_name(), "a");
+    assert_eq!(first.metadata()?.len(), 4);
+
+    let rest = entries
+        .map(|entry| {
+            let entry = entry?;
+            Ok((entry.file_name(), entry.metadata()?.len()))
+        })
+        .collect::<unifs::Result<Vec<_>>>()?;
+    assert_eq!(
+        rest,
+        [
+            (OsString::from("b"), 8),
+            (OsString::from("c"), 8),
+            (OsString::from("d"), 4),
+            (OsString::from("e"), 8),
+            (OsString::from("f"), 8),
+            (OsString::from("g"), 8),
+        ]
+    );
+
+    // Directories only existing in one of the file systems are listed as well.
+    assert_eq!(fs.read_dir("/stacked/base_only")?.count(), 1);
+    assert!(fs.read_dir("/stacked/missing").is_err());
+
+    Ok(())
+}
+
+#[test]
+fn rename_checked() -> unifs::Result<()> {
+    let base = MemoryFs::default();
+    let overlay = MemoryFs::default();
+    let fs = StackedFs::new(&base, &overlay, "/stacked");
+    fs.write("/base.txt", "base")?;
+    fs.write("/stacked/overlay.txt", "overlay")?;
+
+    assert_eq!(
+        fs.rename_checked("/base.txt", "/moved.txt")?,
+        RenameKind::Atomic
+    );
+    assert_eq!(
+        fs.rename_checked("/stacked/overlay.txt", "/stacked/moved.txt")?,
+        RenameKind::Atomic
+    );
+    assert_eq!(overlay.read_to_string("/moved.txt")?, "overlay");
+
+    assert_eq!(
+        fs.rename_checked("/moved.txt", "/stacked/from-base.txt")?,
+        RenameKind::CopyDelete
+    );
+    assert_eq!(overlay.read_to_string("/from-base.txt")?, "base");
+    assert!(!base.exists("/moved.txt")?);
+
+    assert_eq!(
+        fs.rename_checked("/stacked/moved.txt", "/from-overlay.txt")?,
+        RenameKind::CopyDelete
+    );
+    assert_eq!(base.read_to_string("/from-overlay.txt")?, "overlay");
+    assert!(!overlay.exists("/moved.txt")?);
+
+    Ok(())
+}
@@ -0,0 +1,59 @@
+use std::{io::ErrorKind, time::Duration};
+
+use unifs::{MemoryFs, TimeoutFs, UniFs};
+
+mod common;
+
+use common::RecordingFs;
+
+#[test]
+fn timeout() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    fs.write("/file.txt", b"Hello, World!")?;
+
+    let fs = TimeoutFs::new(
+        RecordingFs::with_read_delay(fs, Duration::from_millis(500)),
+        Duration::from_millis(50),
+    );
+
+    assert!(fs.exists("/file.txt")?);
+    assert_eq!(
+        fs.read("/file.txt").unwrap_err().kind(),
+        ErrorKind::TimedOut
+    );
+
+    let mut fs = fs;
+    fs.set_timeout(Duration::from_secs(5));
+    assert_eq!(fs.read("/file.txt")?, b"Hello, World!");
+
+    Ok(())
+}
+
+#[test]
+fn bounded_workers() -> unifs::Result<()> {
+    let fs = MemoryFs::default();
+    fs.write("/file.txt", b"Hello, World!")?;
+
+    let mut fs = TimeoutFs::with_workers(
+        RecordingFs::with_read_delay(fs, Duration::from_millis(300)),
+        Duration::from_millis(50),
+        1,
+    );
+
+    // The only worker is busy with the abandoned read, so the write times out before
+    // it is started and is never applied.
+    assert_eq!(
+        fs.read("/file.txt").unwrap_err().kind(),
+        ErrorKind::TimedOut
+    );
+    assert_eq!(
+        fs.write("/new.txt", b"new").unwrap_err().kind(),
+        ErrorKind::TimedOut
+    );
+
+    fs.set_timeout(Duration::from_secs(5));
+    assert!(!fs.exists("/new.txt")?);
+    assert_eq!(fs.read("/file.txt")?, b"Hello, World!");
+
+    Ok(())
+}
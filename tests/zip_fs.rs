@@ -0,0 +1,164 @@
+#![cfg(feature = "zip")]
+
+use std::{
+    ffi::OsString,
+    io::{Cursor, ErrorKind, Read, Seek, SeekFrom},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use unifs::{
+    FallbackFs, MemoryFs, UniDirEntry as _, UniFs as _, UniMetadata as _, UniOpenOptions as _,
+    ZipFs,
+};
+
+/// A reader that counts the bytes read from it.
+struct CountingReader {
+    inner: Cursor<Vec<u8>>,
+    read: Arc<AtomicU64>,
+}
+
+impl Read for CountingReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.read.fetch_add(read as u64, Ordering::Relaxed);
+        Ok(read)
+    }
+}
+
+impl Seek for CountingReader {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        self.inner.seek(pos)
+    }
+}
+
+/// Returns `len` bytes that do not compress well.
+fn noise(len: usize) -> Vec<u8> {
+    let mut state = 0x2545_f491_u32;
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as u8
+        })
+        .collect()
+}
+
+#[test]
+fn general_test() -> unifs::Result<()> {
+    let memory_fs = MemoryFs::default();
+    memory_fs.create_dir_all("/dir/sub")?;
+    memory_fs.write("/dir/data.txt", "Hello, World!")?;
+    memory_fs.write("/large.bin", noise(256 * 1024))?;
+    let zip = memory_fs.zip()?;
+    let zip_len = zip.len() as u64;
+
+    let read = Arc::new(AtomicU64::new(0));
+    let fs = ZipFs::new(CountingReader {
+        inner: Cursor::new(zip),
+        read: read.clone(),
+    })?;
+
+    assert_eq!(fs.read_to_string("/dir/data.txt")?, "Hello, World!");
+    // Neither indexing the archive nor reading a small file decompresses the large one.
+    assert!(read.load(Ordering::Relaxed) < zip_len / 2);
+
+    assert!(fs.exists("/dir/sub")?);
+    assert!(!fs.exists("/missing.txt")?);
+    assert!(fs.metadata("/dir")?.is_dir());
+    assert_eq!(fs.metadata("/large.bin")?.len(), 256 * 1024);
+    assert_eq!(
+        fs.read("/missing.txt").err().map(|err| err.kind()),
+        Some(ErrorKind::NotFound)
+    );
+
+    let names = fs
+        .read_dir("/dir")?
+        .map(|entry| entry.map(|entry| entry.file_name()))
+        .collect::<unifs::Result<Vec<_>>>()?;
+    assert_eq!(names, [OsString::from("data.txt"), OsString::from("sub")]);
+
+    let mut file = fs.open_file("/dir/data.txt")?;
+    file.seek(SeekFrom::Start(7))?;
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)?;
+    assert_eq!(contents, "World!");
+
+    Ok(())
+}
+
+#[test]
+fn mutations_fail() -> unifs::Result<()> {
+    let memory_fs = MemoryFs::default();
+    memory_fs.write("/data.txt", "Hello, World!")?;
+    let fs = ZipFs::new(Cursor::new(memory_fs.zip()?))?;
+
+    for res in [
+        fs.write("/data.txt", "changed"),
+        fs.write("/new.txt", "new"),
+        fs.create_dir("/dir"),
+        fs.create_dir_all("/dir/sub"),
+        fs.remove_file("/data.txt"),
+        fs.rename("/data.txt", "/renamed.txt"),
+    ] {
+        assert_eq!(
+            res.err().map(|err| err.kind()),
+            Some(ErrorKind::ReadOnlyFilesystem)
+        );
+    }
+    assert_eq!(
+        fs.new_openoptions()
+            .write(true)
+            .open("/data.txt")
+            .err()
+            .map(|err| err.kind()),
+        Some(ErrorKind::ReadOnlyFilesystem)
+    );
+    assert_eq!(fs.read_to_string("/data.txt")?, "Hello, World!");
+
+    Ok(())
+}
+
+#[test]
+fn wrong_declared_size() -> unifs::Result<()> {
+    let memory_fs = MemoryFs::default();
+    memory_fs.write("/data.txt", "Hello, World!")?;
+    let mut zip = memory_fs.zip()?;
+
+    // Claims a size of almost 2 GiB in both the local and the central header.
+    for (signature, offset) in [(b"PK\x03\x04", 22), (b"PK\x01\x02", 24)] {
+        let header = zip
+            .windows(4)
+            .position(|window| window == signature)
+            .unwrap();
+        zip[header + offset..header + offset + 4].copy_from_slice(&0x7fff_ffff_u32.to_le_bytes());
+    }
+
+    let fs = ZipFs::new(Cursor::new(zip))?;
+    assert_eq!(fs.metadata("/data.txt")?.len(), 0x7fff_ffff);
+    assert_eq!(
+        fs.read("/data.txt").err().map(|err| err.kind()),
+        Some(ErrorKind::InvalidData)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn overlay_edits() -> unifs::Result<()> {
+    let memory_fs = MemoryFs::default();
+    memory_fs.write("/base.txt", "base")?;
+    memory_fs.write("/edited.txt", "original")?;
+    let zip_fs = ZipFs::new(Cursor::new(memory_fs.zip()?))?;
+
+    let fs = FallbackFs::new(MemoryFs::default(), zip_fs);
+    fs.write("/edited.txt", "edited")?;
+
+    assert_eq!(fs.read_to_string("/base.txt")?, "base");
+    assert_eq!(fs.read_to_string("/edited.txt")?, "edited");
+
+    Ok(())
+}